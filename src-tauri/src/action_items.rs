@@ -0,0 +1,365 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+
+/// Whether an action item still needs doing
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionStatus {
+    #[default]
+    Open,
+    Done,
+}
+
+/// A task someone took on in a meeting
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(from = "StoredActionItem")]
+pub struct ActionItem {
+    pub text: String,
+    /// One of the transcript's speakers
+    pub assignee: Option<String>,
+    pub due: Option<NaiveDate>,
+    pub status: ActionStatus,
+    /// Transcript segments the item was taken from
+    pub segment_ids: Vec<String>,
+}
+
+/// An action item as saved: summaries written before action items had owners
+/// and due dates hold plain strings
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum StoredActionItem {
+    Text(String),
+    Item {
+        text: String,
+        #[serde(default)]
+        assignee: Option<String>,
+        #[serde(default)]
+        due: Option<NaiveDate>,
+        #[serde(default)]
+        status: ActionStatus,
+        #[serde(default)]
+        segment_ids: Vec<String>,
+    },
+}
+
+impl From<StoredActionItem> for ActionItem {
+    fn from(stored: StoredActionItem) -> Self {
+        match stored {
+            StoredActionItem::Text(text) => ActionItem::new(text),
+            StoredActionItem::Item {
+                text,
+                assignee,
+                due,
+                status,
+                segment_ids,
+            } => ActionItem {
+                text,
+                assignee,
+                due,
+                status,
+                segment_ids,
+            },
+        }
+    }
+}
+
+impl ActionItem {
+    /// An open item nobody has been given yet
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            assignee: None,
+            due: None,
+            status: ActionStatus::Open,
+            segment_ids: Vec::new(),
+        }
+    }
+}
+
+/// The speaker `name` refers to: an exact match ignoring case, otherwise the only
+/// speaker whose first name it is ("Dana" for "Dana Smith")
+pub fn match_speaker(name: &str, speakers: &[String]) -> Option<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+    if let Some(speaker) = speakers.iter().find(|s| s.eq_ignore_ascii_case(name)) {
+        return Some(speaker.clone());
+    }
+    let mut by_first_name = speakers.iter().filter(|s| {
+        s.split_whitespace()
+            .next()
+            .is_some_and(|first| first.eq_ignore_ascii_case(name))
+    });
+    match (by_first_name.next(), by_first_name.next()) {
+        (Some(speaker), None) => Some(speaker.clone()),
+        _ => None,
+    }
+}
+
+/// "fri" or "friday", not "fried"
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    if word.len() < 3 {
+        return None;
+    }
+    [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+    .into_iter()
+    .find(|&weekday| full_name(weekday).starts_with(word))
+}
+
+fn full_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    if word.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|m| m.starts_with(word))
+        .map(|i| i as u32 + 1)
+}
+
+/// "3", "3rd", "21st"
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn parse_count(word: &str) -> Option<i64> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        "two" => Some(2),
+        "three" => Some(3),
+        "four" => Some(4),
+        "five" => Some(5),
+        "six" => Some(6),
+        "seven" => Some(7),
+        "eight" => Some(8),
+        "nine" => Some(9),
+        "ten" => Some(10),
+        _ => word.parse().ok(),
+    }
+}
+
+fn last_day_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    NaiveDate::from_ymd_opt(next_year, next_month, 1)?.pred_opt()
+}
+
+/// The first `weekday` after `date`
+fn next_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+    date + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+/// `weekday` in the week (Monday to Sunday) of `date`
+fn weekday_in_week(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+        + Duration::days(weekday.num_days_from_monday() as i64)
+}
+
+/// The date a deadline as said in a meeting ("by Friday", "end of next week",
+/// "March 3rd", "in two weeks", "2024-03-08") falls on, counting from the day of
+/// the meeting. `None` if it isn't a deadline this understands.
+pub fn parse_due(phrase: &str, meeting_date: NaiveDate) -> Option<NaiveDate> {
+    let phrase = phrase.trim().to_lowercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&phrase, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let words: Vec<&str> = phrase
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut words: &[&str] = &words;
+    while let Some((first, rest)) = words.split_first() {
+        match *first {
+            "by" | "before" | "on" | "due" | "until" | "the" => words = rest,
+            _ => break,
+        }
+    }
+
+    match words {
+        ["today"] | ["tonight"] | ["eod"] | ["end", "of", "day"] | ["end", "of", "today"] => {
+            Some(meeting_date)
+        }
+        ["tomorrow"] => meeting_date.succ_opt(),
+        ["end", "of", "week"]
+        | ["end", "of", "the", "week"]
+        | ["end", "of", "this", "week"]
+        | ["this", "week"]
+        | ["eow"] => {
+            let friday = weekday_in_week(meeting_date, Weekday::Fri);
+            Some(if friday < meeting_date {
+                friday + Duration::weeks(1)
+            } else {
+                friday
+            })
+        }
+        ["next", "week"] | ["end", "of", "next", "week"] => {
+            Some(weekday_in_week(meeting_date, Weekday::Fri) + Duration::weeks(1))
+        }
+        ["end", "of", "month"]
+        | ["end", "of", "the", "month"]
+        | ["end", "of", "this", "month"]
+        | ["this", "month"] => last_day_of_month(meeting_date.year(), meeting_date.month()),
+        ["next", "month"] | ["end", "of", "next", "month"] => {
+            let first = last_day_of_month(meeting_date.year(), meeting_date.month())?.succ_opt()?;
+            last_day_of_month(first.year(), first.month())
+        }
+        ["in", count, unit] => {
+            let count = parse_count(count)?;
+            match unit.trim_end_matches('s') {
+                "day" => Some(meeting_date + Duration::days(count)),
+                "week" => Some(meeting_date + Duration::weeks(count)),
+                _ => None,
+            }
+        }
+        ["next", day] => parse_weekday(day)
+            .map(|weekday| weekday_in_week(meeting_date, weekday) + Duration::weeks(1)),
+        ["this", day] => parse_weekday(day).map(|weekday| {
+            let date = weekday_in_week(meeting_date, weekday);
+            if date < meeting_date {
+                next_weekday(meeting_date, weekday)
+            } else {
+                date
+            }
+        }),
+        [day] => parse_weekday(day).map(|weekday| next_weekday(meeting_date, weekday)),
+        [a, b] | [a, "of", b] => {
+            let (month, day) = match (parse_month(a), parse_day(b)) {
+                (Some(month), Some(day)) => (month, day),
+                _ => (parse_month(b)?, parse_day(a)?),
+            };
+            // a date without a year is the next one to come
+            let date = NaiveDate::from_ymd_opt(meeting_date.year(), month, day)?;
+            if date < meeting_date {
+                NaiveDate::from_ymd_opt(meeting_date.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_parse_due_relative_to_meeting() {
+        // a Wednesday
+        let meeting = date(2024, 3, 6);
+        assert_eq!(parse_due("2024-04-01", meeting), Some(date(2024, 4, 1)));
+        assert_eq!(parse_due("today", meeting), Some(meeting));
+        assert_eq!(parse_due("by tomorrow", meeting), Some(date(2024, 3, 7)));
+        assert_eq!(parse_due("Friday", meeting), Some(date(2024, 3, 8)));
+        assert_eq!(parse_due("by Fri.", meeting), Some(date(2024, 3, 8)));
+        assert_eq!(parse_due("Wednesday", meeting), Some(date(2024, 3, 13)));
+        assert_eq!(parse_due("next Friday", meeting), Some(date(2024, 3, 15)));
+        assert_eq!(parse_due("this Monday", meeting), Some(date(2024, 3, 11)));
+        assert_eq!(
+            parse_due("end of the week", meeting),
+            Some(date(2024, 3, 8))
+        );
+        assert_eq!(parse_due("next week", meeting), Some(date(2024, 3, 15)));
+        assert_eq!(parse_due("end of month", meeting), Some(date(2024, 3, 31)));
+        assert_eq!(parse_due("next month", meeting), Some(date(2024, 4, 30)));
+        assert_eq!(parse_due("in two weeks", meeting), Some(date(2024, 3, 20)));
+        assert_eq!(parse_due("in 3 days", meeting), Some(date(2024, 3, 9)));
+        assert_eq!(parse_due("March 21st", meeting), Some(date(2024, 3, 21)));
+        assert_eq!(
+            parse_due("on the 1st of April", meeting),
+            Some(date(2024, 4, 1))
+        );
+        // already past this year
+        assert_eq!(parse_due("Jan 15", meeting), Some(date(2025, 1, 15)));
+        assert_eq!(
+            parse_due("end of next month", date(2024, 12, 10)),
+            Some(date(2025, 1, 31))
+        );
+
+        assert_eq!(parse_due("soon", meeting), None);
+        assert_eq!(parse_due("fried", meeting), None);
+        assert_eq!(parse_due("February 30", meeting), None);
+        assert_eq!(parse_due("", meeting), None);
+    }
+
+    #[test]
+    fn test_match_speaker() {
+        let speakers = vec![
+            "Me".to_string(),
+            "Dana Smith".to_string(),
+            "Dan Lee".to_string(),
+        ];
+        assert_eq!(match_speaker("me", &speakers).as_deref(), Some("Me"));
+        assert_eq!(
+            match_speaker(" Dana Smith ", &speakers).as_deref(),
+            Some("Dana Smith")
+        );
+        assert_eq!(
+            match_speaker("dana", &speakers).as_deref(),
+            Some("Dana Smith")
+        );
+        assert_eq!(match_speaker("Alex", &speakers), None);
+        assert_eq!(match_speaker("", &speakers), None);
+
+        let twins = vec!["Dana Smith".to_string(), "Dana Lee".to_string()];
+        assert_eq!(match_speaker("Dana", &twins), None);
+    }
+
+    #[test]
+    fn test_plain_string_items_still_load() {
+        let items: Vec<ActionItem> = serde_json::from_str(
+            r#"["Send notes", {"text": "Book room", "assignee": "Me", "due": "2024-03-08", "status": "done", "segment_ids": ["seg_2"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(items[0], ActionItem::new("Send notes"));
+        assert_eq!(items[1].assignee.as_deref(), Some("Me"));
+        assert_eq!(items[1].due, Some(date(2024, 3, 8)));
+        assert_eq!(items[1].status, ActionStatus::Done);
+
+        let json = serde_json::to_value(&items[1]).unwrap();
+        assert_eq!(json["due"], "2024-03-08");
+        assert_eq!(json["status"], "done");
+    }
+}
@@ -0,0 +1,237 @@
+use crate::action_items::ActionItem;
+use crate::summarize::SummaryResult;
+use crate::transcribe::TranscriptionResult;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Transcript as produced by Whisper
+pub const TRANSCRIPT_FILE: &str = "transcript.json";
+/// Transcript as last saved from the editor
+pub const EDITED_TRANSCRIPT_FILE: &str = "transcript_edited.json";
+/// The current summary. Earlier ones are kept as `summary.v1.json`, `summary.v2.json`, ...
+pub const SUMMARY_FILE: &str = "summary.json";
+
+/// Everything generated from a recording, as loaded by `load_meeting_artifacts`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MeetingArtifacts {
+    /// The edited transcript if there is one, otherwise the raw one
+    pub transcript: Option<TranscriptionResult>,
+    pub transcript_edited: bool,
+    pub summary: Option<SummaryResult>,
+    /// Versions of earlier summaries that can be loaded with `load_summary_version`, oldest first
+    pub previous_summaries: Vec<u32>,
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn read_json_if_exists<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    if path.exists() {
+        read_json(path).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Save a freshly generated transcript. An edited transcript, if any, is left alone.
+pub fn save_transcript(dir: &Path, transcript: &TranscriptionResult) -> Result<PathBuf, String> {
+    let path = dir.join(TRANSCRIPT_FILE);
+    write_json(&path, transcript)?;
+    Ok(path)
+}
+
+pub fn save_edited_transcript(dir: &Path, transcript: &TranscriptionResult) -> Result<(), String> {
+    write_json(&dir.join(EDITED_TRANSCRIPT_FILE), transcript)
+}
+
+/// Load the edited transcript if the user saved one, otherwise the raw transcript.
+/// The flag is true for the edited one.
+pub fn load_transcript(dir: &Path) -> Result<Option<(TranscriptionResult, bool)>, String> {
+    if let Some(edited) = read_json_if_exists(&dir.join(EDITED_TRANSCRIPT_FILE))? {
+        return Ok(Some((edited, true)));
+    }
+    Ok(read_json_if_exists(&dir.join(TRANSCRIPT_FILE))?.map(|raw| (raw, false)))
+}
+
+fn summary_version_path(dir: &Path, version: u32) -> PathBuf {
+    dir.join(format!("summary.v{}.json", version))
+}
+
+/// Versions of the summaries that were replaced by a newer one, oldest first
+pub fn summary_versions(dir: &Path) -> Vec<u32> {
+    let mut versions: Vec<u32> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.strip_prefix("summary.v")?
+                .strip_suffix(".json")?
+                .parse()
+                .ok()
+        })
+        .collect();
+    versions.sort_unstable();
+    versions
+}
+
+/// Save a summary as the current one. The summary it replaces is kept under the
+/// next version number rather than overwritten.
+pub fn save_summary(dir: &Path, summary: &SummaryResult) -> Result<PathBuf, String> {
+    let path = dir.join(SUMMARY_FILE);
+    if path.exists() {
+        let version = summary_versions(dir).last().map_or(1, |v| v + 1);
+        fs::rename(&path, summary_version_path(dir, version))
+            .map_err(|e| format!("Failed to keep previous summary: {}", e))?;
+    }
+    write_json(&path, summary)?;
+    Ok(path)
+}
+
+pub fn load_summary(dir: &Path) -> Result<Option<SummaryResult>, String> {
+    read_json_if_exists(&dir.join(SUMMARY_FILE))
+}
+
+/// Replace the action items of the current summary with the user's edits. The
+/// summary is changed in place: an edit doesn't make a new version.
+pub fn save_action_items(
+    dir: &Path,
+    action_items: Vec<ActionItem>,
+) -> Result<SummaryResult, String> {
+    let Some(mut summary) = load_summary(dir)? else {
+        return Err("This recording has not been summarized yet".to_string());
+    };
+    let mut tidied = Vec::with_capacity(action_items.len());
+    for (i, mut item) in action_items.into_iter().enumerate() {
+        item.text = item.text.trim().to_string();
+        if item.text.is_empty() {
+            return Err(format!("Action item {} has no text", i + 1));
+        }
+        item.assignee = item
+            .assignee
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty());
+        tidied.push(item);
+    }
+    summary.action_items = tidied;
+    write_json(&dir.join(SUMMARY_FILE), &summary)?;
+    Ok(summary)
+}
+
+/// Load one of the summaries listed by `summary_versions`
+pub fn load_summary_version(dir: &Path, version: u32) -> Result<SummaryResult, String> {
+    let path = summary_version_path(dir, version);
+    if !path.exists() {
+        return Err(format!("No summary version {}", version));
+    }
+    read_json(&path)
+}
+
+pub fn load_meeting_artifacts(dir: &Path) -> Result<MeetingArtifacts, String> {
+    let (transcript, transcript_edited) = match load_transcript(dir)? {
+        Some((transcript, edited)) => (Some(transcript), edited),
+        None => (None, false),
+    };
+    Ok(MeetingArtifacts {
+        transcript,
+        transcript_edited,
+        summary: load_summary(dir)?,
+        previous_summaries: summary_versions(dir),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcribe::fixture;
+
+    fn artifacts_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn transcript(text: &str) -> TranscriptionResult {
+        fixture::transcript(&[("Me", text)])
+    }
+
+    fn summary(text: &str) -> SummaryResult {
+        SummaryResult {
+            summary: text.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_edited_transcript_is_preferred() {
+        let dir = artifacts_dir("artifacts_test_transcript");
+        let artifacts = load_meeting_artifacts(&dir).unwrap();
+        assert!(artifacts.transcript.is_none() && artifacts.summary.is_none());
+
+        save_transcript(&dir, &transcript("helo")).unwrap();
+        let (loaded, edited) = load_transcript(&dir).unwrap().unwrap();
+        assert_eq!(loaded.full_text, "helo");
+        assert!(!edited);
+
+        save_edited_transcript(&dir, &transcript("hello")).unwrap();
+        // transcribing again must not lose the user's edits
+        save_transcript(&dir, &transcript("helo again")).unwrap();
+        let artifacts = load_meeting_artifacts(&dir).unwrap();
+        assert_eq!(artifacts.transcript.unwrap().full_text, "hello");
+        assert!(artifacts.transcript_edited);
+    }
+
+    #[test]
+    fn test_regenerated_summaries_keep_previous_versions() {
+        let dir = artifacts_dir("artifacts_test_summary");
+        for text in ["first", "second", "third"] {
+            save_summary(&dir, &summary(text)).unwrap();
+        }
+
+        let artifacts = load_meeting_artifacts(&dir).unwrap();
+        assert_eq!(artifacts.summary.unwrap().summary, "third");
+        assert_eq!(artifacts.previous_summaries, vec![1, 2]);
+        assert_eq!(load_summary_version(&dir, 1).unwrap().summary, "first");
+        assert_eq!(load_summary_version(&dir, 2).unwrap().summary, "second");
+        assert!(load_summary_version(&dir, 3).is_err());
+    }
+
+    #[test]
+    fn test_action_item_edits_are_saved_in_place() {
+        let dir = artifacts_dir("artifacts_test_action_items");
+        assert!(save_action_items(&dir, vec![]).is_err());
+
+        let mut generated = summary("first");
+        generated.action_items = vec![ActionItem::new("Send notes")];
+        save_summary(&dir, &generated).unwrap();
+
+        let mut edited = ActionItem::new(" Send the notes ");
+        edited.assignee = Some("Dana".into());
+        edited.status = crate::action_items::ActionStatus::Done;
+        let mut unassigned = ActionItem::new("Book a room");
+        unassigned.assignee = Some(" ".into());
+        save_action_items(&dir, vec![edited, unassigned]).unwrap();
+
+        let artifacts = load_meeting_artifacts(&dir).unwrap();
+        let items = artifacts.summary.unwrap().action_items;
+        assert_eq!(items[0].text, "Send the notes");
+        assert_eq!(items[0].status, crate::action_items::ActionStatus::Done);
+        assert_eq!(items[1].assignee, None);
+        assert!(artifacts.previous_summaries.is_empty());
+
+        let err = save_action_items(&dir, vec![ActionItem::new("  ")]).unwrap_err();
+        assert_eq!(err, "Action item 1 has no text");
+    }
+}
@@ -1,10 +1,25 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crate::capture::{self, CaptureBackend, CaptureSource, ErrorSink, SampleSink};
+use crate::codec::{self, StorageCodec};
+use crate::config::{AppConfig, MixMode, MixSettings};
+use crate::levels::{self, AudioLevels, LevelMeter};
+use crate::loudness;
+use crate::resample::Resampler;
+use crate::session::{
+    MixInfo, PauseInterval, SessionMetadata, SessionTracks, TrackAlignment, TrackInfo, TrackTiming,
+};
+use crate::vad::VoiceDetector;
+use chrono::{DateTime, Local};
 use hound::{WavSpec, WavWriter};
 use parking_lot::Mutex;
-use screencapturekit::prelude::*;
+use rtrb::{Consumer, RingBuffer};
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
-use std::time::Instant;
-use std::{fs::File, io::BufWriter, path::PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use std::{fs::File, io::BufWriter, path::Path, path::PathBuf};
 use tauri::{AppHandle, Emitter};
 
 /// Output from a recording session - contains paths to all audio files
@@ -19,9 +34,14 @@ pub struct RecordingOutput {
 /// Stats about the current recording
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RecordingStats {
-    pub duration_secs: f64,
+    pub duration_secs: f64, // recorded time, excluding pauses
+    pub is_paused: bool,
     pub system_samples_written: u64,
     pub mic_samples_written: u64,
+    // capture blocks dropped because the writer thread fell behind
+    pub overruns: u64,
+    // waiting for voice before writing anything
+    pub is_armed: bool,
 }
 
 /// Progress during audio mixing
@@ -32,10 +52,111 @@ pub struct MixingProgress {
     pub percent: f32,
 }
 
+/// Payload of the `mixing-complete` event sent when the background mixdown finishes
+#[derive(Clone, serde::Serialize)]
+pub struct MixingComplete {
+    pub output: Option<RecordingOutput>,
+    pub error: Option<String>,
+}
+
+/// Payload of the `recording-warning` event, sent when capture runs into trouble
+/// mid-session (e.g. the mic was unplugged) but the recording carries on
+#[derive(Clone, serde::Serialize)]
+pub struct RecordingWarning {
+    pub message: String,
+}
+
+fn emit_warning(app: Option<&AppHandle>, message: String) {
+    eprintln!("Recording warning: {}", message);
+    if let Some(app) = app {
+        let _ = app.emit("recording-warning", RecordingWarning { message });
+    }
+}
+
+/// Directory for a new recording session under `base`, named by the current local time
+pub fn timestamped_dir(base: &Path) -> PathBuf {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+    base.join(timestamp)
+}
+
+/// Marker file present in a recording directory while the session is open.
+/// A directory that still has it at startup was interrupted by a crash.
+pub const IN_PROGRESS_MARKER: &str = ".recording";
+
+/// Exclusive advisory lock on a session's in-progress marker. The recorder holds it
+/// from start until the mixdown is done; recovery only touches sessions it can lock,
+/// so a session still being written by this or another process is left alone.
+pub struct SessionLock {
+    _file: File,
+}
+
+impl SessionLock {
+    /// Create the marker in `dir` and lock it
+    pub fn create(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(IN_PROGRESS_MARKER);
+        let mut file =
+            File::create(&path).map_err(|e| format!("Failed to write recording marker: {}", e))?;
+        file.try_lock()
+            .map_err(|e| format!("Failed to lock recording marker: {}", e))?;
+        let _ = write!(file, "{}", chrono::Local::now().to_rfc3339());
+        Ok(Self { _file: file })
+    }
+
+    /// Lock the existing marker in `dir`. `None` if the session is still held by a
+    /// live recorder or the marker is gone.
+    pub fn acquire(dir: &Path) -> Result<Option<Self>, String> {
+        let file = match File::open(dir.join(IN_PROGRESS_MARKER)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to open recording marker: {}", e)),
+        };
+        match file.try_lock() {
+            Ok(()) => Ok(Some(Self { _file: file })),
+            Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+            Err(std::fs::TryLockError::Error(e)) => {
+                Err(format!("Failed to lock recording marker: {}", e))
+            }
+        }
+    }
+}
+
+/// How long the writer thread waits between attempts to reopen a lost input device
+const MIC_REOPEN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Seconds of audio each capture ring holds. The writer thread drains them every
+/// few milliseconds; the rest is headroom for a stalled disk or a mic reopen.
+const RING_SECS: usize = 4;
+/// Block headers per capture ring, enough for 4 s of 1 ms callbacks
+const RING_BLOCKS: usize = 4096;
+/// How often the writer thread drains the capture rings
+const WRITER_INTERVAL: Duration = Duration::from_millis(10);
+/// How often `audio-levels` is emitted while recording
+const LEVELS_INTERVAL: Duration = Duration::from_millis(100);
+/// Audio kept from before the first voice detected in an armed session, so the
+/// first word isn't clipped
+const PRE_ROLL_SECS: f64 = 0.5;
+
+/// Smoothed drift from the session clock a track may build up before samples are
+/// inserted or dropped. Keeps callback jitter from turning into constant corrections.
+const ALIGN_TOLERANCE_SECS: f64 = 0.04;
+/// A single block this far off the clock is a real jump (dropout, reconnect) and is
+/// corrected at once instead of being smoothed
+const ALIGN_JUMP_SECS: f64 = 0.25;
+/// Weight of each block's measurement in the smoothed drift
+const DRIFT_SMOOTHING: f64 = 0.05;
+
+/// How often the WAV headers are rewritten during recording, so a crash loses
+/// at most this much audio even before recovery repairs the file
+const HEADER_CHECKPOINT_SECS: u64 = 5;
+
 /// Streaming WAV writer that writes samples directly to disk
 struct StreamingWavWriter {
     writer: WavWriter<BufWriter<File>>,
     samples_written: u64,
+    channels: u16,
+    // samples between header checkpoints
+    checkpoint_interval: u64,
+    samples_since_checkpoint: u64,
 }
 
 impl StreamingWavWriter {
@@ -52,6 +173,9 @@ impl StreamingWavWriter {
         Ok(Self {
             writer,
             samples_written: 0,
+            channels,
+            checkpoint_interval: sample_rate as u64 * channels as u64 * HEADER_CHECKPOINT_SECS,
+            samples_since_checkpoint: 0,
         })
     }
 
@@ -62,6 +186,23 @@ impl StreamingWavWriter {
                 .map_err(|e| format!("Failed to write sample: {}", e))?;
         }
         self.samples_written += samples.len() as u64;
+        self.samples_since_checkpoint += samples.len() as u64;
+
+        // hound can only write a valid header on a frame boundary
+        if self.samples_since_checkpoint >= self.checkpoint_interval
+            && self.samples_written.is_multiple_of(self.channels as u64)
+        {
+            self.checkpoint()?;
+        }
+        Ok(())
+    }
+
+    /// Patch the RIFF/data sizes for everything written so far and flush to disk
+    fn checkpoint(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|e| format!("Failed to checkpoint WAV header: {}", e))?;
+        self.samples_since_checkpoint = 0;
         Ok(())
     }
 
@@ -73,533 +214,2113 @@ impl StreamingWavWriter {
     }
 }
 
-pub struct AudioRecorder {
-    // streaming writers - opened at start, closed at stop
-    system_writer: Arc<Mutex<Option<StreamingWavWriter>>>,
-    mic_writer: Arc<Mutex<Option<StreamingWavWriter>>>,
-    // small buffer for mic resampling (holds ~0.5 sec)
-    mic_buffer: Arc<Mutex<Vec<f32>>>,
-    // tracking
-    is_recording: Arc<Mutex<bool>>,
-    start_time: Arc<Mutex<Option<Instant>>>,
-    recording_dir: Arc<Mutex<Option<PathBuf>>>,
-    // audio config
-    sample_rate: u32,
-    mic_sample_rate: Arc<Mutex<u32>>,
-    // stream handles
-    sc_stream: Arc<Mutex<Option<SCStream>>>,
-    mic_stream: Option<cpal::Stream>,
-    // sample counts for stats
-    system_samples_written: Arc<Mutex<u64>>,
-    mic_samples_written: Arc<Mutex<u64>>,
-}
-
-struct SystemAudioHandler {
-    writer: Arc<Mutex<Option<StreamingWavWriter>>>,
-    samples_written: Arc<Mutex<u64>>,
-}
-
-impl SCStreamOutputTrait for SystemAudioHandler {
-    fn did_output_sample_buffer(&self, sample_buffer: CMSampleBuffer, of_type: SCStreamOutputType) {
-        if of_type == SCStreamOutputType::Audio {
-            if let Some(audio_buffer_list) = sample_buffer.audio_buffer_list() {
-                for audio_buffer in audio_buffer_list.iter() {
-                    let data = audio_buffer.data();
-                    if !data.is_empty() {
-                        let samples: Vec<f32> = data
-                            .chunks_exact(4)
-                            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                            .collect();
-                        // write directly to disk
-                        if let Some(ref mut writer) = *self.writer.lock() {
-                            if let Err(e) = writer.write_samples(&samples) {
-                                eprintln!("Failed to write system audio samples: {}", e);
-                            } else {
-                                *self.samples_written.lock() += samples.len() as u64;
-                            }
-                        }
-                    }
-                }
-            }
+/// Monotonic clock shared by every track of a session. Positions are seconds of
+/// recorded audio: time since the session started minus pauses.
+#[derive(Debug, Clone, Copy)]
+struct SessionClock {
+    epoch: Instant,
+    paused_total: Duration,
+    paused_since: Option<Instant>,
+}
+
+impl SessionClock {
+    fn starting_at(epoch: Instant) -> Self {
+        Self {
+            epoch,
+            paused_total: Duration::ZERO,
+            paused_since: None,
         }
     }
-}
 
-/// Simple linear interpolation resampling
-fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate || samples.is_empty() {
-        return samples.to_vec();
+    fn position_at(&self, at: Instant) -> f64 {
+        let current_pause = self
+            .paused_since
+            .map_or(Duration::ZERO, |since| at.saturating_duration_since(since));
+        at.saturating_duration_since(self.epoch)
+            .saturating_sub(self.paused_total + current_pause)
+            .as_secs_f64()
+    }
+
+    fn pause(&mut self, at: Instant) {
+        self.paused_since = Some(at);
     }
 
-    let ratio = from_rate as f64 / to_rate as f64;
-    let new_len = ((samples.len() as f64) / ratio).ceil() as usize;
-    let mut result = Vec::with_capacity(new_len);
+    fn resume(&mut self, at: Instant) {
+        if let Some(since) = self.paused_since.take() {
+            self.paused_total += at.saturating_duration_since(since);
+        }
+    }
+}
 
-    for i in 0..new_len {
-        let src_idx = i as f64 * ratio;
-        let idx_floor = src_idx.floor() as usize;
-        let idx_ceil = (idx_floor + 1).min(samples.len() - 1);
-        let frac = src_idx - idx_floor as f64;
+/// One output file fed by a capture source. Maps the source's channels to the
+/// file's layout and resamples to the recorder rate before streaming to disk.
+struct Track {
+    writer: StreamingWavWriter,
+    source_rate: u32,
+    source_channels: u16,
+    output_rate: u32,
+    channels: u16,
+    // None when the source already runs at the output rate
+    resampler: Option<Resampler>,
+    // session clock alignment, in output frames
+    drift: f64,
+    start_secs: Option<f64>,
+    padded_frames: u64,
+    dropped_frames: u64,
+}
 
-        let sample = samples[idx_floor] * (1.0 - frac as f32) + samples[idx_ceil] * frac as f32;
-        result.push(sample);
+/// Describe the source feeding a track for `session.json`
+fn track_info(source: &dyn CaptureSource, sample_rate: u32, channels: u16) -> TrackInfo {
+    TrackInfo {
+        device: source.name(),
+        source_sample_rate: source.sample_rate(),
+        source_channels: source.channels(),
+        sample_rate,
+        channels,
+        samples: 0,
     }
+}
 
-    result
+fn track_resampler(source_rate: u32, output_rate: u32, channels: u16) -> Option<Resampler> {
+    (source_rate != output_rate).then(|| Resampler::new(source_rate, output_rate, channels))
 }
 
-impl AudioRecorder {
-    pub fn new() -> Self {
-        Self {
-            system_writer: Arc::new(Mutex::new(None)),
-            mic_writer: Arc::new(Mutex::new(None)),
-            mic_buffer: Arc::new(Mutex::new(Vec::with_capacity(48000))), // ~1 sec buffer
-            is_recording: Arc::new(Mutex::new(false)),
-            start_time: Arc::new(Mutex::new(None)),
-            recording_dir: Arc::new(Mutex::new(None)),
-            sample_rate: 48000,
-            mic_sample_rate: Arc::new(Mutex::new(48000)),
-            sc_stream: Arc::new(Mutex::new(None)),
-            mic_stream: None,
-            system_samples_written: Arc::new(Mutex::new(0)),
-            mic_samples_written: Arc::new(Mutex::new(0)),
-        }
+impl Track {
+    fn new(
+        path: &PathBuf,
+        source: &dyn CaptureSource,
+        output_rate: u32,
+        channels: u16,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            writer: StreamingWavWriter::new(path, channels, output_rate)?,
+            source_rate: source.sample_rate(),
+            source_channels: source.channels(),
+            output_rate,
+            channels,
+            resampler: track_resampler(source.sample_rate(), output_rate, channels),
+            drift: 0.0,
+            start_secs: None,
+            padded_frames: 0,
+            dropped_frames: 0,
+        })
     }
 
-    /// Start recording to the given directory
-    pub fn start_recording(&mut self, recording_dir: &PathBuf) -> Result<(), String> {
-        if *self.is_recording.lock() {
-            return Err("Already recording".to_string());
+    /// Add a block captured at `position` (seconds on the session clock), or
+    /// straight after the previous block if the source has no timing
+    fn push(&mut self, samples: &[f32], position: Option<f64>) -> Result<(), String> {
+        let skip = match position {
+            Some(position) => {
+                self.align(position, samples.len() / self.source_channels as usize)?
+            }
+            None => 0,
+        };
+        let samples = &samples[skip * self.source_channels as usize..];
+
+        let mapped = map_channels(samples, self.source_channels, self.channels);
+        match self.resampler.as_mut() {
+            Some(resampler) => self.writer.write_samples(&resampler.process(&mapped)),
+            None => self.writer.write_samples(&mapped),
         }
+    }
 
-        // Create recording directory
-        std::fs::create_dir_all(recording_dir)
-            .map_err(|e| format!("Failed to create recording directory: {}", e))?;
+    /// Compare where the track is with where the clock says a block of `frames`
+    /// starts. Pads silence if the track fell behind; if it ran ahead, returns how
+    /// many source frames to drop from the front of the block.
+    fn align(&mut self, position: f64, frames: usize) -> Result<usize, String> {
+        let rate = self.output_rate as f64;
+        let ratio = rate / self.source_rate as f64;
+        let measured = self.position_frames() - position * rate;
+
+        // the first block and sudden jumps are real offsets; anything else is mostly
+        // jitter in when callbacks fire, so only act on the trend
+        let drift = if self.start_secs.is_none() || measured.abs() > ALIGN_JUMP_SECS * rate {
+            self.start_secs.get_or_insert(position);
+            measured
+        } else {
+            self.drift += DRIFT_SMOOTHING * (measured - self.drift);
+            if self.drift.abs() <= ALIGN_TOLERANCE_SECS * rate {
+                return Ok(0);
+            }
+            self.drift
+        };
 
-        // Open streaming WAV writers
-        let system_file = recording_dir.join("system.wav");
-        let mic_file = recording_dir.join("mic.wav");
+        if drift < 0.0 {
+            let frames = (-drift).round() as u64;
+            self.write_silence(frames)?;
+            self.padded_frames += frames;
+            self.drift = drift + frames as f64;
+            Ok(0)
+        } else {
+            let skip = ((drift / ratio).round() as usize).min(frames);
+            let dropped = skip as f64 * ratio;
+            self.dropped_frames += dropped.round() as u64;
+            self.drift = drift - dropped;
+            Ok(skip)
+        }
+    }
 
-        let system_writer = StreamingWavWriter::new(&system_file, 2, self.sample_rate)?;
-        let mic_writer = StreamingWavWriter::new(&mic_file, 1, self.sample_rate)?;
+    fn timing(&self) -> TrackTiming {
+        let rate = self.output_rate as f64;
+        TrackTiming {
+            start_secs: self.start_secs,
+            padded_secs: self.padded_frames as f64 / rate,
+            dropped_secs: self.dropped_frames as f64 / rate,
+        }
+    }
 
-        *self.system_writer.lock() = Some(system_writer);
-        *self.mic_writer.lock() = Some(mic_writer);
-        *self.recording_dir.lock() = Some(recording_dir.clone());
+    /// Write out the resampler's lookahead, ending the current stretch of audio.
+    /// A fresh resampler picks up whatever comes next.
+    fn flush(&mut self) -> Result<(), String> {
+        if let Some(resampler) = self.resampler.as_mut() {
+            let tail = resampler.finish();
+            self.resampler = track_resampler(self.source_rate, self.output_rate, self.channels);
+            self.writer.write_samples(&tail)?;
+        }
+        Ok(())
+    }
 
-        // Reset counters and buffers
-        self.mic_buffer.lock().clear();
-        *self.system_samples_written.lock() = 0;
-        *self.mic_samples_written.lock() = 0;
+    fn samples_written(&self) -> u64 {
+        self.writer.samples_written
+    }
 
-        // Start system audio capture via ScreenCaptureKit
-        self.start_system_audio_capture()?;
+    /// Frames on disk at the output rate (excludes the resampler's lookahead)
+    fn frames_written(&self) -> u64 {
+        self.writer.samples_written / self.channels as u64
+    }
 
-        // Start microphone capture via cpal
-        self.start_mic_capture()?;
+    /// How many output frames the track has received, including those the
+    /// resampler still holds back
+    fn position_frames(&self) -> f64 {
+        self.frames_written() as f64
+            + self
+                .resampler
+                .as_ref()
+                .map_or(0.0, |r| r.buffered_output_frames())
+    }
 
-        *self.start_time.lock() = Some(Instant::now());
-        *self.is_recording.lock() = true;
+    /// Append `frames` of silence at the output rate, e.g. to cover a device gap
+    fn write_silence(&mut self, frames: u64) -> Result<(), String> {
+        self.flush()?;
+        let block = vec![0.0; self.output_rate as usize * self.channels as usize];
+        let mut remaining = frames * self.channels as u64;
+        while remaining > 0 {
+            let len = remaining.min(block.len() as u64) as usize;
+            self.writer.write_samples(&block[..len])?;
+            remaining -= len as u64;
+        }
         Ok(())
     }
 
-    /// Get stats about the current recording
-    pub fn get_stats(&self) -> Option<RecordingStats> {
-        let start = self.start_time.lock();
-        start.as_ref().map(|t| RecordingStats {
-            duration_secs: t.elapsed().as_secs_f64(),
-            system_samples_written: *self.system_samples_written.lock(),
-            mic_samples_written: *self.mic_samples_written.lock(),
-        })
+    /// Switch to a replacement source that may run at a different rate or layout
+    fn set_source(&mut self, source: &dyn CaptureSource) -> Result<(), String> {
+        self.flush()?;
+        self.source_rate = source.sample_rate();
+        self.source_channels = source.channels();
+        self.resampler = track_resampler(self.source_rate, self.output_rate, self.channels);
+        Ok(())
     }
 
-    fn start_system_audio_capture(&mut self) -> Result<(), String> {
-        let content = SCShareableContent::get()
-            .map_err(|e| format!("Failed to get shareable content: {:?}", e))?;
+    fn finalize(mut self) -> Result<u64, String> {
+        self.flush()?;
+        self.writer.finalize()
+    }
+}
 
-        let displays = content.displays();
-        let display = displays.first().ok_or("No display found")?;
+/// Header for one capture callback's worth of samples in a capture ring
+struct RingBlock {
+    len: usize,
+    captured_at: Option<Instant>,
+}
 
-        let filter = SCContentFilter::create()
-            .with_display(display)
-            .with_excluding_windows(&[])
-            .build();
+/// Consumer half of a capture ring, drained by the writer thread
+struct RingReader {
+    samples: Consumer<f32>,
+    blocks: Consumer<RingBlock>,
+    // contiguous copy of a block that wraps around the end of the ring
+    scratch: Vec<f32>,
+}
 
-        let config = SCStreamConfiguration::new()
-            .with_captures_audio(true)
-            .with_excludes_current_process_audio(false)
-            .with_sample_rate(self.sample_rate as i32)
-            .with_channel_count(2);
+impl RingReader {
+    /// Hand every block captured so far to `f`, with its capture time
+    fn drain(&mut self, label: &str, mut f: impl FnMut(&[f32], Option<Instant>)) {
+        while let Ok(block) = self.blocks.pop() {
+            // the sink commits the samples before their header, so they are all there
+            let Ok(chunk) = self.samples.read_chunk(block.len) else {
+                eprintln!("Capture ring for {} lost its samples", label);
+                return;
+            };
+            let samples = match chunk.as_slices() {
+                (first, []) => first,
+                (first, second) => {
+                    self.scratch.clear();
+                    self.scratch.extend_from_slice(first);
+                    self.scratch.extend_from_slice(second);
+                    &self.scratch
+                }
+            };
+            f(samples, block.captured_at);
+            chunk.commit_all();
+        }
+    }
+}
 
-        let handler = SystemAudioHandler {
-            writer: self.system_writer.clone(),
-            samples_written: self.system_samples_written.clone(),
+/// Build a sink that copies each capture callback's samples into a new lock-free
+/// ring buffer, and the reader that drains it. The sink never locks, allocates or
+/// touches the disk: samples that arrive while the recorder is paused are skipped,
+/// and a block that doesn't fit in the ring is dropped and counted in `overruns`.
+fn ring_sink(
+    source: &dyn CaptureSource,
+    paused: &Arc<AtomicBool>,
+    overruns: &Arc<AtomicU64>,
+) -> (SampleSink, RingReader) {
+    let capacity = RING_SECS * source.sample_rate() as usize * source.channels().max(1) as usize;
+    let (mut samples_tx, samples_rx) = RingBuffer::new(capacity);
+    let (mut blocks_tx, blocks_rx) = RingBuffer::new(RING_BLOCKS);
+    let paused = paused.clone();
+    let overruns = overruns.clone();
+
+    let sink: SampleSink = Box::new(move |samples: &[f32], captured_at: Option<Instant>| {
+        if paused.load(Ordering::Relaxed) {
+            return;
+        }
+        if blocks_tx.is_full() {
+            overruns.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let Ok(chunk) = samples_tx.write_chunk_uninit(samples.len()) else {
+            overruns.fetch_add(1, Ordering::Relaxed);
+            return;
         };
+        chunk.fill_from_iter(samples.iter().copied());
+        let _ = blocks_tx.push(RingBlock {
+            len: samples.len(),
+            captured_at,
+        });
+    });
+    let reader = RingReader {
+        samples: samples_rx,
+        blocks: blocks_rx,
+        scratch: Vec::new(),
+    };
+    (sink, reader)
+}
 
-        let mut stream = SCStream::new(&filter, &config);
-        stream.add_output_handler(handler, SCStreamOutputType::Audio);
+/// Place a captured block on the session clock and write it to `track`
+fn push_block(
+    track: &mut Track,
+    samples: &[f32],
+    captured_at: Option<Instant>,
+    clock: Option<SessionClock>,
+    label: &str,
+) {
+    let position = captured_at
+        .zip(clock)
+        .map(|(at, clock)| clock.position_at(at));
+    if let Err(e) = track.push(samples, position) {
+        eprintln!("Failed to write {} samples: {}", label, e);
+    }
+}
 
-        stream
-            .start_capture()
-            .map_err(|e| format!("Failed to start system audio capture: {:?}", e))?;
+/// One source's side of the writer thread: its capture ring, meters and track
+struct TrackFeed {
+    label: &'static str,
+    ring: RingReader,
+    track: Arc<Mutex<Option<Track>>>,
+    level: LevelMeter,
+    voice: VoiceDetector,
+    // while armed: the latest blocks, written out once someone speaks
+    pre_roll: VecDeque<(Vec<f32>, Option<Instant>)>,
+}
 
-        *self.sc_stream.lock() = Some(stream);
-        Ok(())
+impl TrackFeed {
+    fn new(
+        label: &'static str,
+        ring: RingReader,
+        track: &Arc<Mutex<Option<Track>>>,
+        config: &AppConfig,
+    ) -> Self {
+        Self {
+            label,
+            ring,
+            track: track.clone(),
+            level: LevelMeter::new(&config.levels),
+            voice: VoiceDetector::new(config.auto_record.voice_threshold_db),
+            pre_roll: VecDeque::new(),
+        }
     }
 
-    fn start_mic_capture(&mut self) -> Result<(), String> {
-        let host = cpal::default_host();
-        let device = host
-            .default_input_device()
-            .ok_or("No input device available")?;
-
-        let supported_config = device
-            .default_input_config()
-            .map_err(|e| format!("Failed to get default input config: {}", e))?;
+    /// Drain the capture ring into the track, or into the pre-roll while armed.
+    /// Returns whether anyone spoke in what came off the ring.
+    fn drain(&mut self, clock: Option<SessionClock>, armed: bool) -> bool {
+        let label = self.label;
+        let Self {
+            ring,
+            track,
+            level,
+            voice,
+            pre_roll,
+            ..
+        } = self;
+        let mut track = track.lock();
+        let Some(track) = track.as_mut() else {
+            return false;
+        };
+        let (channels, rate) = (track.source_channels, track.source_rate);
+        let pre_roll_len = (PRE_ROLL_SECS * rate as f64) as usize * channels as usize;
+
+        let mut heard = false;
+        ring.drain(label, |samples, captured_at| {
+            level.push(samples, channels, rate);
+            heard |= voice.push(samples, channels, rate);
+            if !armed {
+                push_block(track, samples, captured_at, clock, label);
+                return;
+            }
+            pre_roll.push_back((samples.to_vec(), captured_at));
+            // once voice is heard everything from here on is kept
+            while !heard && pre_roll.iter().map(|(s, _)| s.len()).sum::<usize>() > pre_roll_len {
+                pre_roll.pop_front();
+            }
+        });
+        heard
+    }
 
-        let mic_rate = u32::from(supported_config.sample_rate());
-        *self.mic_sample_rate.lock() = mic_rate;
-        println!("Mic sample rate: {} Hz", mic_rate);
+    /// When the oldest block in the pre-roll was captured
+    fn pre_roll_start(&self) -> Option<Instant> {
+        self.pre_roll
+            .front()
+            .and_then(|(_, captured_at)| *captured_at)
+    }
 
-        let config = cpal::StreamConfig {
-            channels: 1,
-            sample_rate: supported_config.sample_rate(),
-            buffer_size: cpal::BufferSize::Default,
+    /// Write out the pre-roll now that the session clock is running
+    fn flush_pre_roll(&mut self, clock: SessionClock) {
+        let mut track = self.track.lock();
+        let Some(track) = track.as_mut() else {
+            return;
         };
+        for (samples, captured_at) in self.pre_roll.drain(..) {
+            push_block(track, &samples, captured_at, Some(clock), self.label);
+        }
+    }
+}
+
+/// Sent to the writer thread
+enum WriterEvent {
+    /// The mic source started as `generation` stopped delivering samples
+    MicFailed {
+        generation: u64,
+        error: String,
+    },
+    Stop,
+}
 
-        // flush threshold: ~0.5 sec of samples at mic rate
-        let flush_threshold = (mic_rate / 2) as usize;
-        let output_rate = self.sample_rate;
+/// Something the recorder did on its own in voice-activated mode. Passed to the
+/// handler set with `set_auto_record_handler` and emitted as `auto-record`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AutoRecordEvent {
+    /// An armed session heard voice and started writing
+    Started,
+    /// Nobody has spoken for the configured time; the session should be stopped
+    SilenceTimeout { silent_secs: f64 },
+}
 
-        let mic_buffer = self.mic_buffer.clone();
-        let mic_writer = self.mic_writer.clone();
-        let mic_samples_written = self.mic_samples_written.clone();
-        let is_recording = self.is_recording.clone();
+/// Called on the writer thread with the session directory. Must not call
+/// `stop_recording` directly, since that waits for the writer thread to finish.
+pub type AutoRecordHandler = Arc<dyn Fn(&Path, AutoRecordEvent) + Send + Sync>;
+
+/// Where the session clock reads the time. `Instant::now` except in tests, which
+/// move it by hand so voice-activity timeouts don't depend on how fast they run.
+type Now = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// Drains the capture rings into the tracks on its own thread, so capture callbacks
+/// never wait on the disk. Also reopens the mic when its device disappears
+/// mid-session, padding the gap with silence so `mic.wav` stays aligned with `system.wav`.
+struct TrackWriter {
+    system: TrackFeed,
+    mic: TrackFeed,
+    clock: Arc<Mutex<Option<SessionClock>>>,
+    now: Now,
+    paused: Arc<AtomicBool>,
+    overruns: Arc<AtomicU64>,
+    reported_overruns: u64,
+    next_levels: Instant,
+    // voice activity
+    recording_dir: PathBuf,
+    armed: Arc<AtomicBool>,
+    voice_started_at: Option<DateTime<Local>>,
+    last_voice_secs: f64,
+    stop_after_silent_secs: u64,
+    stop_requested: bool,
+    on_auto_record: Option<AutoRecordHandler>,
+    // mic reconnection
+    backend: Arc<dyn CaptureBackend>,
+    device: Option<String>,
+    mic_source: Arc<Mutex<Option<Box<dyn CaptureSource>>>>,
+    generation: u64,
+    // while the mic is lost: frames it trailed the system track by, and when to retry
+    reconnect: Option<(i64, Instant)>,
+    events: mpsc::Sender<WriterEvent>,
+    app: Option<AppHandle>,
+}
 
-        // helper to flush buffer
-        let flush_mic_buffer = move |buffer: &mut Vec<f32>| {
-            if buffer.is_empty() {
-                return;
-            }
-            let resampled = resample(buffer, mic_rate, output_rate);
-            buffer.clear();
-            if let Some(ref mut writer) = *mic_writer.lock() {
-                if let Err(e) = writer.write_samples(&resampled) {
-                    eprintln!("Failed to write mic samples: {}", e);
-                } else {
-                    *mic_samples_written.lock() += resampled.len() as u64;
+impl TrackWriter {
+    /// Error sink for the mic source started as `generation`. Errors from a source
+    /// that has already been replaced are ignored by `run`.
+    fn error_sink(&self, generation: u64) -> ErrorSink {
+        let events = self.events.clone();
+        Arc::new(move |error: String| {
+            let _ = events.send(WriterEvent::MicFailed { generation, error });
+        })
+    }
+
+    /// Drain until told to stop, then hand the writer back so the caller can pick
+    /// up whatever the sources deliver before they are stopped
+    fn run(mut self, events: mpsc::Receiver<WriterEvent>) -> Self {
+        loop {
+            let event = events.recv_timeout(WRITER_INTERVAL);
+            self.drain();
+            match event {
+                Ok(WriterEvent::Stop) | Err(RecvTimeoutError::Disconnected) => return self,
+                Ok(WriterEvent::MicFailed { generation, error })
+                    if generation == self.generation && self.reconnect.is_none() =>
+                {
+                    self.mic_failed(error)
                 }
+                // stale errors from the lost stream, or just time to drain again
+                Ok(WriterEvent::MicFailed { .. }) | Err(RecvTimeoutError::Timeout) => {}
+            }
+            if self.reconnect.is_some_and(|(_, at)| Instant::now() >= at) {
+                self.try_reopen();
+            }
+            if Instant::now() >= self.next_levels {
+                self.report_levels();
+                self.next_levels = Instant::now() + LEVELS_INTERVAL;
+            }
+            self.check_silence_timeout();
+
+            let overruns = self.overruns.load(Ordering::Relaxed);
+            if overruns > self.reported_overruns {
+                eprintln!("Capture ring overrun: {} blocks dropped so far", overruns);
+                self.reported_overruns = overruns;
+            }
+        }
+    }
+
+    fn drain(&mut self) {
+        let clock = *self.clock.lock();
+        let armed = self.armed.load(Ordering::SeqCst);
+        // not `||`: both rings are drained whatever the first one heard
+        let heard = self.system.drain(clock, armed) | self.mic.drain(clock, armed);
+        if heard {
+            if armed {
+                self.start_writing();
             }
+            self.last_voice_secs = self.recorded_secs();
+        }
+    }
+
+    fn recorded_secs(&self) -> f64 {
+        self.clock
+            .lock()
+            .map_or(0.0, |clock| clock.position_at((self.now)()))
+    }
+
+    fn notify(&self, event: AutoRecordEvent) {
+        if let Some(app) = &self.app {
+            let _ = app.emit("auto-record", &event);
+        }
+        if let Some(handler) = &self.on_auto_record {
+            handler(&self.recording_dir, event);
+        }
+    }
+
+    /// Someone spoke while armed: start the session clock where the pre-roll
+    /// begins and write the pre-roll out
+    fn start_writing(&mut self) {
+        let now = (self.now)();
+        let epoch = [self.system.pre_roll_start(), self.mic.pre_roll_start()]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(now);
+        let clock = SessionClock::starting_at(epoch);
+        *self.clock.lock() = Some(clock);
+        self.armed.store(false, Ordering::SeqCst);
+        let since_epoch = now.saturating_duration_since(epoch);
+        self.voice_started_at =
+            Some(Local::now() - chrono::Duration::from_std(since_epoch).unwrap_or_default());
+        self.system.flush_pre_roll(clock);
+        self.mic.flush_pre_roll(clock);
+        println!("Voice detected, recording");
+        self.notify(AutoRecordEvent::Started);
+    }
+
+    fn check_silence_timeout(&mut self) {
+        if self.stop_after_silent_secs == 0
+            || self.stop_requested
+            || self.armed.load(Ordering::SeqCst)
+        {
+            return;
+        }
+        let silent_secs = self.recorded_secs() - self.last_voice_secs;
+        if silent_secs >= self.stop_after_silent_secs as f64 {
+            println!("No voice for {:.0}s, stopping", silent_secs);
+            self.stop_requested = true;
+            self.notify(AutoRecordEvent::SilenceTimeout { silent_secs });
+        }
+    }
+
+    /// Emit the levels since the last report and warn about sources gone quiet
+    fn report_levels(&mut self) {
+        let levels = AudioLevels {
+            system: self.system.level.take(),
+            mic: self.mic.level.take(),
         };
+        if let Some(app) = &self.app {
+            let _ = app.emit("audio-levels", &levels);
+        }
+        if self.system.level.silence_warning_due() {
+            let period = levels::describe_secs(self.system.level.warn_after_secs());
+            emit_warning(
+                self.app.as_ref(),
+                format!(
+                    "No system audio for {} - is the right output being captured?",
+                    period
+                ),
+            );
+        }
+        if self.mic.level.silence_warning_due() {
+            let period = levels::describe_secs(self.mic.level.warn_after_secs());
+            emit_warning(
+                self.app.as_ref(),
+                format!("No sound from the microphone for {} - is it muted?", period),
+            );
+        }
+    }
 
-        let stream = match supported_config.sample_format() {
-            cpal::SampleFormat::F32 => device
-                .build_input_stream(
-                    &config,
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        if *is_recording.lock() {
-                            let mut buffer = mic_buffer.lock();
-                            buffer.extend_from_slice(data);
-                            if buffer.len() >= flush_threshold {
-                                flush_mic_buffer(&mut buffer);
-                            }
-                        }
-                    },
-                    |err| eprintln!("Mic stream error: {}", err),
-                    None,
-                )
-                .map_err(|e| format!("Failed to build mic stream: {}", e))?,
-            cpal::SampleFormat::I16 => {
-                let mic_buffer = self.mic_buffer.clone();
-                let mic_writer = self.mic_writer.clone();
-                let mic_samples_written = self.mic_samples_written.clone();
-                let is_recording = self.is_recording.clone();
-
-                let flush_mic_buffer_i16 = move |buffer: &mut Vec<f32>| {
-                    if buffer.is_empty() {
-                        return;
-                    }
-                    let resampled = resample(buffer, mic_rate, output_rate);
-                    buffer.clear();
-                    if let Some(ref mut writer) = *mic_writer.lock() {
-                        if let Err(e) = writer.write_samples(&resampled) {
-                            eprintln!("Failed to write mic samples: {}", e);
-                        } else {
-                            *mic_samples_written.lock() += resampled.len() as u64;
-                        }
-                    }
-                };
-
-                device
-                    .build_input_stream(
-                        &config,
-                        move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                            if *is_recording.lock() {
-                                let float_samples: Vec<f32> =
-                                    data.iter().map(|&s| s as f32 / 32768.0).collect();
-                                let mut buffer = mic_buffer.lock();
-                                buffer.extend(float_samples);
-                                if buffer.len() >= flush_threshold {
-                                    flush_mic_buffer_i16(&mut buffer);
-                                }
-                            }
-                        },
-                        |err| eprintln!("Mic stream error: {}", err),
-                        None,
-                    )
-                    .map_err(|e| format!("Failed to build mic stream: {}", e))?
+    fn mic_failed(&mut self, error: String) {
+        emit_warning(self.app.as_ref(), format!("{}, reconnecting", error));
+        if let Some(mut source) = self.mic_source.lock().take() {
+            source.stop();
+        }
+        // keep what the lost stream delivered before it went away
+        self.drain();
+        // however far the mic trailed the system track before the failure is
+        // latency, not gap; keep it so only the missing audio gets padded
+        self.reconnect = Some((self.frames_behind_system(), Instant::now()));
+    }
+
+    fn try_reopen(&mut self) {
+        let Some((lag, _)) = self.reconnect else {
+            return;
+        };
+        match self.reopen(lag) {
+            Ok(name) => {
+                self.reconnect = None;
+                emit_warning(
+                    self.app.as_ref(),
+                    format!("Microphone reconnected: {}", name),
+                );
+            }
+            Err(e) => {
+                eprintln!("Failed to reopen mic: {}", e);
+                self.reconnect = Some((lag, Instant::now() + MIC_REOPEN_INTERVAL));
+            }
+        }
+    }
+
+    fn frames_behind_system(&self) -> i64 {
+        let system = self
+            .system
+            .track
+            .lock()
+            .as_ref()
+            .map_or(0.0, |t| t.position_frames());
+        let mic = self
+            .mic
+            .track
+            .lock()
+            .as_ref()
+            .map_or(0.0, |t| t.position_frames());
+        (system - mic).round() as i64
+    }
+
+    /// Open the configured (or now default) input, fill the gap and start it on a new ring
+    fn reopen(&mut self, lag: i64) -> Result<String, String> {
+        let mut source = self.backend.open_mic(self.device.as_deref())?;
+        let missing = self.frames_behind_system() - lag;
+        if let Some(track) = self.mic.track.lock().as_mut() {
+            if missing > 0 {
+                track.write_silence(missing as u64)?;
+            }
+            track.set_source(source.as_ref())?;
+        }
+        let generation = self.generation + 1;
+        let (sink, ring) = ring_sink(source.as_ref(), &self.paused, &self.overruns);
+        source.start(sink, self.error_sink(generation))?;
+        self.mic.ring = ring;
+        self.generation = generation;
+        let name = source.name();
+        *self.mic_source.lock() = Some(source);
+        Ok(name)
+    }
+}
+
+pub struct AudioRecorder {
+    backend: Arc<dyn CaptureBackend>,
+    // streaming tracks - opened at start, closed at stop
+    system_track: Arc<Mutex<Option<Track>>>,
+    mic_track: Arc<Mutex<Option<Track>>>,
+    // active capture sources; the mic may be swapped by the writer thread
+    system_source: Option<Box<dyn CaptureSource>>,
+    mic_source: Arc<Mutex<Option<Box<dyn CaptureSource>>>>,
+    writer: Option<(mpsc::Sender<WriterEvent>, JoinHandle<TrackWriter>)>,
+    // tracking
+    is_recording: bool,
+    paused: Arc<AtomicBool>,
+    overruns: Arc<AtomicU64>,
+    armed: Arc<AtomicBool>,
+    on_auto_record: Option<AutoRecordHandler>,
+    clock: Arc<Mutex<Option<SessionClock>>>,
+    now: Now,
+    recording_dir: Option<PathBuf>,
+    session: Option<SessionMetadata>,
+    // lock on the in-progress marker, handed to the mixdown thread at stop
+    session_lock: Option<SessionLock>,
+    // mixdown of the last stopped session, running in the background
+    mixdown: Option<JoinHandle<Result<RecordingOutput, String>>>,
+    // audio config
+    sample_rate: u32,
+    config: AppConfig,
+}
+
+/// Convert interleaved samples between channel layouts (downmix to mono, duplicate mono)
+fn map_channels(samples: &[f32], from: u16, to: u16) -> Vec<f32> {
+    if from == to || from == 0 {
+        return samples.to_vec();
+    }
+
+    let from = from as usize;
+    let to = to as usize;
+    let mut result = Vec::with_capacity(samples.len() / from * to);
+    for frame in samples.chunks_exact(from) {
+        if to == 1 {
+            result.push(frame.iter().sum::<f32>() / from as f32);
+        } else if from == 1 {
+            result.extend(std::iter::repeat_n(frame[0], to));
+        } else {
+            result.extend((0..to).map(|c| frame.get(c).copied().unwrap_or(0.0)));
+        }
+    }
+    result
+}
+
+impl AudioRecorder {
+    pub fn new() -> Self {
+        Self::with_backend(capture::default_backend())
+    }
+
+    /// Create a recorder that opens its sources from the given backend
+    pub fn with_backend(backend: Box<dyn CaptureBackend>) -> Self {
+        Self {
+            backend: Arc::from(backend),
+            system_track: Arc::new(Mutex::new(None)),
+            mic_track: Arc::new(Mutex::new(None)),
+            system_source: None,
+            mic_source: Arc::new(Mutex::new(None)),
+            writer: None,
+            is_recording: false,
+            paused: Arc::new(AtomicBool::new(false)),
+            overruns: Arc::new(AtomicU64::new(0)),
+            armed: Arc::new(AtomicBool::new(false)),
+            on_auto_record: None,
+            clock: Arc::new(Mutex::new(None)),
+            now: Arc::new(Instant::now),
+            recording_dir: None,
+            session: None,
+            session_lock: None,
+            mixdown: None,
+            sample_rate: 48000,
+            config: AppConfig::default(),
+        }
+    }
+
+    /// Pick up recorder settings from the app config. Takes effect at the next start.
+    pub fn apply_config(&mut self, config: &AppConfig) {
+        self.config = config.clone();
+    }
+
+    /// Receive the recorder's voice-activated starts and stops (see `AutoRecordSettings`)
+    pub fn set_auto_record_handler(&mut self, handler: AutoRecordHandler) {
+        self.on_auto_record = Some(handler);
+    }
+
+    /// Read the session clock from `now` instead of `Instant::now`
+    #[cfg(test)]
+    fn set_clock(&mut self, now: Now) {
+        self.now = now;
+    }
+
+    /// Start recording to the given directory. Mid-session warnings are emitted to `app`.
+    pub fn start_recording(
+        &mut self,
+        recording_dir: &PathBuf,
+        app: Option<&AppHandle>,
+    ) -> Result<(), String> {
+        if self.is_recording {
+            return Err("Already recording".to_string());
+        }
+
+        // Create recording directory
+        std::fs::create_dir_all(recording_dir)
+            .map_err(|e| format!("Failed to create recording directory: {}", e))?;
+
+        let mut system_source = self
+            .backend
+            .open_system(self.sample_rate, self.config.system_device.as_deref())?;
+        let mut mic_source = self.backend.open_mic(self.config.input_device.as_deref())?;
+        println!(
+            "System source: {} ({} Hz, {} ch), mic source: {} ({} Hz, {} ch)",
+            system_source.name(),
+            system_source.sample_rate(),
+            system_source.channels(),
+            mic_source.name(),
+            mic_source.sample_rate(),
+            mic_source.channels()
+        );
+
+        // Open streaming WAV writers; they are re-encoded to the storage codec at stop
+        let system_file = codec::track_path(recording_dir, "system", StorageCodec::Wav);
+        let mic_file = codec::track_path(recording_dir, "mic", StorageCodec::Wav);
+
+        let system_track = Track::new(&system_file, system_source.as_ref(), self.sample_rate, 2)?;
+        let mic_track = Track::new(&mic_file, mic_source.as_ref(), self.sample_rate, 1)?;
+
+        *self.system_track.lock() = Some(system_track);
+        *self.mic_track.lock() = Some(mic_track);
+
+        match SessionLock::create(recording_dir) {
+            Ok(lock) => self.session_lock = Some(lock),
+            Err(e) => {
+                self.abort_start(recording_dir);
+                return Err(e);
             }
-            format => return Err(format!("Unsupported sample format: {:?}", format)),
+        }
+
+        let mut session = SessionMetadata::new(chrono::Local::now());
+        session.tracks = Some(SessionTracks {
+            system: track_info(system_source.as_ref(), self.sample_rate, 2),
+            mic: track_info(mic_source.as_ref(), self.sample_rate, 1),
+        });
+        if let Err(e) = session.save(recording_dir) {
+            self.abort_start(recording_dir);
+            return Err(e);
+        }
+
+        // capture callbacks only fill these rings; the writer thread does the rest
+        self.overruns.store(0, Ordering::SeqCst);
+        let (system_sink, system_reader) =
+            ring_sink(system_source.as_ref(), &self.paused, &self.overruns);
+        let (mic_sink, mic_reader) = ring_sink(mic_source.as_ref(), &self.paused, &self.overruns);
+        let (events_tx, events_rx) = mpsc::channel();
+        let writer = TrackWriter {
+            system: TrackFeed::new("system", system_reader, &self.system_track, &self.config),
+            mic: TrackFeed::new("mic", mic_reader, &self.mic_track, &self.config),
+            clock: self.clock.clone(),
+            now: self.now.clone(),
+            paused: self.paused.clone(),
+            overruns: self.overruns.clone(),
+            reported_overruns: 0,
+            next_levels: Instant::now() + LEVELS_INTERVAL,
+            recording_dir: recording_dir.clone(),
+            armed: self.armed.clone(),
+            voice_started_at: None,
+            last_voice_secs: 0.0,
+            stop_after_silent_secs: self.config.auto_record.stop_after_silent_secs,
+            stop_requested: false,
+            on_auto_record: self.on_auto_record.clone(),
+            backend: self.backend.clone(),
+            device: self.config.input_device.clone(),
+            mic_source: self.mic_source.clone(),
+            generation: 0,
+            reconnect: None,
+            events: events_tx.clone(),
+            app: app.cloned(),
+        };
+
+        // system audio has no fallback device to switch to; just surface the problem
+        let system_error: ErrorSink = {
+            let app = app.cloned();
+            Arc::new(move |error: String| emit_warning(app.as_ref(), error))
+        };
+
+        // both tracks are placed relative to this instant; an armed session's clock
+        // is started by the writer thread once someone speaks
+        let armed = self.config.auto_record.start_on_voice;
+        *self.clock.lock() = if armed {
+            println!("Armed, waiting for voice");
+            None
+        } else {
+            Some(SessionClock::starting_at((self.now)()))
         };
+        self.armed.store(armed, Ordering::SeqCst);
+        self.paused.store(false, Ordering::SeqCst);
+        if let Err(e) = system_source.start(system_sink, system_error) {
+            self.abort_start(recording_dir);
+            return Err(e);
+        }
 
-        stream
-            .play()
-            .map_err(|e| format!("Failed to start mic stream: {}", e))?;
+        if let Err(e) = mic_source.start(mic_sink, writer.error_sink(0)) {
+            system_source.stop();
+            self.abort_start(recording_dir);
+            return Err(e);
+        }
+
+        let handle = match std::thread::Builder::new()
+            .name("track-writer".to_string())
+            .spawn(move || writer.run(events_rx))
+        {
+            Ok(handle) => handle,
+            Err(e) => {
+                system_source.stop();
+                mic_source.stop();
+                self.abort_start(recording_dir);
+                return Err(format!("Failed to start track writer: {}", e));
+            }
+        };
 
-        self.mic_stream = Some(stream);
+        self.system_source = Some(system_source);
+        *self.mic_source.lock() = Some(mic_source);
+        self.writer = Some((events_tx, handle));
+        self.recording_dir = Some(recording_dir.clone());
+        self.session = Some(session);
+        self.is_recording = true;
         Ok(())
     }
 
-    pub fn stop_recording(&mut self, app: Option<&AppHandle>) -> Result<RecordingOutput, String> {
-        if !*self.is_recording.lock() {
+    /// Stop writing samples to both tracks while keeping the session open
+    pub fn pause_recording(&mut self) -> Result<(), String> {
+        if !self.is_recording {
             return Err("Not recording".to_string());
         }
+        if self.is_paused() {
+            return Err("Already paused".to_string());
+        }
 
-        *self.is_recording.lock() = false;
+        self.paused.store(true, Ordering::SeqCst);
+        let offset_secs = self.recorded_secs();
+        if let Some(clock) = self.clock.lock().as_mut() {
+            clock.pause((self.now)());
+        }
 
-        // Stop ScreenCaptureKit stream
-        if let Some(stream) = self.sc_stream.lock().take() {
-            let _ = stream.stop_capture();
+        if let Some(session) = self.session.as_mut() {
+            session.pauses.push(PauseInterval {
+                offset_secs,
+                paused_at: chrono::Local::now(),
+                resumed_at: None,
+            });
         }
+        self.save_session()
+    }
 
-        // Stop mic stream (drops automatically)
-        self.mic_stream.take();
+    /// Continue writing samples after a pause
+    pub fn resume_recording(&mut self) -> Result<(), String> {
+        if !self.is_recording {
+            return Err("Not recording".to_string());
+        }
+        if !self.is_paused() {
+            return Err("Not paused".to_string());
+        }
 
-        // Flush any remaining mic samples
-        self.flush_remaining_mic_samples()?;
+        if let Some(clock) = self.clock.lock().as_mut() {
+            clock.resume((self.now)());
+        }
+        if let Some(pause) = self.session.as_mut().and_then(|s| s.pauses.last_mut()) {
+            pause.resumed_at = Some(chrono::Local::now());
+        }
+        self.paused.store(false, Ordering::SeqCst);
+        self.save_session()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Seconds of audio recorded so far, i.e. wall time since start minus pauses
+    fn recorded_secs(&self) -> f64 {
+        self.clock
+            .lock()
+            .map_or(0.0, |clock| clock.position_at((self.now)()))
+    }
 
-        // Finalize the streaming writers
-        let system_samples = if let Some(writer) = self.system_writer.lock().take() {
-            writer.finalize()?
+    /// Record the pauses so far in `session.json`, keeping whatever else was
+    /// changed there meanwhile (e.g. a rename)
+    fn save_session(&self) -> Result<(), String> {
+        match (&self.session, &self.recording_dir) {
+            (Some(session), Some(dir)) => {
+                SessionMetadata::update(dir, |stored| stored.pauses = session.pauses.clone())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Drop the open tracks and the in-progress marker after a failed start
+    fn abort_start(&mut self, recording_dir: &Path) {
+        self.clock.lock().take();
+        self.armed.store(false, Ordering::SeqCst);
+        self.system_track.lock().take();
+        self.mic_track.lock().take();
+        let _ = std::fs::remove_file(recording_dir.join(IN_PROGRESS_MARKER));
+        self.session_lock.take();
+    }
+
+    /// Get stats about the current recording
+    pub fn get_stats(&self) -> Option<RecordingStats> {
+        if !self.is_recording {
+            return None;
+        }
+        Some(RecordingStats {
+            duration_secs: self.recorded_secs(),
+            is_paused: self.is_paused(),
+            system_samples_written: self
+                .system_track
+                .lock()
+                .as_ref()
+                .map_or(0, |t| t.samples_written()),
+            mic_samples_written: self
+                .mic_track
+                .lock()
+                .as_ref()
+                .map_or(0, |t| t.samples_written()),
+            overruns: self.overruns.load(Ordering::Relaxed),
+            is_armed: self.armed.load(Ordering::SeqCst),
+        })
+    }
+
+    pub fn stop_recording(&mut self, app: Option<&AppHandle>) -> Result<RecordingOutput, String> {
+        if !self.is_recording {
+            return Err("Not recording".to_string());
+        }
+
+        self.is_recording = false;
+
+        // Stop the writer thread first so it can't reopen the mic behind our back,
+        // then the capture sources so no more samples arrive, then drain what's left.
+        // A paused session stays paused until then, so nothing more gets through.
+        let writer = self.writer.take().and_then(|(events, handle)| {
+            let _ = events.send(WriterEvent::Stop);
+            handle.join().ok()
+        });
+        if let Some(mut source) = self.system_source.take() {
+            source.stop();
+        }
+        if let Some(mut source) = self.mic_source.lock().take() {
+            source.stop();
+        }
+        let mut voice_started_at = None;
+        if let Some(mut writer) = writer {
+            writer.drain();
+            voice_started_at = writer.voice_started_at;
+        }
+        self.paused.store(false, Ordering::SeqCst);
+        self.armed.store(false, Ordering::SeqCst);
+
+        // Flush remaining samples and finalize the streaming writers
+        let mut alignment = TrackAlignment::default();
+        let system_samples = if let Some(track) = self.system_track.lock().take() {
+            alignment.system = track.timing();
+            track.finalize()?
         } else {
             0
         };
 
-        let mic_samples = if let Some(writer) = self.mic_writer.lock().take() {
-            writer.finalize()?
+        let mic_samples = if let Some(track) = self.mic_track.lock().take() {
+            alignment.mic = track.timing();
+            track.finalize()?
         } else {
             0
         };
+        if let (Some(system), Some(mic)) = (alignment.system.start_secs, alignment.mic.start_secs) {
+            alignment.mic_offset_secs = Some(mic - system);
+        }
 
         let recording_dir = self
             .recording_dir
-            .lock()
             .take()
             .ok_or("No recording directory set")?;
 
-        let system_file = recording_dir.join("system.wav");
-        let mic_file = recording_dir.join("mic.wav");
-        let mixed_file = recording_dir.join("mixed.wav");
-
         println!(
             "System samples written: {}, Mic samples written: {}",
             system_samples, mic_samples
         );
 
-        // Generate mixed.wav from the finalized files
-        self.generate_mixed_audio(&system_file, &mic_file, &mixed_file, app)?;
+        // merge into what's on disk rather than saving our copy, which doesn't have
+        // anything set while recording (e.g. a new title)
+        if let Some(session) = self.session.take() {
+            SessionMetadata::update(&recording_dir, |stored| {
+                // an armed session starts when the pre-roll before the first voice does
+                if let Some(started_at) = voice_started_at {
+                    stored.started_at = started_at;
+                }
+                stored.ended_at = Some(chrono::Local::now());
+                stored.pauses = session.pauses;
+                if let Some(tracks) = stored.tracks.as_mut() {
+                    tracks.system.samples = system_samples;
+                    tracks.mic.samples = mic_samples;
+                }
+                stored.alignment = Some(alignment);
+            })?;
+        }
+
+        self.clock.lock().take();
+
+        // Mix and re-encode in the background so stopping a long recording is instant.
+        // The in-progress marker stays locked until that is done, so recovery leaves
+        // the session alone meanwhile and picks it up after a crash like any other.
+        let config = self.config.clone();
+        let codec = config.storage_codec;
+        let output = RecordingOutput {
+            directory: recording_dir.clone(),
+            system_file: codec::track_path(&recording_dir, "system", codec),
+            mic_file: codec::track_path(&recording_dir, "mic", codec),
+            mixed_file: codec::track_path(&recording_dir, "mixed", codec),
+        };
+        let app = app.cloned();
+        let lock = self.session_lock.take();
+        let handle = std::thread::Builder::new()
+            .name("mixdown".to_string())
+            .spawn(move || {
+                let result = finish_session_files(&recording_dir, &config, app.as_ref());
+                drop(lock);
+                if let Err(e) = &result {
+                    eprintln!("Mixdown of {:?} failed: {}", recording_dir, e);
+                }
+                if let Some(app) = &app {
+                    let _ = app.emit(
+                        "mixing-complete",
+                        MixingComplete {
+                            output: result.as_ref().ok().cloned(),
+                            error: result.as_ref().err().cloned(),
+                        },
+                    );
+                }
+                result
+            })
+            .map_err(|e| format!("Failed to start mixdown: {}", e))?;
+        self.mixdown = Some(handle);
 
-        // Clear start time
-        *self.start_time.lock() = None;
+        Ok(output)
+    }
 
-        Ok(RecordingOutput {
-            directory: recording_dir,
-            system_file,
-            mic_file,
-            mixed_file,
-        })
+    /// Block until the mixdown started by the last `stop_recording` has finished
+    pub fn wait_for_mixdown(&mut self) -> Result<RecordingOutput, String> {
+        let handle = self.mixdown.take().ok_or("No mixdown running")?;
+        handle
+            .join()
+            .map_err(|_| "Mixdown thread panicked".to_string())?
     }
 
-    /// Flush any remaining samples in the mic buffer
-    fn flush_remaining_mic_samples(&self) -> Result<(), String> {
-        let mic_rate = *self.mic_sample_rate.lock();
-        let mut buffer = self.mic_buffer.lock();
+    pub fn is_recording(&self) -> bool {
+        self.is_recording
+    }
 
-        if buffer.is_empty() {
-            return Ok(());
-        }
+    /// Directory of the session being recorded
+    pub fn recording_dir(&self) -> Option<&Path> {
+        self.recording_dir.as_deref()
+    }
+}
 
-        let resampled = resample(&buffer, mic_rate, self.sample_rate);
-        buffer.clear();
+/// Turn the finalized capture WAVs in `dir` into the stored session: mix them,
+/// re-encode every track to the storage codec and clear the in-progress marker
+pub(crate) fn finish_session_files(
+    dir: &Path,
+    config: &AppConfig,
+    app: Option<&AppHandle>,
+) -> Result<RecordingOutput, String> {
+    let codec = config.storage_codec;
+    let system_wav = codec::track_path(dir, "system", StorageCodec::Wav);
+    let mic_wav = codec::track_path(dir, "mic", StorageCodec::Wav);
+    let mixed_file = codec::track_path(dir, "mixed", codec);
+
+    let (system_gain, mic_gain) =
+        generate_mixed_audio(&system_wav, &mic_wav, &mixed_file, codec, &config.mix, app)?;
+    let system_file = codec::transcode_wav(&system_wav, codec)?;
+    let mic_file = codec::transcode_wav(&mic_wav, codec)?;
+
+    let mix = MixInfo {
+        mode: config.mix.mode,
+        system_gain,
+        mic_gain,
+        codec,
+        mixed_at: chrono::Local::now(),
+    };
+    if let Err(e) = SessionMetadata::update(dir, |session| session.mix = Some(mix)) {
+        eprintln!("Failed to record mix in session metadata: {}", e);
+    }
 
-        if let Some(ref mut writer) = *self.mic_writer.lock() {
-            writer.write_samples(&resampled)?;
-            *self.mic_samples_written.lock() += resampled.len() as u64;
+    // Session is complete on disk; nothing left for crash recovery to do.
+    // The marker goes first so a crash below only leaves redundant WAVs behind.
+    let _ = std::fs::remove_file(dir.join(IN_PROGRESS_MARKER));
+    if codec != StorageCodec::Wav {
+        for wav in [&system_wav, &mic_wav] {
+            if let Err(e) = std::fs::remove_file(wav) {
+                eprintln!("Failed to remove {:?}: {}", wav, e);
+            }
         }
+    }
 
-        Ok(())
+    Ok(RecordingOutput {
+        directory: dir.to_path_buf(),
+        system_file,
+        mic_file,
+        mixed_file,
+    })
+}
+
+/// Generate the mixed track from system.wav and mic.wav. Both files are streamed
+/// in fixed-size blocks, so memory use doesn't grow with the recording length.
+/// Returns the (system, mic) gains that were applied.
+pub(crate) fn generate_mixed_audio(
+    system_file: &Path,
+    mic_file: &Path,
+    output_path: &Path,
+    codec: StorageCodec,
+    mix: &MixSettings,
+    app: Option<&AppHandle>,
+) -> Result<(f32, f32), String> {
+    use hound::WavReader;
+
+    let (system_gain, mic_gain) = mix_gains(system_file, mic_file, mix)?;
+
+    // System audio (stereo)
+    let mut system_reader =
+        WavReader::open(system_file).map_err(|e| format!("Failed to open system.wav: {}", e))?;
+    let sample_rate = system_reader.spec().sample_rate;
+    let system_frames = system_reader.duration() as usize;
+
+    // Mic audio (mono, already resampled)
+    let mut mic_reader =
+        WavReader::open(mic_file).map_err(|e| format!("Failed to open mic.wav: {}", e))?;
+    let mic_frames = mic_reader.duration() as usize;
+
+    let max_frames = system_frames.max(mic_frames);
+
+    println!(
+        "Mixing: system={} frames, mic={} frames",
+        system_frames, mic_frames
+    );
+
+    let mut encoder = codec::create_encoder(codec, output_path, 2, sample_rate)?;
+
+    // Mix: system audio (stereo) + mic (mono expanded to stereo)
+    const CHUNK_SIZE: usize = 16384; // ~0.34 sec at 48kHz
+    let mut system_samples = system_reader.samples::<f32>();
+    let mut mic_samples = mic_reader.samples::<f32>();
+    let mut system_block = Vec::with_capacity(CHUNK_SIZE * 2);
+    let mut mic_block = Vec::with_capacity(CHUNK_SIZE);
+    let mut mixed = Vec::with_capacity(CHUNK_SIZE * 2);
+    let mut last_percent: f32 = 0.0;
+
+    for chunk_start in (0..max_frames).step_by(CHUNK_SIZE) {
+        let chunk_end = (chunk_start + CHUNK_SIZE).min(max_frames);
+        let frames = chunk_end - chunk_start;
+
+        // the shorter track is padded with silence
+        read_block(&mut system_samples, frames * 2, &mut system_block);
+        read_block(&mut mic_samples, frames, &mut mic_block);
+
+        mixed.clear();
+        for (sys, &mic) in system_block.chunks_exact(2).zip(&mic_block) {
+            let mic = mic * mic_gain;
+            mixed.push(soft_limit(sys[0] * system_gain + mic));
+            mixed.push(soft_limit(sys[1] * system_gain + mic));
+        }
+        encoder.write(&mixed)?;
+
+        // emit progress every 1%
+        let percent = (chunk_end as f32 / max_frames as f32) * 100.0;
+        if let Some(app) = app {
+            if percent - last_percent >= 1.0 {
+                last_percent = percent;
+                let _ = app.emit(
+                    "mixing-progress",
+                    MixingProgress {
+                        current_frame: chunk_end as u64,
+                        total_frames: max_frames as u64,
+                        percent,
+                    },
+                );
+            }
+        }
     }
 
-    /// Generate mixed.wav by reading system.wav and mic.wav and mixing them
-    fn generate_mixed_audio(
-        &self,
-        system_file: &PathBuf,
-        mic_file: &PathBuf,
-        output_path: &PathBuf,
-        app: Option<&AppHandle>,
-    ) -> Result<(), String> {
-        use hound::WavReader;
-
-        // Read system audio (stereo)
-        let system_reader = WavReader::open(system_file)
-            .map_err(|e| format!("Failed to open system.wav: {}", e))?;
-        let system_samples: Vec<f32> = system_reader
-            .into_samples::<f32>()
-            .filter_map(|s| s.ok())
-            .collect();
+    encoder.finish()?;
+    Ok((system_gain, mic_gain))
+}
 
-        // Read mic audio (mono, already resampled)
-        let mic_reader = WavReader::open(mic_file)
-            .map_err(|e| format!("Failed to open mic.wav: {}", e))?;
-        let mic_samples: Vec<f32> = mic_reader
-            .into_samples::<f32>()
-            .filter_map(|s| s.ok())
-            .collect();
+/// Most a quiet track is boosted by in normalize mode, so a mic that only picked
+/// up room noise isn't amplified into a hiss
+const MAX_NORMALIZE_GAIN_DB: f64 = 20.0;
 
-        // Determine output length
-        let system_frames = system_samples.len() / 2;
-        let mic_frames = mic_samples.len();
-        let max_frames = system_frames.max(mic_frames);
+/// Linear (system, mic) gains for the mix
+fn mix_gains(system_file: &Path, mic_file: &Path, mix: &MixSettings) -> Result<(f32, f32), String> {
+    if mix.mode == MixMode::Manual {
+        return Ok((mix.system_gain, mix.mic_gain));
+    }
 
+    let gain_for = |path: &Path| -> Result<f32, String> {
+        let gain_db = match loudness::measure_wav(path)? {
+            Some(lufs) => (mix.target_lufs - lufs).min(MAX_NORMALIZE_GAIN_DB),
+            // silent track; nothing to normalize
+            None => 0.0,
+        };
         println!(
-            "Mixing: system={} frames, mic={} frames",
-            system_frames, mic_frames
+            "Normalizing {:?}: {:+.1} dB",
+            path.file_name().unwrap_or_default(),
+            gain_db
         );
+        Ok(10f64.powf(gain_db / 20.0) as f32)
+    };
+    Ok((gain_for(system_file)?, gain_for(mic_file)?))
+}
 
-        let spec = WavSpec {
-            channels: 2,
-            sample_rate: self.sample_rate,
-            bits_per_sample: 32,
-            sample_format: hound::SampleFormat::Float,
+/// Level above which the limiter starts compressing
+const LIMITER_THRESHOLD: f32 = 0.8;
+
+/// Soft-knee limiter: transparent below the threshold, then bends smoothly
+/// towards full scale instead of flattening peaks like a hard clamp
+fn soft_limit(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= LIMITER_THRESHOLD {
+        return sample;
+    }
+    let headroom = 1.0 - LIMITER_THRESHOLD;
+    let limited =
+        LIMITER_THRESHOLD + headroom * ((magnitude - LIMITER_THRESHOLD) / headroom).tanh();
+    limited.copysign(sample)
+}
+
+/// Fill `block` with the next `len` samples, padding with silence once the
+/// reader runs out (or hits a truncated sample at the end of a crashed file)
+fn read_block(
+    samples: &mut impl Iterator<Item = hound::Result<f32>>,
+    len: usize,
+    block: &mut Vec<f32>,
+) {
+    block.clear();
+    block.extend(samples.by_ref().take(len).map_while(|s| s.ok()));
+    block.resize(len, 0.0);
+}
+
+impl Default for AudioRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::FileSource;
+    use crate::config::AutoRecordSettings;
+
+    #[test]
+    fn test_map_channels() {
+        // stereo -> mono averages each frame
+        assert_eq!(map_channels(&[0.2, 0.4, 1.0, 0.0], 2, 1), vec![0.3, 0.5]);
+        // mono -> stereo duplicates
+        assert_eq!(map_channels(&[0.1, 0.2], 1, 2), vec![0.1, 0.1, 0.2, 0.2]);
+        // same layout passes through
+        assert_eq!(map_channels(&[0.1, 0.2], 2, 2), vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_streaming_writer_checkpoints_header() {
+        let path = std::env::temp_dir().join("audio_test_checkpoint.wav");
+        let mut writer = StreamingWavWriter::new(&path, 1, 8000).unwrap();
+        writer
+            .write_samples(&vec![0.1; 8000 * HEADER_CHECKPOINT_SECS as usize])
+            .unwrap();
+        writer.write_samples(&[0.1; 100]).unwrap();
+
+        // without finalize(), the header already covers everything up to the checkpoint
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 8000 * HEADER_CHECKPOINT_SECS as u32);
+
+        assert_eq!(
+            writer.finalize().unwrap(),
+            8000 * HEADER_CHECKPOINT_SECS + 100
+        );
+    }
+
+    #[test]
+    fn test_stop_recording_removes_marker() {
+        let dir = std::env::temp_dir().join("audio_test_marker");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut recorder = file_recorder();
+        recorder.start_recording(&dir, None).unwrap();
+        assert!(dir.join(IN_PROGRESS_MARKER).exists());
+        recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+        assert!(!dir.join(IN_PROGRESS_MARKER).exists());
+    }
+
+    /// Backend that replays fixed buffers instead of touching real devices
+    struct FileBackend {
+        system: Vec<f32>,
+        mic: Vec<f32>,
+        mic_rate: u32,
+    }
+
+    impl CaptureBackend for FileBackend {
+        fn open_system(
+            &self,
+            sample_rate: u32,
+            _device: Option<&str>,
+        ) -> Result<Box<dyn CaptureSource>, String> {
+            Ok(Box::new(FileSource::from_samples(
+                self.system.clone(),
+                sample_rate,
+                2,
+            )))
+        }
+
+        fn open_mic(&self, _device: Option<&str>) -> Result<Box<dyn CaptureSource>, String> {
+            Ok(Box::new(FileSource::from_samples(
+                self.mic.clone(),
+                self.mic_rate,
+                1,
+            )))
+        }
+    }
+
+    fn file_recorder() -> AudioRecorder {
+        AudioRecorder::with_backend(Box::new(FileBackend {
+            system: vec![0.5; 48000 * 2], // 1s stereo at 48kHz
+            mic: vec![0.25; 16000],       // 1s mono at 16kHz
+            mic_rate: 16000,
+        }))
+    }
+
+    #[test]
+    fn test_start_stop_recording_with_file_sources() {
+        let dir = std::env::temp_dir().join("audio_test_file_sources");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut recorder = file_recorder();
+        recorder.start_recording(&dir, None).unwrap();
+        assert!(recorder.is_recording());
+        assert!(recorder.get_stats().is_some());
+        // renamed in the library while still recording
+        SessionMetadata::update(&dir, |session| session.title = Some("Standup".into())).unwrap();
+
+        let output = recorder.stop_recording(None).unwrap();
+        assert!(!recorder.is_recording());
+        assert!(recorder.get_stats().is_none());
+        assert_eq!(
+            recorder.wait_for_mixdown().unwrap().mixed_file,
+            output.mixed_file
+        );
+
+        let system = hound::WavReader::open(&output.system_file).unwrap();
+        assert_eq!(system.spec().channels, 2);
+        assert_eq!(system.len(), 96000);
+
+        // mic is resampled 16kHz -> 48kHz
+        let mic = hound::WavReader::open(&output.mic_file).unwrap();
+        assert_eq!(mic.spec().channels, 1);
+        assert_eq!(mic.spec().sample_rate, 48000);
+        assert_eq!(mic.len(), 48000);
+
+        let mut mixed = hound::WavReader::open(&output.mixed_file).unwrap();
+        assert_eq!(mixed.len(), 96000);
+        // mid-file, away from the resampler's ramp in and out of the constant mic level
+        let middle: f32 = mixed.samples::<f32>().nth(48000).unwrap().unwrap();
+        assert!((middle - (0.5 * 0.7 + 0.25 * 0.3)).abs() < 1e-5);
+
+        // session.json describes where the audio came from and how it was mixed
+        let session = SessionMetadata::load(&dir).unwrap();
+        assert_eq!(session.app_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(session.title.as_deref(), Some("Standup"));
+        let tracks = session.tracks.unwrap();
+        assert_eq!(tracks.mic.source_sample_rate, 16000);
+        assert_eq!(tracks.mic.sample_rate, 48000);
+        assert_eq!(tracks.mic.samples, 48000);
+        assert_eq!(tracks.system.channels, 2);
+        assert_eq!(tracks.system.samples, 96000);
+        let mix = session.mix.unwrap();
+        assert_eq!((mix.system_gain, mix.mic_gain), (0.7, 0.3));
+        assert_eq!(mix.codec, StorageCodec::Wav);
+    }
+
+    #[test]
+    fn test_stop_recording_stores_configured_codec() {
+        let dir = std::env::temp_dir().join("audio_test_flac_storage");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut recorder = file_recorder();
+        recorder.apply_config(&AppConfig {
+            storage_codec: StorageCodec::Flac,
+            ..Default::default()
+        });
+        recorder.start_recording(&dir, None).unwrap();
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        assert_eq!(output.system_file, dir.join("system.flac"));
+        assert_eq!(output.mic_file, dir.join("mic.flac"));
+        assert_eq!(output.mixed_file, dir.join("mixed.flac"));
+        assert!(!dir.join("system.wav").exists());
+        assert!(!dir.join("mic.wav").exists());
+
+        assert_eq!(
+            codec::decode_file(&output.system_file)
+                .unwrap()
+                .samples
+                .len(),
+            96000
+        );
+        assert_eq!(
+            codec::decode_file(&output.mic_file).unwrap().samples.len(),
+            48000
+        );
+        let mixed = codec::decode_file(&output.mixed_file).unwrap();
+        assert_eq!(mixed.samples.len(), 96000);
+        assert!((mixed.samples[48000] - (0.5 * 0.7 + 0.25 * 0.3)).abs() < 1e-5);
+    }
+
+    fn write_wav(path: &Path, channels: u16, samples: &[f32]) {
+        let mut encoder = codec::create_encoder(StorageCodec::Wav, path, channels, 48000).unwrap();
+        encoder.write(samples).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[test]
+    fn test_generate_mixed_audio_pads_shorter_track() {
+        let dir = std::env::temp_dir().join("audio_test_mixdown");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // spans several mixing blocks, mic stops a third of the way in
+        let system_file = dir.join("system.wav");
+        let mic_file = dir.join("mic.wav");
+        write_wav(&system_file, 2, &vec![0.5; 40000 * 2]);
+        write_wav(&mic_file, 1, &vec![0.25; 13000]);
+
+        let mixed_file = dir.join("mixed.wav");
+        generate_mixed_audio(
+            &system_file,
+            &mic_file,
+            &mixed_file,
+            StorageCodec::Wav,
+            &MixSettings::default(),
+            None,
+        )
+        .unwrap();
+
+        let mut reader = hound::WavReader::open(&mixed_file).unwrap();
+        assert_eq!(reader.len(), 40000 * 2);
+        let mixed: Vec<f32> = reader.samples::<f32>().map(|s| s.unwrap()).collect();
+        assert!((mixed[0] - (0.5 * 0.7 + 0.25 * 0.3)).abs() < 1e-6);
+        assert!((mixed[13000 * 2 - 1] - (0.5 * 0.7 + 0.25 * 0.3)).abs() < 1e-6);
+        assert!((mixed[13000 * 2] - 0.5 * 0.7).abs() < 1e-6);
+        assert!((mixed[40000 * 2 - 1] - 0.5 * 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_soft_limit() {
+        // transparent below the threshold
+        assert_eq!(soft_limit(0.5), 0.5);
+        assert_eq!(soft_limit(-LIMITER_THRESHOLD), -LIMITER_THRESHOLD);
+        // never reaches full scale, keeps sign and ordering
+        assert!(soft_limit(5.0) <= 1.0);
+        assert!(soft_limit(0.9) < 0.9 && soft_limit(0.9) > LIMITER_THRESHOLD);
+        assert!(soft_limit(1.5) > soft_limit(1.2));
+        assert_eq!(soft_limit(-1.2), -soft_limit(1.2));
+    }
+
+    #[test]
+    fn test_normalize_mode_brings_tracks_to_target() {
+        let dir = std::env::temp_dir().join("audio_test_normalize");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let tone = |amplitude: f32, channels: u16| -> Vec<f32> {
+            (0..48000 * 3)
+                .flat_map(|i| {
+                    let s = (i as f32 * 1000.0 * std::f32::consts::TAU / 48000.0).sin() * amplitude;
+                    std::iter::repeat_n(s, channels as usize)
+                })
+                .collect()
+        };
+        // stereo 1 kHz at -23 dBFS reads -23 LUFS; mono at -35 dBFS reads -38 LUFS
+        let system_file = dir.join("system.wav");
+        let mic_file = dir.join("mic.wav");
+        write_wav(&system_file, 2, &tone(10f32.powf(-23.0 / 20.0), 2));
+        write_wav(&mic_file, 1, &tone(10f32.powf(-35.0 / 20.0), 1));
+
+        let mix = MixSettings {
+            mode: MixMode::Normalize,
+            target_lufs: -23.0,
+            ..Default::default()
         };
+        let (system_gain, mic_gain) = mix_gains(&system_file, &mic_file, &mix).unwrap();
+        assert!(
+            (system_gain - 1.0).abs() < 0.02,
+            "system gain {}",
+            system_gain
+        );
+        assert!((mic_gain - 5.62).abs() < 0.1, "mic gain {}", mic_gain);
 
-        let file =
-            File::create(output_path).map_err(|e| format!("Failed to create mixed.wav: {}", e))?;
-        let mut writer = WavWriter::new(BufWriter::new(file), spec)
-            .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+        // a quiet mic is not boosted past the cap
+        let mix = MixSettings {
+            target_lufs: -5.0,
+            ..mix
+        };
+        let (_, mic_gain) = mix_gains(&system_file, &mic_file, &mix).unwrap();
+        assert!((mic_gain - 10.0).abs() < 0.01, "mic gain {}", mic_gain);
 
-        // Mix: system audio (stereo) + mic (mono expanded to stereo)
-        // Use chunked writes for performance (~10-100x faster than sample-by-sample)
-        const CHUNK_SIZE: usize = 16384; // ~0.34 sec at 48kHz
-        let mut last_percent: f32 = 0.0;
-
-        for chunk_start in (0..max_frames).step_by(CHUNK_SIZE) {
-            let chunk_end = (chunk_start + CHUNK_SIZE).min(max_frames);
-
-            for i in chunk_start..chunk_end {
-                let sys_left = system_samples.get(i * 2).copied().unwrap_or(0.0);
-                let sys_right = system_samples.get(i * 2 + 1).copied().unwrap_or(0.0);
-                let mic = mic_samples.get(i).copied().unwrap_or(0.0);
-
-                // Mix: 70% system + 30% mic
-                let left = (sys_left * 0.7 + mic * 0.3).clamp(-1.0, 1.0);
-                let right = (sys_right * 0.7 + mic * 0.3).clamp(-1.0, 1.0);
-
-                writer
-                    .write_sample(left)
-                    .map_err(|e| format!("Failed to write sample: {}", e))?;
-                writer
-                    .write_sample(right)
-                    .map_err(|e| format!("Failed to write sample: {}", e))?;
-            }
+        // manual mode uses the configured gains as-is
+        let (system_gain, mic_gain) =
+            mix_gains(&system_file, &mic_file, &MixSettings::default()).unwrap();
+        assert_eq!((system_gain, mic_gain), (0.7, 0.3));
+    }
 
-            // emit progress every 1%
-            let percent = (chunk_end as f32 / max_frames as f32) * 100.0;
-            if let Some(app) = app {
-                if percent - last_percent >= 1.0 {
-                    last_percent = percent;
-                    let _ = app.emit(
-                        "mixing-progress",
-                        MixingProgress {
-                            current_frame: chunk_end as u64,
-                            total_frames: max_frames as u64,
-                            percent,
-                        },
-                    );
+    #[test]
+    fn test_start_recording_twice_fails() {
+        let dir = std::env::temp_dir().join("audio_test_start_twice");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut recorder = file_recorder();
+        recorder.start_recording(&dir, None).unwrap();
+        assert_eq!(
+            recorder.start_recording(&dir, None),
+            Err("Already recording".to_string())
+        );
+        recorder.stop_recording(None).unwrap();
+        assert!(recorder.stop_recording(None).is_err());
+    }
+
+    /// Backend that records which devices the recorder asked for
+    struct DeviceBackend {
+        inner: FileBackend,
+        requested: Arc<Mutex<Vec<Option<String>>>>,
+    }
+
+    impl CaptureBackend for DeviceBackend {
+        fn open_system(
+            &self,
+            sample_rate: u32,
+            device: Option<&str>,
+        ) -> Result<Box<dyn CaptureSource>, String> {
+            self.requested.lock().push(device.map(String::from));
+            self.inner.open_system(sample_rate, device)
+        }
+
+        fn open_mic(&self, device: Option<&str>) -> Result<Box<dyn CaptureSource>, String> {
+            self.requested.lock().push(device.map(String::from));
+            self.inner.open_mic(device)
+        }
+    }
+
+    #[test]
+    fn test_start_recording_opens_configured_devices() {
+        let dir = std::env::temp_dir().join("audio_test_devices");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let mut recorder = AudioRecorder::with_backend(Box::new(DeviceBackend {
+            inner: FileBackend {
+                system: vec![0.0; 4800],
+                mic: vec![0.0; 1600],
+                mic_rate: 16000,
+            },
+            requested: requested.clone(),
+        }));
+        recorder.apply_config(&AppConfig {
+            input_device: Some("alsa:hw:CARD=Headset,DEV=0".to_string()),
+            ..Default::default()
+        });
+        recorder.start_recording(&dir, None).unwrap();
+        recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        assert_eq!(
+            *requested.lock(),
+            vec![None, Some("alsa:hw:CARD=Headset,DEV=0".to_string())]
+        );
+    }
+
+    /// Source whose sink is handed back to the test so samples can be pushed by hand
+    struct ManualSource {
+        slot: Arc<Mutex<Option<SampleSink>>>,
+        error_slot: Arc<Mutex<Option<ErrorSink>>>,
+        channels: u16,
+        // deliver one last block while stopping, like a device flushing its buffer
+        flush_on_stop: Arc<AtomicBool>,
+    }
+
+    impl CaptureSource for ManualSource {
+        fn name(&self) -> String {
+            "Manual".to_string()
+        }
+
+        fn sample_rate(&self) -> u32 {
+            48000
+        }
+
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+
+        fn start(&mut self, sink: SampleSink, on_error: ErrorSink) -> Result<(), String> {
+            *self.slot.lock() = Some(sink);
+            *self.error_slot.lock() = Some(on_error);
+            Ok(())
+        }
+
+        fn stop(&mut self) {
+            if let Some(mut sink) = self.slot.lock().take() {
+                if self.flush_on_stop.load(Ordering::SeqCst) {
+                    sink(&vec![0.9; 480 * self.channels as usize], None);
                 }
             }
         }
+    }
 
-        writer
-            .finalize()
-            .map_err(|e| format!("Failed to finalize mixed.wav: {}", e))?;
+    #[derive(Default)]
+    struct ManualBackend {
+        system: Arc<Mutex<Option<SampleSink>>>,
+        mic: Arc<Mutex<Option<SampleSink>>>,
+        mic_error: Arc<Mutex<Option<ErrorSink>>>,
+        // cleared to simulate the mic being unplugged
+        mic_unavailable: Arc<AtomicBool>,
+        flush_on_stop: Arc<AtomicBool>,
+    }
 
-        Ok(())
+    impl CaptureBackend for ManualBackend {
+        fn open_system(
+            &self,
+            _sample_rate: u32,
+            _device: Option<&str>,
+        ) -> Result<Box<dyn CaptureSource>, String> {
+            Ok(Box::new(ManualSource {
+                slot: self.system.clone(),
+                error_slot: Arc::new(Mutex::new(None)),
+                channels: 2,
+                flush_on_stop: self.flush_on_stop.clone(),
+            }))
+        }
+
+        fn open_mic(&self, _device: Option<&str>) -> Result<Box<dyn CaptureSource>, String> {
+            if self.mic_unavailable.load(Ordering::SeqCst) {
+                return Err("No input device available".to_string());
+            }
+            Ok(Box::new(ManualSource {
+                slot: self.mic.clone(),
+                error_slot: self.mic_error.clone(),
+                channels: 1,
+                flush_on_stop: self.flush_on_stop.clone(),
+            }))
+        }
     }
 
-    pub fn is_recording(&self) -> bool {
-        *self.is_recording.lock()
+    fn push(slot: &Arc<Mutex<Option<SampleSink>>>, samples: &[f32]) {
+        let mut slot = slot.lock();
+        let sink = slot.as_mut().expect("source started");
+        sink(samples, None);
     }
-}
 
-impl Default for AudioRecorder {
-    fn default() -> Self {
-        Self::new()
+    /// Push `blocks` blocks of 480 frames, stamped as if each block took `block_secs`
+    fn push_timed(
+        slot: &Arc<Mutex<Option<SampleSink>>>,
+        start: Instant,
+        blocks: usize,
+        block_secs: f64,
+        block: &[f32],
+    ) {
+        let mut slot = slot.lock();
+        let sink = slot.as_mut().expect("source started");
+        for i in 0..blocks {
+            sink(
+                block,
+                Some(start + Duration::from_secs_f64(i as f64 * block_secs)),
+            );
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_pause_drops_samples_and_records_interval() {
+        let dir = std::env::temp_dir().join("audio_test_pause");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let system = Arc::new(Mutex::new(None));
+        let mic = Arc::new(Mutex::new(None));
+        let mut recorder = AudioRecorder::with_backend(Box::new(ManualBackend {
+            system: system.clone(),
+            mic: mic.clone(),
+            ..Default::default()
+        }));
+
+        assert!(recorder.pause_recording().is_err());
+        recorder.start_recording(&dir, None).unwrap();
+        push(&system, &[0.5; 200]);
+        push(&mic, &[0.25; 100]);
+
+        recorder.pause_recording().unwrap();
+        assert!(recorder.is_paused());
+        assert!(recorder.get_stats().unwrap().is_paused);
+        assert_eq!(
+            recorder.pause_recording(),
+            Err("Already paused".to_string())
+        );
+        push(&system, &[0.9; 200]);
+        push(&mic, &[0.9; 100]);
+
+        recorder.resume_recording().unwrap();
+        assert!(!recorder.is_paused());
+        assert_eq!(recorder.resume_recording(), Err("Not paused".to_string()));
+        push(&system, &[0.5; 200]);
+        push(&mic, &[0.25; 100]);
+
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        // the paused samples never reach disk
+        let mut system_reader = hound::WavReader::open(&output.system_file).unwrap();
+        assert_eq!(system_reader.len(), 400);
+        assert!(system_reader.samples::<f32>().all(|s| s.unwrap() == 0.5));
+        assert_eq!(hound::WavReader::open(&output.mic_file).unwrap().len(), 200);
+
+        let session = SessionMetadata::load(&dir).unwrap();
+        assert!(session.ended_at.is_some());
+        assert_eq!(session.pauses.len(), 1);
+        assert!(session.pauses[0].resumed_at.is_some());
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_mic_disconnect_reopens_and_pads_gap() {
+        let dir = std::env::temp_dir().join("audio_test_mic_disconnect");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let system = Arc::new(Mutex::new(None));
+        let mic = Arc::new(Mutex::new(None));
+        let mic_error = Arc::new(Mutex::new(None::<ErrorSink>));
+        let mic_unavailable = Arc::new(AtomicBool::new(false));
+        let mut recorder = AudioRecorder::with_backend(Box::new(ManualBackend {
+            system: system.clone(),
+            mic: mic.clone(),
+            mic_error: mic_error.clone(),
+            mic_unavailable: mic_unavailable.clone(),
+            ..Default::default()
+        }));
+
+        recorder.start_recording(&dir, None).unwrap();
+        push(&system, &[0.5; 9600]);
+        push(&mic, &[0.25; 4800]);
+
+        // unplug: the stream reports an error and no device can be opened for a while
+        mic_unavailable.store(true, Ordering::SeqCst);
+        let on_error = mic_error.lock().clone().unwrap();
+        on_error("Microphone disconnected".to_string());
+        // a second report from the same dead stream must not cause a second reopen
+        on_error("Microphone disconnected".to_string());
+        wait_until(|| mic.lock().is_none());
+
+        push(&system, &[0.5; 9600]);
+        mic_unavailable.store(false, Ordering::SeqCst);
+        wait_until(|| mic.lock().is_some());
+        push(&system, &[0.5; 9600]);
+        push(&mic, &[0.25; 4800]);
+
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        let samples: Vec<f32> = hound::WavReader::open(&output.mic_file)
+            .unwrap()
+            .samples::<f32>()
+            .map(|s| s.unwrap())
+            .collect();
+        assert_eq!(samples.len(), 14400);
+        assert!(samples[..4800].iter().all(|&s| s == 0.25));
+        assert!(samples[4800..9600].iter().all(|&s| s == 0.0));
+        assert!(samples[9600..].iter().all(|&s| s == 0.25));
+    }
+
+    fn manual_recorder(dir: &Path) -> (AudioRecorder, ManualBackend) {
+        let handles = ManualBackend::default();
+        let mut recorder = AudioRecorder::with_backend(Box::new(ManualBackend {
+            system: handles.system.clone(),
+            mic: handles.mic.clone(),
+            flush_on_stop: handles.flush_on_stop.clone(),
+            ..Default::default()
+        }));
+        let _ = std::fs::remove_dir_all(dir);
+        recorder.start_recording(&dir.to_path_buf(), None).unwrap();
+        (recorder, handles)
+    }
+
+    #[test]
+    fn test_stop_while_paused_keeps_paused_samples_out() {
+        let dir = std::env::temp_dir().join("audio_test_stop_paused");
+        let (mut recorder, backend) = manual_recorder(&dir);
+
+        push(&backend.system, &[0.5; 200]);
+        push(&backend.mic, &[0.25; 100]);
+        recorder.pause_recording().unwrap();
+        push(&backend.system, &[0.9; 200]);
+        push(&backend.mic, &[0.9; 100]);
+
+        // the sources still deliver a block while they are being stopped
+        backend.flush_on_stop.store(true, Ordering::SeqCst);
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+        assert!(!recorder.is_paused());
+
+        let mut system = hound::WavReader::open(&output.system_file).unwrap();
+        assert_eq!(system.len(), 200);
+        assert!(system.samples::<f32>().all(|s| s.unwrap() == 0.5));
+        let mut mic = hound::WavReader::open(&output.mic_file).unwrap();
+        assert_eq!(mic.len(), 100);
+        assert!(mic.samples::<f32>().all(|s| s.unwrap() == 0.25));
+    }
 
     #[test]
-    fn test_resample_identity() {
-        // Same rate should return same data
-        let input = vec![0.1, 0.2, 0.3, 0.4, 0.5];
-        let output = resample(&input, 48000, 48000);
-        assert_eq!(input, output);
+    fn test_late_mic_start_is_padded_and_recorded() {
+        let dir = std::env::temp_dir().join("audio_test_late_mic");
+        let (mut recorder, backend) = manual_recorder(&dir);
+
+        // 1 s of system audio from the start, the mic only kicks in 200 ms later
+        let start = Instant::now();
+        push_timed(&backend.system, start, 100, 0.01, &[0.5; 960]);
+        let mic_start = start + Duration::from_millis(200);
+        push_timed(&backend.mic, mic_start, 80, 0.01, &[0.25; 480]);
+
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        let mic: Vec<f32> = hound::WavReader::open(&output.mic_file)
+            .unwrap()
+            .samples::<f32>()
+            .map(|s| s.unwrap())
+            .collect();
+        let lead_in = mic.iter().position(|&s| s != 0.0).unwrap();
+        // 200 ms, plus however long start_recording took before `start`
+        assert!((9600..9600 + 480).contains(&lead_in), "lead-in {}", lead_in);
+        assert_eq!(mic.len(), lead_in + 80 * 480);
+
+        let alignment = SessionMetadata::load(&dir).unwrap().alignment.unwrap();
+        let offset = alignment.mic_offset_secs.unwrap();
+        assert!((offset - 0.2).abs() < 0.005, "offset {}", offset);
+        assert_eq!(alignment.mic.dropped_secs, 0.0);
     }
 
     #[test]
-    fn test_resample_empty() {
-        let input: Vec<f32> = vec![];
-        let output = resample(&input, 48000, 16000);
-        assert!(output.is_empty());
+    fn test_fast_device_clock_is_trimmed_without_reacting_to_jitter() {
+        let dir = std::env::temp_dir().join("audio_test_fast_clock");
+        let (mut recorder, backend) = manual_recorder(&dir);
+
+        // 20 s of mic blocks that each carry 490 frames per 10 ms (2% fast), with
+        // callbacks firing up to 5 ms early or late
+        let start = Instant::now();
+        for i in 0..2000u64 {
+            let jitter = [0.0, 0.005, -0.005, 0.002][i as usize % 4];
+            let at = start + Duration::from_secs_f64((i as f64 * 0.01 + jitter).max(0.0));
+            (backend.mic.lock().as_mut().unwrap())(&[0.25; 490], Some(at));
+            // a real device takes 20 s for this; give the writer a chance to keep up
+            if i % 100 == 99 {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        }
+        assert_eq!(recorder.get_stats().unwrap().overruns, 0);
+
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        let frames = hound::WavReader::open(&output.mic_file).unwrap().len() as f64;
+        // the clock says 20 s; the device delivered 20.4 s worth
+        assert!(
+            (frames / 48000.0 - 20.0).abs() < ALIGN_TOLERANCE_SECS + 0.02,
+            "{}",
+            frames
+        );
+
+        let alignment = SessionMetadata::load(&dir).unwrap().alignment.unwrap();
+        assert!(alignment.mic.dropped_secs > 0.3, "{:?}", alignment.mic);
+        // jitter alone never pads; the only silence is the few ms before the first block
+        assert!(alignment.mic.padded_secs < 0.01, "{:?}", alignment.mic);
     }
 
     #[test]
-    fn test_resample_downsample() {
-        // 48kHz -> 16kHz should produce ~1/3 the samples
-        let input: Vec<f32> = (0..4800).map(|i| (i as f32 / 4800.0)).collect();
-        let output = resample(&input, 48000, 16000);
+    fn test_overrun_drops_whole_blocks_and_is_counted() {
+        let dir = std::env::temp_dir().join("audio_test_overrun");
+        let (mut recorder, backend) = manual_recorder(&dir);
+
+        push(&backend.system, &[0.5; 960]);
+        // more than the ring can ever hold, as if the writer had stalled for seconds
+        push(&backend.system, &vec![0.9; RING_SECS * 48000 * 2 + 2]);
+        push(&backend.system, &[0.5; 960]);
+        assert_eq!(recorder.get_stats().unwrap().overruns, 1);
+
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        // the blocks either side of the dropped one arrive intact and in order
+        let system: Vec<f32> = hound::WavReader::open(&output.system_file)
+            .unwrap()
+            .samples::<f32>()
+            .map(|s| s.unwrap())
+            .collect();
+        assert_eq!(system, vec![0.5; 1920]);
+    }
+
+    /// 300 Hz square wave at +-0.1, loud enough to count as someone speaking
+    fn speech(frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| if i % 160 < 80 { 0.1 } else { -0.1 })
+            .collect()
+    }
+
+    /// Session clock that only moves when the test advances it
+    #[derive(Clone)]
+    struct ManualClock(Arc<Mutex<Instant>>);
+
+    impl ManualClock {
+        fn new() -> Self {
+            Self(Arc::new(Mutex::new(Instant::now())))
+        }
 
-        // Expected length: 4800 * (16000/48000) = 1600
-        assert_eq!(output.len(), 1600);
+        fn advance(&self, secs: f64) {
+            *self.0.lock() += Duration::from_secs_f64(secs);
+        }
 
-        // First and last samples should be approximately preserved
-        assert!((output[0] - input[0]).abs() < 0.01);
+        fn now(&self) -> Now {
+            let time = self.0.clone();
+            Arc::new(move || *time.lock())
+        }
+    }
+
+    fn auto_recorder(
+        dir: &Path,
+        settings: AutoRecordSettings,
+    ) -> (
+        AudioRecorder,
+        ManualBackend,
+        Arc<Mutex<Vec<AutoRecordEvent>>>,
+        ManualClock,
+    ) {
+        let handles = ManualBackend::default();
+        let mut recorder = AudioRecorder::with_backend(Box::new(ManualBackend {
+            system: handles.system.clone(),
+            mic: handles.mic.clone(),
+            ..Default::default()
+        }));
+        let clock = ManualClock::new();
+        recorder.set_clock(clock.now());
+        recorder.apply_config(&AppConfig {
+            auto_record: settings,
+            ..Default::default()
+        });
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        recorder.set_auto_record_handler(Arc::new(move |_: &Path, event| seen.lock().push(event)));
+        let _ = std::fs::remove_dir_all(dir);
+        recorder.start_recording(&dir.to_path_buf(), None).unwrap();
+        (recorder, handles, events, clock)
     }
 
     #[test]
-    fn test_resample_upsample() {
-        // 16kHz -> 48kHz should produce ~3x the samples
-        let input: Vec<f32> = (0..1600).map(|i| (i as f32 / 1600.0)).collect();
-        let output = resample(&input, 16000, 48000);
+    fn test_armed_session_starts_writing_on_voice() {
+        let dir = std::env::temp_dir().join("audio_test_armed");
+        let (mut recorder, backend, events, _) = auto_recorder(
+            &dir,
+            AutoRecordSettings {
+                start_on_voice: true,
+                ..Default::default()
+            },
+        );
+
+        // a second of room noise: kept in memory as pre-roll, never written. Whether
+        // or not the writer has got to it yet, nothing may be on disk; that noise
+        // never started the session is checked by the layout of mic.wav below.
+        for _ in 0..100 {
+            push(&backend.mic, &[0.001; 480]);
+        }
+        let stats = recorder.get_stats().unwrap();
+        assert!(stats.is_armed);
+        assert_eq!(stats.mic_samples_written, 0);
 
-        // Expected length: 1600 * (48000/16000) = 4800
-        assert_eq!(output.len(), 4800);
+        let voice = speech(24000);
+        for block in voice.chunks(480) {
+            push(&backend.mic, block);
+        }
+        wait_until(|| !events.lock().is_empty());
+        assert_eq!(*events.lock(), vec![AutoRecordEvent::Started]);
+        assert!(!recorder.get_stats().unwrap().is_armed);
+        push(&backend.mic, &[0.001; 4800]);
+
+        let output = recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
+
+        let mic: Vec<f32> = hound::WavReader::open(&output.mic_file)
+            .unwrap()
+            .samples::<f32>()
+            .map(|s| s.unwrap())
+            .collect();
+        // all of the voice, at most half a second of the noise before it, and
+        // everything after
+        let voice_start = mic.iter().position(|&s| s.abs() == 0.1).unwrap();
+        assert!(voice_start > 0 && voice_start <= 24000, "{}", voice_start);
+        assert_eq!(mic[voice_start..voice_start + 24000], voice[..]);
+        assert_eq!(mic.len(), voice_start + 24000 + 4800);
     }
 
     #[test]
-    fn test_resample_interpolation() {
-        // Test that downsampling interpolates between values
-        // 4 samples at 4Hz -> 2 samples at 2Hz
-        let input = vec![0.0, 0.5, 1.0, 0.5];
-        let output = resample(&input, 4, 2);
-
-        // Should have 2 samples
-        assert_eq!(output.len(), 2);
-        // First sample should be 0.0 (or close to it)
-        assert!((output[0] - 0.0).abs() < 0.01);
+    fn test_silence_timeout_asks_for_stop() {
+        let dir = std::env::temp_dir().join("audio_test_auto_stop");
+        let (mut recorder, backend, events, clock) = auto_recorder(
+            &dir,
+            AutoRecordSettings {
+                stop_after_silent_secs: 1,
+                ..Default::default()
+            },
+        );
+
+        for block in speech(24000).chunks(480) {
+            push(&backend.mic, block);
+        }
+        wait_until(|| recorder.get_stats().unwrap().mic_samples_written == 24000);
+        // still talking recently enough, however long the writer takes to notice
+        clock.advance(0.5);
+        assert!(events.lock().is_empty());
+
+        // quiet from here on: the clock keeps moving until the writer gives up
+        wait_until(|| {
+            clock.advance(0.05);
+            !events.lock().is_empty()
+        });
+        match events.lock()[0] {
+            AutoRecordEvent::SilenceTimeout { silent_secs } => assert!(silent_secs >= 1.0),
+            ref other => panic!("unexpected {:?}", other),
+        }
+        // only asked once, however long it stays quiet
+        clock.advance(10.0);
+        push(&backend.mic, &[0.0; 480]);
+        wait_until(|| recorder.get_stats().unwrap().mic_samples_written == 24480);
+        assert_eq!(events.lock().len(), 1);
+
+        recorder.stop_recording(None).unwrap();
+        recorder.wait_for_mixdown().unwrap();
     }
 }
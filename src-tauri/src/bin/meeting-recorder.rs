@@ -0,0 +1,319 @@
+//! Headless command-line interface for scripting and batch reprocessing.
+//!
+//!     meeting-recorder record [--duration SECS]
+//!     meeting-recorder transcribe <dir>
+//!     meeting-recorder summarize <dir>
+//!     meeting-recorder export <dir> [--output FILE]
+//!     meeting-recorder list
+//!     meeting-recorder recover
+
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tauri_app_lib::artifacts;
+use tauri_app_lib::audio::{self, AudioRecorder, AutoRecordEvent};
+use tauri_app_lib::codec;
+use tauri_app_lib::config::AppConfig;
+use tauri_app_lib::export;
+use tauri_app_lib::library::{Library, MeetingDetails};
+use tauri_app_lib::recovery;
+use tauri_app_lib::session::{ExportInfo, SessionMetadata};
+use tauri_app_lib::summarize::{self, SummaryProgress};
+use tauri_app_lib::transcribe::{self, TranscriptionProgress, TranscriptionResult};
+
+#[derive(Parser)]
+#[command(
+    name = "meeting-recorder",
+    version,
+    about = "Record, transcribe and summarize meetings without the GUI"
+)]
+struct Cli {
+    /// Recordings directory (defaults to ~/Documents/MeetingRecordings)
+    #[arg(long, global = true)]
+    recordings_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Record system audio + mic until Enter is pressed or --duration elapses
+    Record {
+        /// Stop automatically after this many seconds
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+    /// Transcribe a recording directory and write transcript.json
+    Transcribe { dir: PathBuf },
+    /// Summarize a recording's transcript and write summary.json
+    Summarize { dir: PathBuf },
+    /// Export transcript and summary as markdown (stdout unless --output is given)
+    Export {
+        dir: PathBuf,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// List recordings with their title, length and which artifacts they have
+    List,
+    /// Repair recordings left unfinished by a crash and regenerate their mixed track
+    Recover,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let recordings_dir = cli.recordings_dir.unwrap_or_else(AppConfig::recordings_dir);
+    let config = AppConfig::load();
+
+    let result = match cli.command {
+        Command::Record { duration } => record(&recordings_dir, &config, duration),
+        Command::Transcribe { dir } => transcribe_dir(&dir),
+        Command::Summarize { dir } => summarize_dir(&dir),
+        Command::Export { dir, output } => export_dir(&dir, output.as_deref()),
+        Command::List => list(&recordings_dir),
+        Command::Recover => {
+            let recovered = recovery::recover_unfinished_sessions(&recordings_dir, &config);
+            println!("Recovered {} recording(s)", recovered.len());
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn record(recordings_dir: &Path, config: &AppConfig, duration: Option<u64>) -> Result<(), String> {
+    std::fs::create_dir_all(recordings_dir)
+        .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+
+    let recording_dir = audio::timestamped_dir(recordings_dir);
+    let mut recorder = AudioRecorder::new();
+    recorder.apply_config(config);
+
+    // Enter on stdin stops the recording. Under cron stdin is usually closed, in
+    // which case the sender is dropped and only --duration or the silence
+    // timeout can end the session.
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    if config.auto_record.stop_after_silent_secs > 0 {
+        let stop_tx = stop_tx.clone();
+        recorder.set_auto_record_handler(Arc::new(move |_: &Path, event| {
+            if let AutoRecordEvent::SilenceTimeout { .. } = event {
+                let _ = stop_tx.send(());
+            }
+        }));
+    }
+    recorder.start_recording(&recording_dir, None)?;
+
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        if let Ok(n) = std::io::stdin().read_line(&mut line) {
+            if n > 0 {
+                let _ = stop_tx.send(());
+            }
+        }
+    });
+
+    match duration {
+        Some(secs) => {
+            println!(
+                "Recording to {} for {}s (press Enter to stop early)",
+                recording_dir.display(),
+                secs
+            );
+            let deadline = Instant::now() + Duration::from_secs(secs);
+            if let Err(mpsc::RecvTimeoutError::Disconnected) =
+                stop_rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            }
+        }
+        None => {
+            println!(
+                "Recording to {} (press Enter to stop)",
+                recording_dir.display()
+            );
+            if stop_rx.recv().is_err() {
+                eprintln!(
+                    "stdin closed; stopping. Pass --duration when running non-interactively."
+                );
+            }
+        }
+    }
+
+    recorder.stop_recording(None)?;
+    println!("Mixing...");
+    let output = recorder.wait_for_mixdown()?;
+    println!("Recording saved to: {}", output.directory.display());
+    Ok(())
+}
+
+fn transcribe_dir(dir: &Path) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel::<TranscriptionProgress>();
+    let progress = std::thread::spawn(move || {
+        while let Ok(p) = rx.recv() {
+            eprint!(
+                "\rTranscribing {}: {:>3}% ({:.0}% overall)",
+                p.phase, p.file_percent, p.overall_percent
+            );
+        }
+        eprintln!();
+    });
+
+    // whisper needs a large stack, same as the transcribe_recording command
+    let dir_owned = dir.to_path_buf();
+    let result = std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(move || transcribe::transcribe_recording_dir_with_progress(&dir_owned, Some(tx)))
+        .map_err(|e| format!("Spawn failed: {}", e))?
+        .join()
+        .map_err(|_| "Transcription thread panicked".to_string())??;
+    let _ = progress.join();
+
+    let path = dir.join(artifacts::TRANSCRIPT_FILE);
+    println!(
+        "{} segments ({:.1}s) written to {}",
+        result.segments.len(),
+        result.duration,
+        path.display()
+    );
+    Ok(())
+}
+
+fn summarize_dir(dir: &Path) -> Result<(), String> {
+    let transcript = load_transcript(dir)?;
+
+    let (tx, rx) = mpsc::channel::<SummaryProgress>();
+    let progress = std::thread::spawn(move || {
+        while let Ok(p) = rx.recv() {
+            let stage = if p.combining {
+                " combining parts".to_string()
+            } else if p.parts > 1 {
+                format!(" part {}/{}", p.part, p.parts)
+            } else {
+                String::new()
+            };
+            eprint!("\rSummarizing{}: {} tokens", stage, p.tokens);
+        }
+        eprintln!();
+    });
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to start runtime: {}", e))?;
+    let result = rt.block_on(summarize::summarize_recording_with_progress(
+        dir,
+        &transcript,
+        Some(tx),
+        None,
+    ));
+    let _ = progress.join();
+    result?;
+
+    let previous = artifacts::summary_versions(dir).len();
+    let path = dir.join(artifacts::SUMMARY_FILE);
+    if previous > 0 {
+        println!(
+            "Summary written to {} ({} earlier version(s) kept)",
+            path.display(),
+            previous
+        );
+    } else {
+        println!("Summary written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Look up a recording directory in the library it belongs to, so its title and
+/// session are read the same way the app reads them
+fn meeting_details(dir: &Path) -> Result<MeetingDetails, String> {
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to open {}: {}", dir.display(), e))?;
+    match (dir.parent(), dir.file_name()) {
+        (Some(root), Some(id)) => Library::new(root.to_path_buf()).get(&id.to_string_lossy()),
+        _ => Err(format!("{} is not a recording directory", dir.display())),
+    }
+}
+
+fn export_dir(dir: &Path, output: Option<&Path>) -> Result<(), String> {
+    let transcript = load_transcript(dir)?;
+    let summary = artifacts::load_summary(dir)?;
+
+    // recordings from before session.json have no times to show
+    let details = meeting_details(dir)?;
+    let markdown = export::to_markdown(
+        &details.meeting.title,
+        &transcript,
+        summary.as_ref(),
+        details.session.as_ref(),
+    );
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, markdown)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            println!("Exported to {}", path.display());
+            let export = ExportInfo {
+                path: std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+                exported_at: chrono::Local::now(),
+            };
+            SessionMetadata::update(dir, |session| session.exports.push(export))?;
+        }
+        None => print!("{}", markdown),
+    }
+    Ok(())
+}
+
+fn list(recordings_dir: &Path) -> Result<(), String> {
+    let meetings = Library::new(recordings_dir.to_path_buf()).list()?;
+    if meetings.is_empty() {
+        println!("No recordings in {}", recordings_dir.display());
+        return Ok(());
+    }
+
+    for meeting in meetings {
+        let dir = recordings_dir.join(&meeting.id);
+        let transcript = if dir.join(artifacts::EDITED_TRANSCRIPT_FILE).exists() {
+            "edited"
+        } else if meeting.has_transcript {
+            "yes"
+        } else {
+            "-"
+        };
+        let secs = meeting.duration_secs.round() as u64;
+        println!(
+            "{}  {}  {}:{:02}:{:02}  mixed={} transcript={} summary={}{}",
+            meeting.id,
+            meeting.title,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            codec::find_track(&dir, "mixed")
+                .and_then(|p| p.extension().map(|e| e.to_string_lossy().to_string()))
+                .unwrap_or_else(|| "-".to_string()),
+            transcript,
+            if meeting.has_summary { "yes" } else { "-" },
+            if meeting.in_progress {
+                "  (unfinished)"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Load the edited transcript if the user saved one, otherwise the raw transcript
+fn load_transcript(dir: &Path) -> Result<TranscriptionResult, String> {
+    match artifacts::load_transcript(dir)? {
+        Some((transcript, _)) => Ok(transcript),
+        None => Err(format!(
+            "No transcript in {}; run `meeting-recorder transcribe` first",
+            dir.display()
+        )),
+    }
+}
@@ -0,0 +1,126 @@
+use super::{CaptureSource, ErrorSink, SampleSink};
+use hound::WavReader;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Block size pushed per callback (~10ms at 48kHz), mirroring a real device
+const FRAMES_PER_BLOCK: usize = 480;
+
+/// Replays samples from a WAV file or an in-memory buffer.
+///
+/// By default every sample is pushed synchronously inside `start()`, which makes
+/// recorder tests deterministic. `realtime(true)` paces the blocks on a thread
+/// like a live device would.
+pub struct FileSource {
+    name: String,
+    samples: Arc<Vec<f32>>,
+    sample_rate: u32,
+    channels: u16,
+    realtime: bool,
+    stop_flag: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FileSource {
+    /// Load a WAV file into memory
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let reader =
+            WavReader::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+        let spec = reader.spec();
+
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .filter_map(|s| s.ok())
+                .collect(),
+            hound::SampleFormat::Int => {
+                let max_val = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .filter_map(|s| s.ok())
+                    .map(|s| s as f32 / max_val)
+                    .collect()
+            }
+        };
+
+        let mut source = Self::from_samples(samples, spec.sample_rate, spec.channels);
+        source.name = path.to_string_lossy().to_string();
+        Ok(source)
+    }
+
+    /// Synthetic source from interleaved samples
+    pub fn from_samples(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            name: "Synthetic".to_string(),
+            samples: Arc::new(samples),
+            sample_rate,
+            channels,
+            realtime: false,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+
+    /// Pace blocks at the source's sample rate on a background thread
+    pub fn realtime(mut self, realtime: bool) -> Self {
+        self.realtime = realtime;
+        self
+    }
+}
+
+impl CaptureSource for FileSource {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn start(&mut self, mut sink: SampleSink, _on_error: ErrorSink) -> Result<(), String> {
+        let block_len = FRAMES_PER_BLOCK * self.channels.max(1) as usize;
+
+        if !self.realtime {
+            for block in self.samples.chunks(block_len) {
+                sink(block, None);
+            }
+            return Ok(());
+        }
+
+        let samples = self.samples.clone();
+        let stop_flag = self.stop_flag.clone();
+        let block_duration =
+            Duration::from_secs_f64(FRAMES_PER_BLOCK as f64 / self.sample_rate as f64);
+        stop_flag.store(false, Ordering::SeqCst);
+
+        let handle = std::thread::Builder::new()
+            .name("file-capture".to_string())
+            .spawn(move || {
+                for block in samples.chunks(block_len) {
+                    if stop_flag.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    sink(block, Some(Instant::now()));
+                    std::thread::sleep(block_duration);
+                }
+            })
+            .map_err(|e| format!("Spawn failed: {}", e))?;
+
+        self.thread = Some(handle);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use super::{CaptureSource, DeviceInfo, ErrorSink, SampleSink};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::time::Instant;
+
+/// Microphone input via cpal, captured as mono at the device's default rate
+pub struct CpalMicSource {
+    device: cpal::Device,
+    supported_config: cpal::SupportedStreamConfig,
+    stream: Option<cpal::Stream>,
+}
+
+impl CpalMicSource {
+    /// Open the host's default input device
+    pub fn open_default() -> Result<Self, String> {
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or("No input device available")?;
+        Self::from_device(device)
+    }
+
+    /// Open the input with the given `DeviceInfo::id` (or name), or the default input
+    /// if it isn't given or is no longer connected
+    pub fn open(device: Option<&str>) -> Result<Self, String> {
+        let Some(wanted) = device else {
+            return Self::open_default();
+        };
+        let host = cpal::default_host();
+        let found = host
+            .input_devices()
+            .map_err(|e| format!("Failed to list input devices: {}", e))?
+            .find(|d| device_info(d, None).is_some_and(|info| info.matches(wanted)));
+        match found {
+            Some(device) => Self::from_device(device),
+            None => {
+                eprintln!("Input device {} not found, using default input", wanted);
+                Self::open_default()
+            }
+        }
+    }
+
+    fn from_device(device: cpal::Device) -> Result<Self, String> {
+        let supported_config = device
+            .default_input_config()
+            .map_err(|e| format!("Failed to get default input config: {}", e))?;
+
+        println!("Mic sample rate: {} Hz", supported_config.sample_rate());
+
+        Ok(Self {
+            device,
+            supported_config,
+            stream: None,
+        })
+    }
+}
+
+/// Describe a device, or None if it has no usable input config (e.g. unplugged mid-listing)
+fn device_info(device: &cpal::Device, default_id: Option<&str>) -> Option<DeviceInfo> {
+    let id = device.id().ok()?.to_string();
+    let name = device
+        .description()
+        .map(|d| d.name().to_string())
+        .unwrap_or_else(|_| id.clone());
+    let config = device.default_input_config().ok()?;
+    Some(DeviceInfo {
+        is_default: default_id == Some(id.as_str()),
+        id,
+        name,
+        channels: config.channels(),
+        sample_rate: config.sample_rate(),
+    })
+}
+
+/// When the first frame of a callback was captured: cpal reports capture and callback
+/// times on the device clock, so their difference is the input latency
+fn captured_at(info: &cpal::InputCallbackInfo) -> Instant {
+    let timestamp = info.timestamp();
+    let latency = timestamp
+        .callback
+        .duration_since(&timestamp.capture)
+        .unwrap_or_default();
+    Instant::now() - latency
+}
+
+/// Stream error callback: a vanished device is reported to `on_error` so the recorder
+/// can reopen it, anything else (e.g. an xrun) is transient and only logged
+fn error_callback(on_error: ErrorSink) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    move |err| match err {
+        cpal::StreamError::DeviceNotAvailable => on_error("Microphone disconnected".to_string()),
+        err => eprintln!("Mic stream error: {}", err),
+    }
+}
+
+/// All input devices of the default host
+pub fn list_devices() -> Result<Vec<DeviceInfo>, String> {
+    let host = cpal::default_host();
+    let default_id = host
+        .default_input_device()
+        .and_then(|d| d.id().ok())
+        .map(|id| id.to_string());
+    let devices = host
+        .input_devices()
+        .map_err(|e| format!("Failed to list input devices: {}", e))?;
+    Ok(devices
+        .filter_map(|d| device_info(&d, default_id.as_deref()))
+        .collect())
+}
+
+impl CaptureSource for CpalMicSource {
+    fn name(&self) -> String {
+        self.device
+            .description()
+            .map(|d| d.name().to_string())
+            .unwrap_or_else(|_| "Unknown input device".to_string())
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.supported_config.sample_rate()
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn start(&mut self, mut sink: SampleSink, on_error: ErrorSink) -> Result<(), String> {
+        let config = cpal::StreamConfig {
+            channels: self.channels(),
+            sample_rate: self.supported_config.sample_rate(),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let stream = match self.supported_config.sample_format() {
+            cpal::SampleFormat::F32 => self
+                .device
+                .build_input_stream(
+                    &config,
+                    move |data: &[f32], info: &cpal::InputCallbackInfo| {
+                        sink(data, Some(captured_at(info)))
+                    },
+                    error_callback(on_error),
+                    None,
+                )
+                .map_err(|e| format!("Failed to build mic stream: {}", e))?,
+            cpal::SampleFormat::I16 => {
+                // converted in place; 100 ms up front, so it only grows for huge buffers
+                let mut float_samples = Vec::with_capacity(self.sample_rate() as usize / 10);
+                self.device
+                    .build_input_stream(
+                        &config,
+                        move |data: &[i16], info: &cpal::InputCallbackInfo| {
+                            float_samples.clear();
+                            float_samples.extend(data.iter().map(|&s| s as f32 / 32768.0));
+                            sink(&float_samples, Some(captured_at(info)));
+                        },
+                        error_callback(on_error),
+                        None,
+                    )
+                    .map_err(|e| format!("Failed to build mic stream: {}", e))?
+            }
+            format => return Err(format!("Unsupported sample format: {:?}", format)),
+        };
+
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start mic stream: {}", e))?;
+
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        // dropping the stream stops it
+        self.stream.take();
+    }
+}
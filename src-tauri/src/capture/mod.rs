@@ -0,0 +1,196 @@
+//! Audio capture backends.
+//!
+//! A `CaptureSource` produces interleaved f32 samples at its own native rate and
+//! channel count and pushes them into a `SampleSink`. The recorder owns resampling,
+//! channel mapping and disk I/O, so a new platform is just another `CaptureSource`.
+
+mod file;
+mod mic_cpal;
+#[cfg(target_os = "linux")]
+mod system_linux;
+#[cfg(target_os = "macos")]
+mod system_macos;
+
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[allow(unused_imports)] // used by recorder tests
+pub use file::FileSource;
+pub use mic_cpal::CpalMicSource;
+#[cfg(target_os = "linux")]
+pub use system_linux::PulseMonitorSource;
+#[cfg(target_os = "macos")]
+pub use system_macos::ScreenCaptureSource;
+
+/// An input the user can pick in the settings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceInfo {
+    /// Stable identifier stored in the config
+    pub id: String,
+    pub name: String,
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub is_default: bool,
+}
+
+impl DeviceInfo {
+    /// Whether a configured device refers to this one. Older configs and hand-edited
+    /// ones may hold the display name instead of the id.
+    pub fn matches(&self, wanted: &str) -> bool {
+        self.id == wanted || self.name == wanted
+    }
+}
+
+/// Callback that receives interleaved f32 samples from a capture source, plus when
+/// the first frame was captured. Live sources stamp every block on the monotonic
+/// clock so the recorder can keep tracks aligned; `None` means "no timing", and the
+/// samples are written back to back (file replay in tests).
+///
+/// Called on the capture thread, so it must not block: the recorder's sinks only
+/// copy into a lock-free ring buffer.
+pub type SampleSink = Box<dyn FnMut(&[f32], Option<Instant>) + Send>;
+
+/// Callback a source uses to report that it stopped delivering samples mid-stream
+/// (e.g. the device was unplugged). Called from the capture thread.
+pub type ErrorSink = Arc<dyn Fn(String) + Send + Sync>;
+
+/// A source of audio samples (system output, microphone, file, ...)
+pub trait CaptureSource: Send {
+    /// Human-readable name of the device or source
+    fn name(&self) -> String;
+
+    /// Native sample rate of the samples pushed into the sink
+    fn sample_rate(&self) -> u32;
+
+    /// Number of interleaved channels pushed into the sink
+    fn channels(&self) -> u16;
+
+    /// Begin pushing samples into `sink`; fatal stream errors go to `on_error`
+    fn start(&mut self, sink: SampleSink, on_error: ErrorSink) -> Result<(), String>;
+
+    /// Stop capturing. No samples are pushed after this returns.
+    fn stop(&mut self);
+}
+
+/// Opens the system and microphone sources for a recording session
+pub trait CaptureBackend: Send + Sync {
+    /// Open the system (speaker/output) audio source. `device` is a `DeviceInfo::id`
+    /// from `system_devices()`; if it is gone the default output is used instead.
+    fn open_system(
+        &self,
+        sample_rate: u32,
+        device: Option<&str>,
+    ) -> Result<Box<dyn CaptureSource>, String>;
+
+    /// Open the microphone source, falling back to the default input like `open_system`
+    fn open_mic(&self, device: Option<&str>) -> Result<Box<dyn CaptureSource>, String>;
+}
+
+/// Backend for the current OS: ScreenCaptureKit (macOS) or a PulseAudio/PipeWire
+/// monitor (Linux) for system audio, cpal for the mic
+pub struct PlatformBackend;
+
+impl CaptureBackend for PlatformBackend {
+    #[cfg(target_os = "macos")]
+    fn open_system(
+        &self,
+        sample_rate: u32,
+        device: Option<&str>,
+    ) -> Result<Box<dyn CaptureSource>, String> {
+        let source = ScreenCaptureSource::new(sample_rate);
+        match device.map(|id| id.parse::<u32>()) {
+            Some(Ok(display_id)) => Ok(Box::new(source.with_display(display_id))),
+            Some(Err(_)) => {
+                eprintln!(
+                    "Ignoring invalid display id {:?}, using main display",
+                    device
+                );
+                Ok(Box::new(source))
+            }
+            None => Ok(Box::new(source)),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn open_system(
+        &self,
+        sample_rate: u32,
+        device: Option<&str>,
+    ) -> Result<Box<dyn CaptureSource>, String> {
+        let source = PulseMonitorSource::new(sample_rate);
+        let Some(wanted) = device else {
+            return Ok(Box::new(source));
+        };
+        match system_devices() {
+            Ok(devices) => match devices.iter().find(|d| d.matches(wanted)) {
+                Some(found) => Ok(Box::new(source.with_device(&found.id))),
+                None => {
+                    eprintln!("Output {} not found, using default output", wanted);
+                    Ok(Box::new(source))
+                }
+            },
+            // can't tell whether it exists; let start() report a bad name
+            Err(_) => Ok(Box::new(source.with_device(wanted))),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    fn open_system(
+        &self,
+        _sample_rate: u32,
+        _device: Option<&str>,
+    ) -> Result<Box<dyn CaptureSource>, String> {
+        Err("System audio capture is not supported on this platform".to_string())
+    }
+
+    fn open_mic(&self, device: Option<&str>) -> Result<Box<dyn CaptureSource>, String> {
+        Ok(Box::new(CpalMicSource::open(device)?))
+    }
+}
+
+/// Input devices the mic can be recorded from
+pub fn input_devices() -> Result<Vec<DeviceInfo>, String> {
+    mic_cpal::list_devices()
+}
+
+/// Outputs whose audio can be captured as the system track: PulseAudio monitors
+/// on Linux, displays on macOS (ScreenCaptureKit captures audio per display)
+#[cfg(target_os = "linux")]
+pub fn system_devices() -> Result<Vec<DeviceInfo>, String> {
+    system_linux::list_monitors()
+}
+
+#[cfg(target_os = "macos")]
+pub fn system_devices() -> Result<Vec<DeviceInfo>, String> {
+    system_macos::list_displays()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn system_devices() -> Result<Vec<DeviceInfo>, String> {
+    Ok(Vec::new())
+}
+
+/// Backend used by `AudioRecorder::new()`
+pub fn default_backend() -> Box<dyn CaptureBackend> {
+    Box::new(PlatformBackend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_matches_id_or_name() {
+        let device = DeviceInfo {
+            id: "alsa:hw:CARD=Headset,DEV=0".to_string(),
+            name: "USB Headset".to_string(),
+            channels: 1,
+            sample_rate: 48000,
+            is_default: false,
+        };
+        assert!(device.matches("alsa:hw:CARD=Headset,DEV=0"));
+        assert!(device.matches("USB Headset"));
+        assert!(!device.matches("Built-in Microphone"));
+    }
+}
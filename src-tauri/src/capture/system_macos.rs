@@ -0,0 +1,169 @@
+use super::{CaptureSource, DeviceInfo, ErrorSink, SampleSink};
+use screencapturekit::prelude::*;
+use std::cell::UnsafeCell;
+use std::time::{Duration, Instant};
+
+/// System audio captured from a display via ScreenCaptureKit
+pub struct ScreenCaptureSource {
+    sample_rate: u32,
+    /// Display to capture; the main display if unset or disconnected
+    display_id: Option<u32>,
+    stream: Option<SCStream>,
+    /// Serial queue the audio handler runs on
+    queue: Option<DispatchQueue>,
+}
+
+struct SystemAudioHandler {
+    // only touched from the handler, which runs on one serial queue
+    output: UnsafeCell<HandlerOutput>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+struct HandlerOutput {
+    sink: SampleSink,
+    /// Decoded samples, reused so buffers don't allocate once it has grown to size
+    samples: Vec<f32>,
+}
+
+impl SCStreamOutputTrait for SystemAudioHandler {
+    fn did_output_sample_buffer(&self, sample_buffer: CMSampleBuffer, of_type: SCStreamOutputType) {
+        if of_type == SCStreamOutputType::Audio {
+            if let Some(audio_buffer_list) = sample_buffer.audio_buffer_list() {
+                // the callback fires once the whole buffer list has been captured, so
+                // it started that long ago; each buffer follows on from the previous one
+                // SAFETY: buffers are delivered one at a time on the serial queue the
+                // handler was added with, so nothing else holds this reference
+                let output = unsafe { &mut *self.output.get() };
+                let secs_per_byte = 1.0 / (self.sample_rate as f64 * self.channels as f64 * 4.0);
+                let total_bytes: usize = audio_buffer_list.iter().map(|b| b.data().len()).sum();
+                let mut captured_at =
+                    Instant::now() - Duration::from_secs_f64(total_bytes as f64 * secs_per_byte);
+                for audio_buffer in audio_buffer_list.iter() {
+                    let data = audio_buffer.data();
+                    if !data.is_empty() {
+                        output.samples.clear();
+                        output.samples.extend(data.chunks_exact(4).map(|chunk| {
+                            f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+                        }));
+                        (output.sink)(&output.samples, Some(captured_at));
+                        captured_at += Duration::from_secs_f64(data.len() as f64 * secs_per_byte);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl ScreenCaptureSource {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            display_id: None,
+            stream: None,
+            queue: None,
+        }
+    }
+
+    /// Capture the display with this `CGDirectDisplayID`
+    pub fn with_display(mut self, display_id: u32) -> Self {
+        self.display_id = Some(display_id);
+        self
+    }
+}
+
+/// Displays that can be captured; the first one is the main display
+pub fn list_displays() -> Result<Vec<DeviceInfo>, String> {
+    let content = SCShareableContent::get()
+        .map_err(|e| format!("Failed to get shareable content: {:?}", e))?;
+    Ok(content
+        .displays()
+        .iter()
+        .enumerate()
+        .map(|(i, display)| DeviceInfo {
+            id: display.display_id().to_string(),
+            name: format!(
+                "Display {} ({}x{})",
+                i + 1,
+                display.width(),
+                display.height()
+            ),
+            channels: 2,
+            sample_rate: 48000,
+            is_default: i == 0,
+        })
+        .collect())
+}
+
+impl CaptureSource for ScreenCaptureSource {
+    fn name(&self) -> String {
+        match self.display_id {
+            Some(id) => format!("ScreenCaptureKit (display {})", id),
+            None => "ScreenCaptureKit (main display)".to_string(),
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn start(&mut self, sink: SampleSink, _on_error: ErrorSink) -> Result<(), String> {
+        let content = SCShareableContent::get()
+            .map_err(|e| format!("Failed to get shareable content: {:?}", e))?;
+
+        let displays = content.displays();
+        let chosen = self
+            .display_id
+            .and_then(|id| displays.iter().find(|d| d.display_id() == id));
+        if chosen.is_none() && self.display_id.is_some() {
+            eprintln!(
+                "Display {:?} not found, capturing main display",
+                self.display_id
+            );
+        }
+        let display = chosen.or(displays.first()).ok_or("No display found")?;
+
+        let filter = SCContentFilter::create()
+            .with_display(display)
+            .with_excluding_windows(&[])
+            .build();
+
+        let config = SCStreamConfiguration::new()
+            .with_captures_audio(true)
+            .with_excludes_current_process_audio(false)
+            .with_sample_rate(self.sample_rate as i32)
+            .with_channel_count(self.channels() as i32);
+
+        let mut stream = SCStream::new(&filter, &config);
+        let handler = SystemAudioHandler {
+            output: UnsafeCell::new(HandlerOutput {
+                sink,
+                // 100 ms of audio; ScreenCaptureKit delivers much smaller buffers
+                samples: Vec::with_capacity(self.sample_rate as usize / 10 * 2),
+            }),
+            sample_rate: self.sample_rate,
+            channels: self.channels(),
+        };
+        let queue = DispatchQueue::new("system-audio", DispatchQoS::UserInteractive);
+        stream.add_output_handler_with_queue(handler, SCStreamOutputType::Audio, Some(&queue));
+
+        stream
+            .start_capture()
+            .map_err(|e| format!("Failed to start system audio capture: {:?}", e))?;
+
+        self.stream = Some(stream);
+        self.queue = Some(queue);
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let _ = stream.stop_capture();
+        }
+        self.queue.take();
+    }
+}
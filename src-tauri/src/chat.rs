@@ -0,0 +1,304 @@
+use crate::artifacts;
+use crate::config::AppConfig;
+use crate::export::format_timestamp;
+use crate::ollama::{ChatMessage, Ollama};
+use crate::semantic;
+use crate::summarize::{ollama_model_name, strip_thinking_blocks};
+use crate::transcribe::{TranscriptSegment, TranscriptionResult};
+use chrono::{DateTime, Local};
+use std::fs;
+use std::path::Path;
+
+/// Questions asked about a meeting and their answers, oldest first
+pub const CHAT_FILE: &str = "chat.json";
+
+/// Transcripts up to this long are sent whole; longer ones only as the passages
+/// most relevant to the question, so the prompt fits small models' context
+const FULL_TRANSCRIPT_CHARS: usize = 16_000;
+/// Passages retrieved for a question about a long meeting
+const RETRIEVED_CHUNKS: usize = 8;
+/// Earlier turns sent along so follow-up questions make sense
+const HISTORY_TURNS: usize = 6;
+
+const SYSTEM_PROMPT: &str = "You answer questions about a meeting using only its transcript below. Each transcript line starts with a segment id, a timestamp and the speaker, e.g. [seg_4 01:23 Me]. Cite the segments your answer is based on by their id in square brackets, e.g. [seg_4] or [seg_4, seg_7]. If the transcript doesn't answer the question, say so. Do not include any thinking or reasoning - just answer.";
+
+/// One question about a meeting and its answer
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ChatTurn {
+    pub question: String,
+    pub answer: String,
+    /// Ids of the transcript segments the answer cites, in the order cited
+    pub citations: Vec<String>,
+    pub asked_at: DateTime<Local>,
+}
+
+/// Conversation so far about the recording in `dir`; empty if none was started
+pub fn load_history(dir: &Path) -> Result<Vec<ChatTurn>, String> {
+    let path = dir.join(CHAT_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read chat: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse chat: {}", e))
+}
+
+fn save_history(dir: &Path, history: &[ChatTurn]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Failed to serialize chat: {}", e))?;
+    fs::write(dir.join(CHAT_FILE), json).map_err(|e| format!("Failed to write chat: {}", e))
+}
+
+/// Forget the conversation about the recording in `dir`
+pub fn clear_history(dir: &Path) -> Result<(), String> {
+    let path = dir.join(CHAT_FILE);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to clear chat: {}", e))?;
+    }
+    Ok(())
+}
+
+fn format_segment(seg: &TranscriptSegment) -> String {
+    format!(
+        "[{} {} {}] {}\n",
+        seg.id,
+        format_timestamp(seg.start_time),
+        seg.speaker,
+        seg.text.trim()
+    )
+}
+
+/// Transcript lines the model gets to answer `question` from: all of them for a
+/// short meeting, otherwise those in the passages semantic search finds most
+/// relevant, in the order they were said
+async fn transcript_context(
+    ollama: &Ollama,
+    embedding_model: &str,
+    dir: &Path,
+    transcript: &TranscriptionResult,
+    question: &str,
+) -> Result<String, String> {
+    let full: String = transcript.segments.iter().map(format_segment).collect();
+    if full.len() <= FULL_TRANSCRIPT_CHARS {
+        return Ok(full);
+    }
+
+    let hits =
+        semantic::search_meeting(ollama, embedding_model, dir, question, RETRIEVED_CHUNKS).await?;
+    let relevant: Vec<&String> = hits.iter().flat_map(|h| &h.segment_ids).collect();
+    println!(
+        "Transcript is {} chars; sending {} retrieved segments",
+        full.len(),
+        relevant.len()
+    );
+    Ok(transcript
+        .segments
+        .iter()
+        .filter(|seg| relevant.contains(&&seg.id))
+        .map(format_segment)
+        .collect())
+}
+
+/// Segment ids cited as `[seg_4]` or `[seg_4, seg_7]` in `answer`, without
+/// duplicates, keeping only ids that exist in the transcript
+fn parse_citations(answer: &str, transcript: &TranscriptionResult) -> Vec<String> {
+    let mut citations: Vec<String> = Vec::new();
+    for bracketed in answer.split('[').skip(1) {
+        let Some((inside, _)) = bracketed.split_once(']') else {
+            continue;
+        };
+        for id in inside.split(',').map(str::trim) {
+            let known = transcript.segments.iter().any(|seg| seg.id == id);
+            if known && !citations.iter().any(|c| c == id) {
+                citations.push(id.to_string());
+            }
+        }
+    }
+    citations
+}
+
+/// Ask a question about the recording in `dir`. The answer is added to the
+/// meeting's conversation, which later questions see as context.
+pub async fn ask(dir: &Path, question: &str) -> Result<ChatTurn, String> {
+    let config = AppConfig::load();
+    ask_with(
+        &Ollama::local(),
+        &ollama_model_name(&config),
+        &semantic::embedding_model_name(&config),
+        dir,
+        question,
+    )
+    .await
+}
+
+async fn ask_with(
+    ollama: &Ollama,
+    model: &str,
+    embedding_model: &str,
+    dir: &Path,
+    question: &str,
+) -> Result<ChatTurn, String> {
+    let question = question.trim();
+    if question.is_empty() {
+        return Err("Question is empty".to_string());
+    }
+    let Some((transcript, _)) = artifacts::load_transcript(dir)? else {
+        return Err("This recording has not been transcribed yet".to_string());
+    };
+    let mut history = load_history(dir)?;
+
+    let context = transcript_context(ollama, embedding_model, dir, &transcript, question).await?;
+    let mut messages = vec![ChatMessage::new(
+        "system",
+        format!("{}\n\nTranscript:\n{}", SYSTEM_PROMPT, context),
+    )];
+    for turn in history
+        .iter()
+        .skip(history.len().saturating_sub(HISTORY_TURNS))
+    {
+        messages.push(ChatMessage::new("user", turn.question.as_str()));
+        messages.push(ChatMessage::new("assistant", turn.answer.as_str()));
+    }
+    messages.push(ChatMessage::new("user", question));
+
+    println!("Asking {} about {}", model, dir.display());
+    let reply = ollama.chat(model, &messages).await?;
+    let answer = strip_thinking_blocks(&reply).trim().to_string();
+
+    let turn = ChatTurn {
+        question: question.to_string(),
+        citations: parse_citations(&answer, &transcript),
+        answer,
+        asked_at: Local::now(),
+    };
+    history.push(turn.clone());
+    save_history(dir, &history)?;
+    Ok(turn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ollama::stub::{self, block_on};
+    use crate::transcribe::fixture;
+    use serde_json::{json, Value};
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    fn chat_dir(name: &str, texts: &[&str]) -> (PathBuf, TranscriptionResult) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let lines: Vec<(&str, &str)> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| (if i % 2 == 0 { "Me" } else { "Meeting" }, *text))
+            .collect();
+        let transcript = fixture::transcript(&lines);
+        artifacts::save_transcript(&dir, &transcript).unwrap();
+        (dir, transcript)
+    }
+
+    /// Stub Ollama answering chats with `answer` and embedding by keyword;
+    /// records the messages of every chat request
+    fn stub_ollama(answer: &'static str) -> (Ollama, Arc<Mutex<Vec<Value>>>) {
+        let chats = Arc::new(Mutex::new(Vec::new()));
+        let log = chats.clone();
+        let url = stub::serve(move |path, body| match path {
+            "/api/chat" => {
+                log.lock().unwrap().push(body["messages"].clone());
+                json!({ "message": { "role": "assistant", "content": answer } }).to_string()
+            }
+            _ => {
+                let inputs: Vec<String> = serde_json::from_value(body["input"].clone()).unwrap();
+                let vectors: Vec<[f32; 2]> = inputs
+                    .iter()
+                    .map(|t| {
+                        if t.contains("deadline") {
+                            [1.0, 0.0]
+                        } else {
+                            [0.0, 1.0]
+                        }
+                    })
+                    .collect();
+                json!({ "embeddings": vectors }).to_string()
+            }
+        });
+        (Ollama::new(url), chats)
+    }
+
+    #[test]
+    fn test_parse_citations() {
+        let (_, transcript) = chat_dir("chat_test_citations", &["a", "b", "c"]);
+        let answer = "Friday [seg_2], confirmed later [seg_0, seg_2] [seg_9] [note]";
+        assert_eq!(parse_citations(answer, &transcript), ["seg_2", "seg_0"]);
+        assert!(parse_citations("no sources", &transcript).is_empty());
+    }
+
+    #[test]
+    fn test_answers_cite_segments_and_keep_history() {
+        let (dir, _) = chat_dir(
+            "chat_test_history",
+            &["Hi all", "Dana: the deadline is Friday", "Sounds good"],
+        );
+        let (ollama, chats) = stub_ollama("<think>hmm</think>Dana said Friday [seg_1].");
+
+        let turn = block_on(ask_with(
+            &ollama,
+            "m",
+            "e",
+            &dir,
+            "What did Dana say about the deadline?",
+        ))
+        .unwrap();
+        assert_eq!(turn.answer, "Dana said Friday [seg_1].");
+        assert_eq!(turn.citations, ["seg_1"]);
+
+        block_on(ask_with(&ollama, "m", "e", &dir, "Which Friday?")).unwrap();
+        let chats = chats.lock().unwrap();
+        // a short transcript is sent whole
+        let system = chats[0][0]["content"].as_str().unwrap();
+        assert!(system.contains("[seg_1 00:10 Meeting] Dana: the deadline is Friday"));
+        assert!(system.contains("[seg_2 00:20 Me] Sounds good"));
+        // the follow-up carries the first exchange
+        let roles: Vec<&str> = chats[1]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "user", "assistant", "user"]);
+        assert_eq!(chats[1][3]["content"], "Which Friday?");
+
+        let history = load_history(&dir).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].citations, ["seg_1"]);
+        clear_history(&dir).unwrap();
+        assert!(load_history(&dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_long_meetings_send_retrieved_segments() {
+        let filler = "We went through the quarterly numbers in detail. ".repeat(10);
+        let mut texts = vec![filler.as_str(); 80];
+        texts[41] = "The deadline moves to March.";
+        let (dir, _) = chat_dir("chat_test_retrieval", &texts);
+        let (ollama, chats) = stub_ollama("March [seg_41]");
+
+        let turn = block_on(ask_with(&ollama, "m", "e", &dir, "When is the deadline?")).unwrap();
+        assert_eq!(turn.citations, ["seg_41"]);
+
+        let chats = chats.lock().unwrap();
+        let system = chats[0][0]["content"].as_str().unwrap();
+        assert!(system.contains("[seg_41 06:50 Meeting] The deadline moves to March."));
+        assert!(system.len() < FULL_TRANSCRIPT_CHARS);
+    }
+
+    #[test]
+    fn test_untranscribed_recording_is_an_error() {
+        let dir = std::env::temp_dir().join("chat_test_untranscribed");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (ollama, _) = stub_ollama("unused");
+        assert!(block_on(ask_with(&ollama, "m", "e", &dir, "Anything?")).is_err());
+    }
+}
@@ -0,0 +1,380 @@
+//! Minimal streaming FLAC encoder: fixed 4096-frame blocks, independent channels,
+//! fixed-predictor (order 0-4) or verbatim subframes with Rice-coded residuals.
+//! Compresses speech to roughly half of 24-bit PCM without holding the whole
+//! file in memory; decoding goes through `claxon`.
+
+use super::{AudioEncoder, DecodedAudio};
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const BLOCK_SIZE: usize = 4096;
+const BITS_PER_SAMPLE: u32 = 24;
+const MAX_SAMPLE: f32 = ((1 << (BITS_PER_SAMPLE - 1)) - 1) as f32;
+const MAX_FIXED_ORDER: usize = 4;
+/// RICE2 parameters are 5 bits; 31 is the escape code
+const MAX_RICE_PARAM: u32 = 30;
+
+pub(super) struct FlacEncoder {
+    file: BufWriter<File>,
+    channels: u16,
+    sample_rate: u32,
+    // interleaved samples not yet filling a whole block
+    pending: Vec<i32>,
+    frame_number: u64,
+    total_frames: u64,
+    min_frame_bytes: u32,
+    max_frame_bytes: u32,
+}
+
+impl FlacEncoder {
+    pub(super) fn create(path: &Path, channels: u16, sample_rate: u32) -> Result<Self, String> {
+        if !(1..=8).contains(&channels) {
+            return Err(format!("FLAC supports 1-8 channels, got {}", channels));
+        }
+        let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+        let mut encoder = Self {
+            file: BufWriter::new(file),
+            channels,
+            sample_rate,
+            pending: Vec::with_capacity(BLOCK_SIZE * channels as usize),
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_bytes: u32::MAX,
+            max_frame_bytes: 0,
+        };
+        // STREAMINFO is rewritten with the real totals in finish()
+        encoder.write_header()?;
+        Ok(encoder)
+    }
+
+    fn write_header(&mut self) -> Result<(), String> {
+        let mut bits = BitWriter::new();
+        bits.write(0x664C_6143, 32); // "fLaC"
+        bits.write(1, 1); // last metadata block
+        bits.write(0, 7); // STREAMINFO
+        bits.write(34, 24);
+        bits.write(BLOCK_SIZE as u64, 16);
+        bits.write(BLOCK_SIZE as u64, 16);
+        let (min_frame, max_frame) = if self.max_frame_bytes == 0 {
+            (0, 0) // unknown
+        } else {
+            (self.min_frame_bytes, self.max_frame_bytes)
+        };
+        bits.write(min_frame as u64, 24);
+        bits.write(max_frame as u64, 24);
+        bits.write(self.sample_rate as u64, 20);
+        bits.write(self.channels as u64 - 1, 3);
+        bits.write(BITS_PER_SAMPLE as u64 - 1, 5);
+        bits.write(self.total_frames >> 32, 4);
+        bits.write(self.total_frames & 0xFFFF_FFFF, 32);
+        // MD5 of the audio is optional; all zeros means "not computed"
+        for _ in 0..4 {
+            bits.write(0, 32);
+        }
+        self.file
+            .write_all(&bits.into_bytes())
+            .map_err(|e| format!("Failed to write FLAC header: {}", e))
+    }
+
+    fn write_frame(&mut self, samples: &[i32]) -> Result<(), String> {
+        let channels = self.channels as usize;
+        let block_size = samples.len() / channels;
+
+        let mut bits = BitWriter::new();
+        bits.write(0b11_1111_1111_1110, 14); // sync code
+        bits.write(0, 1);
+        bits.write(0, 1); // fixed block size stream
+        if block_size == BLOCK_SIZE {
+            bits.write(0b1100, 4); // 256 * 2^4
+        } else {
+            bits.write(0b0111, 4); // 16-bit size follows the frame number
+        }
+        bits.write(0, 4); // sample rate from STREAMINFO
+        bits.write(channels as u64 - 1, 4); // independent channels
+        bits.write(0b110, 3); // 24 bits per sample
+        bits.write(0, 1);
+        write_utf8_number(&mut bits, self.frame_number);
+        if block_size != BLOCK_SIZE {
+            bits.write(block_size as u64 - 1, 16);
+        }
+        let crc = crc8(bits.bytes());
+        bits.write(crc as u64, 8);
+
+        let mut channel = Vec::with_capacity(block_size);
+        for c in 0..channels {
+            channel.clear();
+            channel.extend(samples.iter().skip(c).step_by(channels).map(|&s| s as i64));
+            write_subframe(&mut bits, &channel);
+        }
+
+        bits.align();
+        let crc = crc16(bits.bytes());
+        bits.write(crc as u64, 16);
+
+        let bytes = bits.into_bytes();
+        self.min_frame_bytes = self.min_frame_bytes.min(bytes.len() as u32);
+        self.max_frame_bytes = self.max_frame_bytes.max(bytes.len() as u32);
+        self.frame_number += 1;
+        self.total_frames += block_size as u64;
+        self.file
+            .write_all(&bytes)
+            .map_err(|e| format!("Failed to write FLAC frame: {}", e))
+    }
+}
+
+impl AudioEncoder for FlacEncoder {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        self.pending.extend(
+            samples
+                .iter()
+                .map(|&s| (s.clamp(-1.0, 1.0) * MAX_SAMPLE).round() as i32),
+        );
+
+        let block_len = BLOCK_SIZE * self.channels as usize;
+        if self.pending.len() >= block_len {
+            let pending = std::mem::take(&mut self.pending);
+            let mut blocks = pending.chunks_exact(block_len);
+            for block in &mut blocks {
+                self.write_frame(block)?;
+            }
+            self.pending = blocks.remainder().to_vec();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        let channels = self.channels as usize;
+        let whole = self.pending.len() - self.pending.len() % channels;
+        if whole > 0 {
+            let pending = std::mem::take(&mut self.pending);
+            self.write_frame(&pending[..whole])?;
+        }
+
+        self.file
+            .seek(SeekFrom::Start(0))
+            .map_err(|e| format!("Failed to rewrite FLAC header: {}", e))?;
+        self.write_header()?;
+        self.file
+            .flush()
+            .map_err(|e| format!("Failed to flush FLAC file: {}", e))
+    }
+}
+
+/// Pick the cheapest encoding for one channel of a block and write it
+fn write_subframe(bits: &mut BitWriter, samples: &[i64]) {
+    // CONSTANT: digital silence while the other side talks is common
+    if samples.iter().all(|&s| s == samples[0]) {
+        bits.write(0b0000_0000, 8);
+        bits.write_signed(samples[0], BITS_PER_SAMPLE);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * BITS_PER_SAMPLE as u64;
+    let mut best: Option<(usize, u32, u64)> = None;
+    if samples.len() > MAX_FIXED_ORDER {
+        for order in 0..=MAX_FIXED_ORDER {
+            let residuals = fixed_residuals(samples, order);
+            let (param, residual_bits) = best_rice_param(&residuals);
+            let total = order as u64 * BITS_PER_SAMPLE as u64 + 6 + 5 + residual_bits;
+            if best.is_none_or(|(_, _, b)| total < b) {
+                best = Some((order, param, total));
+            }
+        }
+    }
+
+    match best {
+        Some((order, param, total)) if total < verbatim_bits => {
+            bits.write(0b0001_0000 | ((order as u64) << 1), 8);
+            for &s in &samples[..order] {
+                bits.write_signed(s, BITS_PER_SAMPLE);
+            }
+            bits.write(0b01, 2); // RICE2: 5-bit parameters
+            bits.write(0, 4); // partition order 0
+            bits.write(param as u64, 5);
+            for r in fixed_residuals(samples, order) {
+                let folded = zigzag(r);
+                bits.write_unary(folded >> param);
+                bits.write(folded & ((1 << param) - 1), param);
+            }
+        }
+        _ => {
+            bits.write(0b0000_0010, 8);
+            for &s in samples {
+                bits.write_signed(s, BITS_PER_SAMPLE);
+            }
+        }
+    }
+}
+
+/// Prediction error of the FLAC fixed polynomial predictor of the given order
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |k: usize| samples[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+fn zigzag(value: i64) -> u64 {
+    if value >= 0 {
+        (value as u64) << 1
+    } else {
+        ((-value as u64) << 1) - 1
+    }
+}
+
+/// Rice parameter minimising the encoded size, and that size in bits
+fn best_rice_param(residuals: &[i64]) -> (u32, u64) {
+    let size = |param: u32| -> u64 {
+        residuals
+            .iter()
+            .map(|&r| (zigzag(r) >> param) + 1 + param as u64)
+            .sum()
+    };
+
+    // the optimum is within one of log2(mean)
+    let sum: u64 = residuals.iter().map(|&r| zigzag(r)).sum();
+    let mean = sum / residuals.len().max(1) as u64;
+    let estimate = (64 - mean.leading_zeros()).min(MAX_RICE_PARAM);
+    (estimate.saturating_sub(1)..=(estimate + 1).min(MAX_RICE_PARAM))
+        .map(|param| (param, size(param)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, 0))
+}
+
+/// Frame numbers use the UTF-8 style variable-length encoding
+fn write_utf8_number(bits: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        bits.write(value, 8);
+        return;
+    }
+    let continuation_bytes = match value {
+        0..=0x7FF => 1,
+        0x800..=0xFFFF => 2,
+        0x1_0000..=0x1F_FFFF => 3,
+        0x20_0000..=0x3FF_FFFF => 4,
+        0x400_0000..=0x7FFF_FFFF => 5,
+        _ => 6,
+    };
+    let lead_marker = (0xFF00u64 >> (continuation_bytes + 1)) & 0xFF;
+    bits.write(lead_marker | (value >> (6 * continuation_bytes)), 8);
+    for i in (0..continuation_bytes).rev() {
+        bits.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for &byte in bytes {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &byte in bytes {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// MSB-first bit packer
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    nbits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            acc: 0,
+            nbits: 0,
+        }
+    }
+
+    /// Write the low `count` bits of `value` (count <= 32)
+    fn write(&mut self, value: u64, count: u32) {
+        if count == 0 {
+            return;
+        }
+        self.acc = (self.acc << count) | (value & ((1u64 << count) - 1));
+        self.nbits += count;
+        while self.nbits >= 8 {
+            self.nbits -= 8;
+            self.bytes.push((self.acc >> self.nbits) as u8);
+        }
+        self.acc &= (1u64 << self.nbits) - 1;
+    }
+
+    fn write_signed(&mut self, value: i64, count: u32) {
+        self.write(value as u64, count);
+    }
+
+    /// `count` zero bits followed by a one
+    fn write_unary(&mut self, mut count: u64) {
+        while count >= 32 {
+            self.write(0, 32);
+            count -= 32;
+        }
+        self.write(1, count as u32 + 1);
+    }
+
+    fn align(&mut self) {
+        if self.nbits > 0 {
+            self.write(0, 8 - self.nbits);
+        }
+    }
+
+    /// Completed bytes so far (excludes a trailing partial byte)
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+pub(super) fn decode(path: &Path) -> Result<DecodedAudio, String> {
+    let mut reader = claxon::FlacReader::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let info = reader.streaminfo();
+    let scale = (1u64 << (info.bits_per_sample - 1)) as f32;
+    let samples = reader
+        .samples()
+        .map(|s| s.map(|s| s as f32 / scale))
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: info.sample_rate,
+        channels: info.channels as u16,
+    })
+}
@@ -0,0 +1,323 @@
+//! On-disk audio formats.
+//!
+//! Capture always streams to 32-bit float WAV because a WAV with a stale header
+//! can be repaired after a crash (see `recovery`). Once a session is finished the
+//! tracks are re-encoded to the configured `StorageCodec` and the WAVs removed.
+
+mod flac;
+mod ogg_opus;
+
+use hound::{WavReader, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Format recordings are kept in once a session is finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageCodec {
+    /// 32-bit float WAV, as captured
+    #[default]
+    Wav,
+    /// Lossless, roughly 1/4 the size of float WAV
+    Flac,
+    /// Lossy Ogg Opus for long-term archives, roughly 1/30 the size
+    Opus,
+}
+
+impl StorageCodec {
+    pub fn extension(self) -> &'static str {
+        match self {
+            StorageCodec::Wav => "wav",
+            StorageCodec::Flac => "flac",
+            StorageCodec::Opus => "opus",
+        }
+    }
+}
+
+/// Streaming encoder for interleaved f32 samples
+pub trait AudioEncoder {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String>;
+    /// Flush buffered audio and fix up headers. Must be called or the file is incomplete.
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+struct WavEncoder {
+    writer: WavWriter<BufWriter<File>>,
+}
+
+impl AudioEncoder for WavEncoder {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        for &sample in samples {
+            self.writer
+                .write_sample(sample)
+                .map_err(|e| format!("Failed to write sample: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        self.writer
+            .finalize()
+            .map_err(|e| format!("Failed to finalize WAV: {}", e))
+    }
+}
+
+/// Open an encoder writing `codec` to `path`
+pub fn create_encoder(
+    codec: StorageCodec,
+    path: &Path,
+    channels: u16,
+    sample_rate: u32,
+) -> Result<Box<dyn AudioEncoder>, String> {
+    match codec {
+        StorageCodec::Wav => {
+            let spec = WavSpec {
+                channels,
+                sample_rate,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+            let writer = WavWriter::new(BufWriter::new(file), spec)
+                .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
+            Ok(Box::new(WavEncoder { writer }))
+        }
+        StorageCodec::Flac => Ok(Box::new(flac::FlacEncoder::create(
+            path,
+            channels,
+            sample_rate,
+        )?)),
+        StorageCodec::Opus => Ok(Box::new(ogg_opus::OpusEncoder::create(
+            path,
+            channels,
+            sample_rate,
+        )?)),
+    }
+}
+
+/// A fully decoded audio file
+pub struct DecodedAudio {
+    /// Interleaved samples in [-1, 1]
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Decode a WAV, FLAC or Opus file, picking the format from the extension
+pub fn decode_file(path: &Path) -> Result<DecodedAudio, String> {
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "flac" => flac::decode(path),
+        "opus" | "ogg" => ogg_opus::decode(path),
+        _ => decode_wav(path),
+    }
+}
+
+fn decode_wav(path: &Path) -> Result<DecodedAudio, String> {
+    let reader = WavReader::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let spec = reader.spec();
+
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .into_samples::<f32>()
+            .filter_map(|s| s.ok())
+            .collect(),
+        hound::SampleFormat::Int => {
+            let bits = spec.bits_per_sample;
+            let max_val = (1 << (bits - 1)) as f32;
+            reader
+                .into_samples::<i32>()
+                .filter_map(|s| s.ok())
+                .map(|s| s as f32 / max_val)
+                .collect()
+        }
+    };
+
+    Ok(DecodedAudio {
+        samples,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+    })
+}
+
+/// Path of a track (`system`, `mic`, `mixed`) stored with the given codec
+pub fn track_path(dir: &Path, track: &str, codec: StorageCodec) -> PathBuf {
+    dir.join(format!("{}.{}", track, codec.extension()))
+}
+
+/// Find a track in a recording directory in whichever format it was stored
+pub fn find_track(dir: &Path, track: &str) -> Option<PathBuf> {
+    [StorageCodec::Wav, StorageCodec::Flac, StorageCodec::Opus]
+        .into_iter()
+        .map(|codec| track_path(dir, track, codec))
+        .find(|path| path.exists())
+}
+
+/// Re-encode a finished WAV to `codec`, next to the original.
+/// The WAV is left in place; returns the path of the new file.
+pub fn transcode_wav(wav_path: &Path, codec: StorageCodec) -> Result<PathBuf, String> {
+    let output = wav_path.with_extension(codec.extension());
+    if codec == StorageCodec::Wav {
+        return Ok(wav_path.to_path_buf());
+    }
+
+    let mut reader = WavReader::open(wav_path)
+        .map_err(|e| format!("Failed to open {}: {}", wav_path.display(), e))?;
+    let spec = reader.spec();
+    let mut encoder = create_encoder(codec, &output, spec.channels, spec.sample_rate)?;
+
+    let samples: Box<dyn Iterator<Item = hound::Result<f32>>> = match spec.sample_format {
+        hound::SampleFormat::Float => Box::new(reader.samples::<f32>()),
+        hound::SampleFormat::Int => {
+            let max_val = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            Box::new(
+                reader
+                    .samples::<i32>()
+                    .map(move |s| s.map(|s| s as f32 / max_val)),
+            )
+        }
+    };
+
+    // stream in ~1 sec blocks so long recordings don't need to fit in memory
+    let block_len = spec.sample_rate as usize * spec.channels as usize;
+    let mut block = Vec::with_capacity(block_len);
+    for sample in samples {
+        block.push(sample.map_err(|e| format!("Failed to read {}: {}", wav_path.display(), e))?);
+        if block.len() == block_len {
+            encoder.write(&block)?;
+            block.clear();
+        }
+    }
+    encoder.write(&block)?;
+    encoder.finish()?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frames: usize, channels: u16) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let s = (i as f32 * 440.0 * std::f32::consts::TAU / 48000.0).sin() * 0.5;
+                (0..channels).map(move |c| if c == 0 { s } else { -s })
+            })
+            .collect()
+    }
+
+    fn encode(codec: StorageCodec, name: &str, samples: &[f32], channels: u16) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}.{}", name, codec.extension()));
+        let mut encoder = create_encoder(codec, &path, channels, 48000).unwrap();
+        // uneven writes, like a capture callback would produce
+        for chunk in samples.chunks(1234) {
+            encoder.write(chunk).unwrap();
+        }
+        encoder.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn test_flac_roundtrip_is_lossless_at_24_bit() {
+        // 10000 frames: two full 4096 blocks plus a short final block
+        let input = sine(10000, 2);
+        let path = encode(StorageCodec::Flac, "codec_test_roundtrip", &input, 2);
+
+        let decoded = decode_file(&path).unwrap();
+        assert_eq!(decoded.sample_rate, 48000);
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.samples.len(), input.len());
+        for (a, b) in input.iter().zip(&decoded.samples) {
+            assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+        }
+
+        let wav_size = (input.len() * 4) as u64;
+        assert!(std::fs::metadata(&path).unwrap().len() < wav_size / 2);
+    }
+
+    #[test]
+    fn test_flac_handles_silence_and_noise() {
+        let mut input = vec![0.0; 5000];
+        // full-scale pseudo-random noise is incompressible and falls back to verbatim
+        let mut x: u32 = 1;
+        input.extend((0..5000).map(|_| {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (x >> 8) as f32 / (1 << 23) as f32 - 1.0
+        }));
+        let path = encode(StorageCodec::Flac, "codec_test_noise", &input, 1);
+
+        let decoded = decode_file(&path).unwrap();
+        assert_eq!(decoded.samples.len(), input.len());
+        assert!(decoded.samples[..5000].iter().all(|&s| s == 0.0));
+        for (a, b) in input.iter().zip(&decoded.samples) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_opus_roundtrip_keeps_length() {
+        let input = sine(48000, 1);
+        let path = encode(StorageCodec::Opus, "codec_test_roundtrip", &input, 1);
+
+        let decoded = decode_file(&path).unwrap();
+        assert_eq!(decoded.sample_rate, 48000);
+        assert_eq!(decoded.channels, 1);
+        // encoder delay and tail padding are trimmed via pre-skip and the granule position
+        assert_eq!(decoded.samples.len(), input.len());
+    }
+
+    #[test]
+    fn test_opus_pre_skip_matches_encoder_delay() {
+        let input = sine(48000, 1);
+        let path = encode(StorageCodec::Opus, "codec_test_pre_skip", &input, 1);
+
+        let bytes = std::fs::read(&path).unwrap();
+        let head = bytes.windows(8).position(|w| w == b"OpusHead").unwrap();
+        let pre_skip = u16::from_le_bytes([bytes[head + 10], bytes[head + 11]]);
+        let mut encoder =
+            opus::Encoder::new(48000, opus::Channels::Mono, opus::Application::Audio).unwrap();
+        assert_eq!(pre_skip as i32, encoder.get_lookahead().unwrap());
+
+        // with the right pre-skip the decoded audio lines up with the input
+        let decoded = decode_file(&path).unwrap().samples;
+        let error_at = |lag: i32| -> f32 {
+            (10000..40000)
+                .map(|i| (input[i] - decoded[(i as i32 + lag) as usize]).powi(2))
+                .sum()
+        };
+        let best = (-20..=20).min_by(|&a, &b| error_at(a).total_cmp(&error_at(b)));
+        assert_eq!(best, Some(0));
+    }
+
+    #[test]
+    fn test_opus_rejects_other_rates() {
+        let path = std::env::temp_dir().join("codec_test_rate.opus");
+        assert!(create_encoder(StorageCodec::Opus, &path, 1, 44100).is_err());
+    }
+
+    #[test]
+    fn test_transcode_and_find_track() {
+        let dir = std::env::temp_dir().join("codec_test_transcode");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let input = sine(4800, 1);
+        let wav = track_path(&dir, "mic", StorageCodec::Wav);
+        let mut encoder = create_encoder(StorageCodec::Wav, &wav, 1, 48000).unwrap();
+        encoder.write(&input).unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(find_track(&dir, "mic"), Some(wav.clone()));
+
+        let flac = transcode_wav(&wav, StorageCodec::Flac).unwrap();
+        assert_eq!(flac, dir.join("mic.flac"));
+        std::fs::remove_file(&wav).unwrap();
+        assert_eq!(find_track(&dir, "mic"), Some(flac.clone()));
+        assert_eq!(decode_file(&flac).unwrap().samples.len(), 4800);
+        assert_eq!(find_track(&dir, "system"), None);
+    }
+}
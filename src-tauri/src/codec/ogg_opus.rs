@@ -0,0 +1,214 @@
+//! Ogg Opus encoding/decoding (RFC 7845) on top of libopus and the `ogg` crate.
+
+use super::{AudioEncoder, DecodedAudio};
+use ogg::{PacketReader, PacketWriteEndInfo, PacketWriter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Opus only runs at a handful of rates; recordings are always 48 kHz
+const OPUS_RATE: u32 = 48000;
+/// 20 ms frames
+const FRAME_SIZE: usize = 960;
+/// Per-channel bitrate; plenty for speech, still ~30x smaller than float WAV
+const BITRATE_PER_CHANNEL: i32 = 32_000;
+/// Largest packet libopus can produce
+const MAX_PACKET: usize = 4000;
+const STREAM_SERIAL: u32 = 1;
+
+pub(super) struct OpusEncoder {
+    writer: PacketWriter<BufWriter<File>>,
+    encoder: opus::Encoder,
+    channels: usize,
+    /// Encoder lookahead at 48 kHz, which decoders drop from the start
+    pre_skip: u16,
+    pending: Vec<f32>,
+    frames_in: u64,
+    frames_encoded: u64,
+    // the last packet has to be written with EndStream, so hold one back
+    held: Option<(Vec<u8>, u64)>,
+}
+
+impl OpusEncoder {
+    pub(super) fn create(path: &Path, channels: u16, sample_rate: u32) -> Result<Self, String> {
+        if sample_rate != OPUS_RATE {
+            return Err(format!(
+                "Opus storage needs {} Hz audio, got {} Hz",
+                OPUS_RATE, sample_rate
+            ));
+        }
+        let opus_channels = match channels {
+            1 => opus::Channels::Mono,
+            2 => opus::Channels::Stereo,
+            n => return Err(format!("Opus storage supports 1 or 2 channels, got {}", n)),
+        };
+
+        let mut encoder = opus::Encoder::new(OPUS_RATE, opus_channels, opus::Application::Audio)
+            .map_err(|e| format!("Failed to create Opus encoder: {}", e))?;
+        encoder
+            .set_bitrate(opus::Bitrate::Bits(BITRATE_PER_CHANNEL * channels as i32))
+            .map_err(|e| format!("Failed to set Opus bitrate: {}", e))?;
+        // reported in samples at the encoder's rate, which is always 48 kHz here
+        let lookahead = encoder
+            .get_lookahead()
+            .map_err(|e| format!("Failed to read Opus lookahead: {}", e))?;
+        let pre_skip = u16::try_from(lookahead)
+            .map_err(|_| format!("Unexpected Opus lookahead: {}", lookahead))?;
+
+        let file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+        let mut writer = PacketWriter::new(BufWriter::new(file));
+
+        // ID header, then comment header, each on its own page
+        let mut head = b"OpusHead".to_vec();
+        head.push(1); // version
+        head.push(channels as u8);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&sample_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+        head.push(0); // mono/stereo mapping family
+        let vendor = concat!("meeting-recorder ", env!("CARGO_PKG_VERSION"));
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+
+        for header in [head, tags] {
+            writer
+                .write_packet(
+                    header.into_boxed_slice(),
+                    STREAM_SERIAL,
+                    PacketWriteEndInfo::EndPage,
+                    0,
+                )
+                .map_err(|e| format!("Failed to write Opus header: {}", e))?;
+        }
+
+        Ok(Self {
+            writer,
+            encoder,
+            channels: channels as usize,
+            pre_skip,
+            pending: Vec::with_capacity(FRAME_SIZE * channels as usize),
+            frames_in: 0,
+            frames_encoded: 0,
+            held: None,
+        })
+    }
+
+    fn encode_frame(&mut self, frame: &[f32]) -> Result<(), String> {
+        let mut packet = vec![0u8; MAX_PACKET];
+        let len = self
+            .encoder
+            .encode_float(frame, &mut packet)
+            .map_err(|e| format!("Opus encoding failed: {}", e))?;
+        packet.truncate(len);
+        self.frames_encoded += FRAME_SIZE as u64;
+
+        // granule position counts decoded samples including the pre-skip
+        let granule = self.pre_skip as u64 + self.frames_encoded;
+        if let Some((previous, previous_granule)) = self.held.replace((packet, granule)) {
+            self.writer
+                .write_packet(
+                    previous.into_boxed_slice(),
+                    STREAM_SERIAL,
+                    PacketWriteEndInfo::NormalPacket,
+                    previous_granule,
+                )
+                .map_err(|e| format!("Failed to write Opus packet: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+impl AudioEncoder for OpusEncoder {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        self.frames_in += (samples.len() / self.channels) as u64;
+        self.pending.extend_from_slice(samples);
+
+        let frame_len = FRAME_SIZE * self.channels;
+        if self.pending.len() >= frame_len {
+            let pending = std::mem::take(&mut self.pending);
+            let mut frames = pending.chunks_exact(frame_len);
+            for frame in &mut frames {
+                self.encode_frame(frame)?;
+            }
+            self.pending = frames.remainder().to_vec();
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        // push the encoder lookahead out with silence and pad to a whole frame;
+        // the final granule position tells decoders to trim it off again
+        let frame_len = FRAME_SIZE * self.channels;
+        let mut tail = std::mem::take(&mut self.pending);
+        tail.resize(tail.len() + self.pre_skip as usize * self.channels, 0.0);
+        tail.resize(tail.len().div_ceil(frame_len) * frame_len, 0.0);
+        for frame in tail.chunks_exact(frame_len) {
+            self.encode_frame(frame)?;
+        }
+
+        if let Some((packet, _)) = self.held.take() {
+            let granule = self.pre_skip as u64 + self.frames_in;
+            self.writer
+                .write_packet(
+                    packet.into_boxed_slice(),
+                    STREAM_SERIAL,
+                    PacketWriteEndInfo::EndStream,
+                    granule,
+                )
+                .map_err(|e| format!("Failed to write Opus packet: {}", e))?;
+        }
+        self.writer
+            .inner_mut()
+            .flush()
+            .map_err(|e| format!("Failed to flush Opus file: {}", e))
+    }
+}
+
+pub(super) fn decode(path: &Path) -> Result<DecodedAudio, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut reader = PacketReader::new(BufReader::new(file));
+    let mut next_packet = || {
+        reader
+            .read_packet()
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+
+    let head = next_packet()?.ok_or("Missing OpusHead")?;
+    if head.data.len() < 19 || &head.data[0..8] != b"OpusHead" {
+        return Err(format!("{} is not an Ogg Opus file", path.display()));
+    }
+    let channels = head.data[9] as usize;
+    let pre_skip = u16::from_le_bytes([head.data[10], head.data[11]]) as usize;
+    let opus_channels = match channels {
+        1 => opus::Channels::Mono,
+        2 => opus::Channels::Stereo,
+        n => return Err(format!("Unsupported Opus channel count: {}", n)),
+    };
+    next_packet()?.ok_or("Missing OpusTags")?;
+
+    let mut decoder = opus::Decoder::new(OPUS_RATE, opus_channels)
+        .map_err(|e| format!("Failed to create Opus decoder: {}", e))?;
+    // 120 ms is the longest Opus packet
+    let mut buffer = vec![0f32; 5760 * channels];
+    let mut samples = Vec::new();
+    let mut last_granule = 0;
+    while let Some(packet) = next_packet()? {
+        let frames = decoder
+            .decode_float(&packet.data, &mut buffer, false)
+            .map_err(|e| format!("Opus decoding failed: {}", e))?;
+        samples.extend_from_slice(&buffer[..frames * channels]);
+        last_granule = packet.absgp_page();
+    }
+
+    // drop the encoder delay at the start and the padding at the end
+    let total_frames = (last_granule as usize).saturating_sub(pre_skip);
+    let start = (pre_skip * channels).min(samples.len());
+    let end = (start + total_frames * channels).min(samples.len());
+    Ok(DecodedAudio {
+        samples: samples[start..end].to_vec(),
+        sample_rate: OPUS_RATE,
+        channels: channels as u16,
+    })
+}
@@ -1,3 +1,4 @@
+use crate::codec::StorageCodec;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -7,6 +8,100 @@ pub struct AppConfig {
     pub setup_complete: bool,
     pub whisper_model: Option<String>,
     pub llm_model: Option<String>,
+    /// Format finished recordings are stored in
+    #[serde(default)]
+    pub storage_codec: StorageCodec,
+    /// How system audio and mic are balanced in the mixed track
+    #[serde(default)]
+    pub mix: MixSettings,
+    /// Mic to record from (`DeviceInfo::id`); the OS default input if unset
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// Output whose audio is recorded as the system track; the default output if unset
+    #[serde(default)]
+    pub system_device: Option<String>,
+    /// Live level meters and the silence warning
+    #[serde(default)]
+    pub levels: LevelSettings,
+    /// Starting and stopping on voice activity
+    #[serde(default)]
+    pub auto_record: AutoRecordSettings,
+    /// Ollama model that embeds transcripts for semantic search
+    #[serde(default)]
+    pub embedding_model: Option<String>,
+}
+
+/// How the two tracks are scaled before summing into the mixed track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MixMode {
+    /// Fixed gains from `MixSettings`
+    #[default]
+    Manual,
+    /// Measure each track's EBU R128 loudness and bring both to `target_lufs`
+    Normalize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MixSettings {
+    pub mode: MixMode,
+    /// Linear gains used in manual mode
+    pub system_gain: f32,
+    pub mic_gain: f32,
+    /// Per-track loudness target in normalize mode
+    pub target_lufs: f64,
+}
+
+impl Default for MixSettings {
+    fn default() -> Self {
+        Self {
+            mode: MixMode::Manual,
+            system_gain: 0.7,
+            mic_gain: 0.3,
+            target_lufs: -23.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelSettings {
+    /// Warn when a source has been silent this long while recording; 0 turns it off
+    pub silence_warning_secs: u64,
+    /// Peak level in dBFS below which audio counts as silence
+    pub silence_threshold_db: f32,
+}
+
+impl Default for LevelSettings {
+    fn default() -> Self {
+        Self {
+            silence_warning_secs: 120,
+            silence_threshold_db: -60.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoRecordSettings {
+    /// Stop and save the session once nobody has spoken on either source for this
+    /// many seconds; 0 turns it off
+    pub stop_after_silent_secs: u64,
+    /// Arm instead of recording straight away: nothing is written until someone speaks
+    pub start_on_voice: bool,
+    /// RMS level in dBFS a 30 ms frame needs to count as voice
+    pub voice_threshold_db: f32,
+}
+
+impl Default for AutoRecordSettings {
+    fn default() -> Self {
+        Self {
+            stop_after_silent_secs: 0,
+            start_on_voice: false,
+            voice_threshold_db: -45.0,
+        }
+    }
 }
 
 impl AppConfig {
@@ -22,6 +117,13 @@ impl AppConfig {
         Self::data_dir().join("models")
     }
 
+    /// Get the directory new recordings are saved under
+    pub fn recordings_dir() -> PathBuf {
+        dirs::document_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("MeetingRecordings")
+    }
+
     /// Get the config file path
     fn config_path() -> PathBuf {
         Self::data_dir().join("config.json")
@@ -0,0 +1,201 @@
+use crate::action_items::ActionStatus;
+use crate::session::SessionMetadata;
+use crate::summarize::SummaryResult;
+use crate::transcribe::TranscriptionResult;
+
+/// Format seconds as `MM:SS`, or `H:MM:SS` once past the hour
+pub(crate) fn format_timestamp(secs: f32) -> String {
+    let total = secs.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, (total % 3600) / 60, total % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+/// Render a transcript (and summary, if there is one) as a markdown document.
+/// The session metadata, if available, adds when the meeting took place.
+pub fn to_markdown(
+    title: &str,
+    transcript: &TranscriptionResult,
+    summary: Option<&SummaryResult>,
+    session: Option<&SessionMetadata>,
+) -> String {
+    let mut out = format!("# {}\n\n", title);
+
+    if let Some(session) = session {
+        let started = session.started_at.format("%Y-%m-%d %H:%M");
+        match session.ended_at {
+            Some(ended) => out.push_str(&format!(
+                "*Recorded {} - {}*\n\n",
+                started,
+                ended.format("%H:%M")
+            )),
+            None => out.push_str(&format!("*Recorded {}*\n\n", started)),
+        }
+    }
+
+    if let Some(summary) = summary {
+        out.push_str("## Summary\n\n");
+        out.push_str(&summary.summary);
+        out.push_str("\n\n");
+
+        if !summary.key_points.is_empty() {
+            out.push_str("## Key Points\n\n");
+            for point in &summary.key_points {
+                out.push_str(&format!("- {}\n", point));
+            }
+            out.push('\n');
+        }
+
+        if !summary.decisions.is_empty() {
+            out.push_str("## Decisions\n\n");
+            for decision in &summary.decisions {
+                out.push_str(&format!("- {}\n", decision));
+            }
+            out.push('\n');
+        }
+
+        if !summary.action_items.is_empty() {
+            out.push_str("## Action Items\n\n");
+            for item in &summary.action_items {
+                let check = if item.status == ActionStatus::Done {
+                    "x"
+                } else {
+                    " "
+                };
+                out.push_str(&format!("- [{}] {}", check, item.text));
+                let mut details = Vec::new();
+                if let Some(assignee) = &item.assignee {
+                    details.push(assignee.clone());
+                }
+                if let Some(due) = item.due {
+                    details.push(format!("due {}", due));
+                }
+                if !details.is_empty() {
+                    out.push_str(&format!(" ({})", details.join(", ")));
+                }
+                out.push('\n');
+            }
+            out.push('\n');
+        }
+
+        if !summary.open_questions.is_empty() {
+            out.push_str("## Open Questions\n\n");
+            for question in &summary.open_questions {
+                out.push_str(&format!("- {}\n", question));
+            }
+            out.push('\n');
+        }
+    }
+
+    out.push_str("## Transcript\n\n");
+    for seg in &transcript.segments {
+        out.push_str(&format!(
+            "**[{}] {}:** {}\n\n",
+            format_timestamp(seg.start_time),
+            seg.speaker,
+            seg.text
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action_items::ActionItem;
+    use crate::transcribe::TranscriptSegment;
+
+    fn transcript() -> TranscriptionResult {
+        TranscriptionResult {
+            segments: vec![
+                TranscriptSegment {
+                    id: "seg_0".into(),
+                    text: "Hello team".into(),
+                    start_time: 1.5,
+                    end_time: 3.0,
+                    speaker: "Me".into(),
+                },
+                TranscriptSegment {
+                    id: "seg_1".into(),
+                    text: "Hi there".into(),
+                    start_time: 3725.0,
+                    end_time: 3727.0,
+                    speaker: "Meeting".into(),
+                },
+            ],
+            full_text: "Hello team Hi there".into(),
+            duration: 3727.0,
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0.0), "00:00");
+        assert_eq!(format_timestamp(61.9), "01:01");
+        assert_eq!(format_timestamp(3725.0), "1:02:05");
+    }
+
+    #[test]
+    fn test_to_markdown_transcript_only() {
+        let md = to_markdown("Standup", &transcript(), None, None);
+        assert!(md.starts_with("# Standup\n"));
+        assert!(!md.contains("## Summary"));
+        assert!(md.contains("**[00:01] Me:** Hello team"));
+        assert!(md.contains("**[1:02:05] Meeting:** Hi there"));
+    }
+
+    #[test]
+    fn test_to_markdown_with_summary() {
+        let summary = SummaryResult {
+            summary: "Quick sync.".into(),
+            key_points: vec!["Greetings".into()],
+            decisions: vec![],
+            action_items: vec![
+                ActionItem::new("Send notes"),
+                ActionItem {
+                    text: "Book the room".into(),
+                    assignee: Some("Me".into()),
+                    due: chrono::NaiveDate::from_ymd_opt(2024, 3, 8),
+                    status: ActionStatus::Done,
+                    segment_ids: vec!["seg_0".into()],
+                },
+            ],
+            open_questions: vec!["Who takes notes next week?".into()],
+        };
+        let md = to_markdown("Standup", &transcript(), Some(&summary), None);
+        assert!(md.contains("## Summary\n\nQuick sync."));
+        assert!(md.contains("## Key Points\n\n- Greetings"));
+        assert!(md.contains(
+            "## Action Items\n\n- [ ] Send notes\n- [x] Book the room (Me, due 2024-03-08)\n"
+        ));
+        assert!(md.contains("## Open Questions\n\n- Who takes notes next week?"));
+        // empty sections are left out
+        assert!(!md.contains("## Decisions"));
+        // summary comes before the transcript
+        assert!(md.find("## Summary").unwrap() < md.find("## Transcript").unwrap());
+    }
+
+    #[test]
+    fn test_to_markdown_with_session_times() {
+        use chrono::TimeZone;
+        let mut session = SessionMetadata::new(
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 5, 9, 30, 0)
+                .unwrap(),
+        );
+        let md = to_markdown("Standup", &transcript(), None, Some(&session));
+        assert!(md.starts_with("# Standup\n\n*Recorded 2024-03-05 09:30*\n\n"));
+
+        session.ended_at = Some(
+            chrono::Local
+                .with_ymd_and_hms(2024, 3, 5, 10, 15, 0)
+                .unwrap(),
+        );
+        let md = to_markdown("Standup", &transcript(), None, Some(&session));
+        assert!(md.contains("*Recorded 2024-03-05 09:30 - 10:15*"));
+    }
+}
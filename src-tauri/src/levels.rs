@@ -0,0 +1,191 @@
+use crate::config::LevelSettings;
+
+/// Reported for digital silence instead of -inf
+const FLOOR_DB: f32 = -100.0;
+
+/// Level of one source over the last metering window, in dBFS
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct SourceLevel {
+    pub rms_db: f32,
+    pub peak_db: f32,
+    /// Seconds of audio since this source last rose above the silence threshold
+    pub silent_secs: f64,
+}
+
+/// Payload of the `audio-levels` event sent while recording
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AudioLevels {
+    pub system: SourceLevel,
+    pub mic: SourceLevel,
+}
+
+fn to_db(linear: f32) -> f32 {
+    if linear > 0.0 {
+        (20.0 * linear.log10()).max(FLOOR_DB)
+    } else {
+        FLOOR_DB
+    }
+}
+
+/// Accumulates one source's samples between level events and keeps track of how
+/// long it has been silent. Time is counted in audio received, so pauses and
+/// device gaps don't count as silence.
+pub struct LevelMeter {
+    threshold: f32,
+    warn_after_secs: u64,
+    warned: bool,
+    sum_squares: f64,
+    peak: f32,
+    samples: u64,
+    window_secs: f64,
+    silent_secs: f64,
+}
+
+impl LevelMeter {
+    pub fn new(settings: &LevelSettings) -> Self {
+        Self {
+            threshold: 10f32.powf(settings.silence_threshold_db / 20.0),
+            warn_after_secs: settings.silence_warning_secs,
+            warned: false,
+            sum_squares: 0.0,
+            peak: 0.0,
+            samples: 0,
+            window_secs: 0.0,
+            silent_secs: 0.0,
+        }
+    }
+
+    /// Feed interleaved samples as they come off the capture ring
+    pub fn push(&mut self, samples: &[f32], channels: u16, sample_rate: u32) {
+        for &sample in samples {
+            self.sum_squares += (sample as f64).powi(2);
+            self.peak = self.peak.max(sample.abs());
+        }
+        self.samples += samples.len() as u64;
+        self.window_secs +=
+            samples.len() as f64 / channels.max(1) as f64 / sample_rate.max(1) as f64;
+    }
+
+    /// Level of everything pushed since the last call, which starts a new window.
+    /// An empty window (source paused or gone) leaves the silence count alone.
+    pub fn take(&mut self) -> SourceLevel {
+        let rms = if self.samples > 0 {
+            (self.sum_squares / self.samples as f64).sqrt() as f32
+        } else {
+            0.0
+        };
+        if self.samples > 0 {
+            if self.peak < self.threshold {
+                self.silent_secs += self.window_secs;
+            } else {
+                self.silent_secs = 0.0;
+                self.warned = false;
+            }
+        }
+        let level = SourceLevel {
+            rms_db: to_db(rms),
+            peak_db: to_db(self.peak),
+            silent_secs: self.silent_secs,
+        };
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.samples = 0;
+        self.window_secs = 0.0;
+        level
+    }
+
+    /// True once per stretch of silence, when it reaches the configured warning period
+    pub fn silence_warning_due(&mut self) -> bool {
+        if self.warn_after_secs == 0
+            || self.warned
+            || self.silent_secs < self.warn_after_secs as f64
+        {
+            return false;
+        }
+        self.warned = true;
+        true
+    }
+
+    pub fn warn_after_secs(&self) -> u64 {
+        self.warn_after_secs
+    }
+}
+
+/// "2 minutes", "90 seconds"
+pub fn describe_secs(secs: u64) -> String {
+    match secs {
+        60 => "1 minute".to_string(),
+        secs if secs % 60 == 0 => format!("{} minutes", secs / 60),
+        1 => "1 second".to_string(),
+        secs => format!("{} seconds", secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_of_full_scale_sine() {
+        let mut meter = LevelMeter::new(&LevelSettings::default());
+        let sine: Vec<f32> = (0..4800)
+            .map(|i| (i as f32 * 1000.0 * std::f32::consts::TAU / 48000.0).sin())
+            .collect();
+        meter.push(&sine, 1, 48000);
+        let level = meter.take();
+        assert!(level.peak_db.abs() < 0.01, "{:?}", level);
+        assert!((level.rms_db - -3.01).abs() < 0.05, "{:?}", level);
+
+        // nothing pushed since: the floor, not NaN
+        assert_eq!(meter.take().rms_db, FLOOR_DB);
+    }
+
+    #[test]
+    fn test_silence_accumulates_until_sound_returns() {
+        let mut meter = LevelMeter::new(&LevelSettings::default());
+        // 100 ms stereo windows of -80 dBFS noise floor
+        for _ in 0..30 {
+            meter.push(&[0.0001; 9600], 2, 48000);
+            meter.take();
+        }
+        assert!((meter.take().silent_secs - 3.0).abs() < 1e-9);
+
+        meter.push(&[0.0001; 9600], 2, 48000);
+        meter.push(&[0.2; 2], 2, 48000);
+        assert_eq!(meter.take().silent_secs, 0.0);
+    }
+
+    #[test]
+    fn test_silence_warning_fires_once_per_stretch() {
+        let mut meter = LevelMeter::new(&LevelSettings {
+            silence_warning_secs: 1,
+            ..Default::default()
+        });
+        let mut warnings = 0;
+        for window in 0..40 {
+            // 1.5 s of silence, a word, then 2 s of silence again
+            let level = if window == 15 { 0.5 } else { 0.0 };
+            meter.push(&[level; 4800], 1, 48000);
+            meter.take();
+            if meter.silence_warning_due() {
+                warnings += 1;
+            }
+        }
+        assert_eq!(warnings, 2);
+
+        let mut disabled = LevelMeter::new(&LevelSettings {
+            silence_warning_secs: 0,
+            ..Default::default()
+        });
+        disabled.push(&vec![0.0; 48000 * 10], 1, 48000);
+        disabled.take();
+        assert!(!disabled.silence_warning_due());
+    }
+
+    #[test]
+    fn test_describe_secs() {
+        assert_eq!(describe_secs(120), "2 minutes");
+        assert_eq!(describe_secs(60), "1 minute");
+        assert_eq!(describe_secs(90), "90 seconds");
+    }
+}
@@ -1,15 +1,41 @@
-mod audio;
-mod config;
+pub mod action_items;
+pub mod artifacts;
+pub mod audio;
+mod capture;
+pub mod chat;
+pub mod codec;
+pub mod config;
 mod download;
-mod summarize;
-mod transcribe;
-
-use audio::{AudioRecorder, RecordingOutput, RecordingStats};
-use config::{AppConfig, ModelInfo};
-use summarize::SummaryResult;
+pub mod export;
+mod levels;
+pub mod library;
+mod loudness;
+mod ollama;
+pub mod recovery;
+mod resample;
+pub mod search;
+pub mod semantic;
+pub mod session;
+pub mod summarize;
+pub mod transcribe;
+mod vad;
+
+use action_items::ActionItem;
+use artifacts::MeetingArtifacts;
+use audio::{AudioRecorder, AutoRecordEvent, RecordingOutput, RecordingStats};
+use capture::DeviceInfo;
+use chat::ChatTurn;
+use codec::StorageCodec;
+use library::{Library, Meeting, MeetingDetails};
+use ollama::Ollama;
+use search::{MeetingHits, SearchIndex};
+use semantic::SemanticHit;
+use config::{AppConfig, AutoRecordSettings, LevelSettings, MixSettings, ModelInfo};
+use summarize::{SummaryProgress, SummaryResult};
 use transcribe::TranscriptionResult;
 use parking_lot::Mutex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tauri::{
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
@@ -21,28 +47,76 @@ struct AppState {
     recorder: Mutex<AudioRecorder>,
     recordings_dir: PathBuf,
     config: Mutex<AppConfig>,
+    library: Mutex<Library>,
+    search: Mutex<SearchIndex>,
+    /// Stops the summary being generated; set while one is running
+    summary_cancel: Mutex<Option<tokio::sync::watch::Sender<bool>>>,
+}
+
+/// Tray menu items whose enabled state follows the recorder
+struct TrayItems {
+    start: MenuItem<tauri::Wry>,
+    stop: MenuItem<tauri::Wry>,
+    pause: MenuItem<tauri::Wry>,
+    resume: MenuItem<tauri::Wry>,
 }
 
 // === Recording Commands ===
 
 #[tauri::command]
-fn start_recording(state: State<AppState>) -> Result<(), String> {
-    // Generate timestamp for directory name
-    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-    let recording_dir = state.recordings_dir.join(&timestamp);
+fn start_recording(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    let recording_dir = audio::timestamped_dir(&state.recordings_dir);
 
     // Ensure base recordings directory exists
     std::fs::create_dir_all(&state.recordings_dir)
         .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
 
+    let config = state.config.lock().clone();
     let mut recorder = state.recorder.lock();
-    recorder.start_recording(&recording_dir)
+    recorder.apply_config(&config);
+    recorder.start_recording(&recording_dir, Some(&app))?;
+    update_tray_menu(&app, true, false);
+    Ok(())
 }
 
 #[tauri::command]
 fn stop_recording(app: AppHandle, state: State<AppState>) -> Result<RecordingOutput, String> {
     let mut recorder = state.recorder.lock();
-    recorder.stop_recording(Some(&app))
+    let output = recorder.stop_recording(Some(&app))?;
+    update_tray_menu(&app, false, false);
+    Ok(output)
+}
+
+/// Stop the session in `dir` after the recorder reported it has gone quiet,
+/// unless it was already stopped by hand in the meantime
+fn auto_stop_recording(app: &AppHandle, dir: &Path) {
+    let state = app.state::<AppState>();
+    let mut recorder = state.recorder.lock();
+    if recorder.recording_dir() != Some(dir) {
+        return;
+    }
+    match recorder.stop_recording(Some(app)) {
+        Ok(output) => {
+            println!("Recording auto-stopped, saved to: {:?}", output.directory);
+            update_tray_menu(app, false, false);
+            let _ = app.emit("recording-auto-stopped", output);
+        }
+        Err(e) => eprintln!("Failed to auto-stop recording: {}", e),
+    }
+}
+
+#[tauri::command]
+fn pause_recording(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    state.recorder.lock().pause_recording()?;
+    update_tray_menu(&app, true, true);
+    Ok(())
+}
+
+#[tauri::command]
+fn resume_recording(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    state.recorder.lock().resume_recording()?;
+    update_tray_menu(&app, true, false);
+    Ok(())
 }
 
 #[tauri::command]
@@ -130,10 +204,156 @@ fn get_config(state: State<AppState>) -> AppConfig {
     state.config.lock().clone()
 }
 
+#[tauri::command]
+fn set_mix_settings(state: State<AppState>, mix: MixSettings) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.mix = mix;
+    config.save()
+}
+
+#[tauri::command]
+fn set_level_settings(state: State<AppState>, levels: LevelSettings) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.levels = levels;
+    config.save()
+}
+
+#[tauri::command]
+fn set_auto_record_settings(
+    state: State<AppState>,
+    auto_record: AutoRecordSettings,
+) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.auto_record = auto_record;
+    config.save()
+}
+
+#[tauri::command]
+fn set_storage_codec(state: State<AppState>, codec: StorageCodec) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.storage_codec = codec;
+    config.save()
+}
+
+// === Device Commands ===
+
+#[tauri::command]
+fn list_input_devices() -> Result<Vec<DeviceInfo>, String> {
+    capture::input_devices()
+}
+
+#[tauri::command]
+fn list_system_devices() -> Result<Vec<DeviceInfo>, String> {
+    capture::system_devices()
+}
+
+/// Pass `None` to follow the OS default
+#[tauri::command]
+fn set_input_device(state: State<AppState>, device: Option<String>) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.input_device = device;
+    config.save()
+}
+
+#[tauri::command]
+fn set_system_device(state: State<AppState>, device: Option<String>) -> Result<(), String> {
+    let mut config = state.config.lock();
+    config.system_device = device;
+    config.save()
+}
+
+// === Library Commands ===
+
+#[tauri::command]
+fn list_meetings(state: State<AppState>) -> Result<Vec<Meeting>, String> {
+    state.library.lock().list()
+}
+
+#[tauri::command]
+fn get_meeting(state: State<AppState>, id: String) -> Result<MeetingDetails, String> {
+    state.library.lock().get(&id)
+}
+
+#[tauri::command]
+fn rename_meeting(state: State<AppState>, id: String, title: String) -> Result<Meeting, String> {
+    state.library.lock().rename(&id, &title)
+}
+
+#[tauri::command]
+fn delete_meeting(state: State<AppState>, id: String) -> Result<(), String> {
+    state.library.lock().delete(&id)?;
+    if let Err(e) = state.search.lock().remove_meeting(&id) {
+        eprintln!("Failed to remove {} from search index: {}", id, e);
+    }
+    Ok(())
+}
+
+/// Maximum number of hits `search_meetings` returns, across all meetings
+const SEARCH_LIMIT: usize = 200;
+
+/// Full-text search over all transcripts and summaries, grouped by meeting
+#[tauri::command]
+fn search_meetings(state: State<AppState>, query: String) -> Result<Vec<MeetingHits>, String> {
+    let hits = {
+        let mut index = state.search.lock();
+        index.sync()?;
+        index.search(&query, SEARCH_LIMIT)?
+    };
+    let meetings = state.library.lock().list()?;
+    Ok(search::group_by_meeting(hits, &meetings))
+}
+
+/// Default number of chunks `semantic_search` returns
+const SEMANTIC_SEARCH_LIMIT: usize = 10;
+
+/// Passages closest in meaning to `query` across all meetings, for when the exact
+/// words aren't known. Needs the embedding model pulled in Ollama.
+#[tauri::command]
+async fn semantic_search(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SemanticHit>, String> {
+    let model = semantic::embedding_model_name(&state.config.lock());
+    let limit = limit.unwrap_or(SEMANTIC_SEARCH_LIMIT);
+    semantic::semantic_search(&Ollama::local(), &model, &state.recordings_dir, &query, limit).await
+}
+
+/// Re-embed a recording's transcript in the background after it was saved, so
+/// semantic search doesn't have to. Failures (e.g. the model not being pulled) are
+/// only logged; `semantic_search` retries them.
+fn update_embeddings(state: &AppState, recording_dir: &Path) {
+    let model = semantic::embedding_model_name(&state.config.lock());
+    let dir = recording_dir.to_path_buf();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = semantic::index_meeting(&Ollama::local(), &model, &dir).await {
+            eprintln!("Failed to embed {}: {}", dir.display(), e);
+        }
+    });
+}
+
+/// Re-index a recording after its transcript or summary was saved. Search still
+/// works if this fails: the next `sync` picks the change up.
+fn update_search_index(state: &AppState, recording_dir: &Path) {
+    if recording_dir.parent() != Some(state.recordings_dir.as_path()) {
+        return;
+    }
+    let Some(id) = recording_dir.file_name().map(|n| n.to_string_lossy().to_string()) else {
+        return;
+    };
+    if let Err(e) = state.search.lock().index_meeting(&id) {
+        eprintln!("Failed to update search index for {}: {}", id, e);
+    }
+}
+
 // === Transcription Commands ===
 
 #[tauri::command]
-async fn transcribe_recording(app: AppHandle, recording_dir: String) -> Result<TranscriptionResult, String> {
+async fn transcribe_recording(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    recording_dir: String,
+) -> Result<TranscriptionResult, String> {
     let (tx, rx) = std::sync::mpsc::channel::<transcribe::TranscriptionProgress>();
 
     // spawn thread to forward progress to frontend
@@ -145,6 +365,7 @@ async fn transcribe_recording(app: AppHandle, recording_dir: String) -> Result<T
     });
 
     let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+    let dir = PathBuf::from(&recording_dir);
     std::thread::Builder::new()
         .stack_size(64 * 1024 * 1024)
         .spawn(move || {
@@ -153,14 +374,72 @@ async fn transcribe_recording(app: AppHandle, recording_dir: String) -> Result<T
             let _ = result_tx.send(result);
         })
         .map_err(|e| format!("Spawn failed: {}", e))?;
-    result_rx.await.map_err(|e| format!("Task failed: {}", e))?
+    let result = result_rx.await.map_err(|e| format!("Task failed: {}", e))??;
+    update_search_index(&state, &dir);
+    update_embeddings(&state, &dir);
+    Ok(result)
 }
 
 // === Summarization Commands ===
 
+/// Pass the recording's directory to have the summary noted in its session.json.
+/// The output is streamed to the frontend as `summary-progress` events while it is
+/// generated. Only one summary runs at a time, whichever window asked for it.
+#[tauri::command]
+async fn summarize_transcript(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transcript: TranscriptionResult,
+    recording_dir: Option<String>,
+) -> Result<SummaryResult, String> {
+    let (cancel_tx, cancel_rx) = tokio::sync::watch::channel(false);
+    {
+        let mut cancel = state.summary_cancel.lock();
+        if cancel.is_some() {
+            return Err("Already summarizing".to_string());
+        }
+        *cancel = Some(cancel_tx);
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel::<SummaryProgress>();
+
+    // spawn thread to forward progress to frontend
+    std::thread::spawn(move || {
+        while let Ok(progress) = rx.recv() {
+            let _ = app.emit("summary-progress", progress);
+        }
+    });
+
+    let result = match &recording_dir {
+        Some(dir) => {
+            summarize::summarize_recording_with_progress(
+                Path::new(dir),
+                &transcript,
+                Some(tx),
+                Some(cancel_rx),
+            )
+            .await
+        }
+        None => {
+            summarize::summarize_transcript_with_progress(&transcript, Some(tx), Some(cancel_rx))
+                .await
+        }
+    };
+    state.summary_cancel.lock().take();
+
+    if let (Ok(_), Some(dir)) = (&result, &recording_dir) {
+        update_search_index(&state, Path::new(dir));
+    }
+    result
+}
+
+/// Stop the summary being generated; `summarize_transcript` then fails with
+/// "Summarization cancelled" and nothing is saved
 #[tauri::command]
-async fn summarize_transcript(transcript: TranscriptionResult) -> Result<SummaryResult, String> {
-    summarize::summarize_transcript(&transcript).await
+fn cancel_summarization(state: State<AppState>) {
+    if let Some(cancel) = state.summary_cancel.lock().as_ref() {
+        let _ = cancel.send(true);
+    }
 }
 
 // === Editor Window Commands ===
@@ -221,21 +500,76 @@ async fn open_editor(
 
 #[tauri::command]
 async fn save_edited_transcript(
+    state: State<'_, AppState>,
     recording_dir: String,
     transcript: TranscriptionResult,
 ) -> Result<(), String> {
-    let path = std::path::Path::new(&recording_dir).join("transcript_edited.json");
-    let json = serde_json::to_string_pretty(&transcript)
-        .map_err(|e| format!("Failed to serialize transcript: {}", e))?;
-    std::fs::write(&path, json)
-        .map_err(|e| format!("Failed to write transcript: {}", e))?;
+    artifacts::save_edited_transcript(Path::new(&recording_dir), &transcript)?;
+    update_search_index(&state, Path::new(&recording_dir));
+    update_embeddings(&state, Path::new(&recording_dir));
     Ok(())
 }
 
-fn update_tray_menu(_app: &AppHandle, is_recording: bool) {
-    // We'll update menu item enabled states based on recording status
-    // For now, just log the state
-    println!("Recording state: {}", is_recording);
+/// Saved transcript (edited if there is one) and summary of a recording
+#[tauri::command]
+fn load_meeting_artifacts(recording_dir: String) -> Result<MeetingArtifacts, String> {
+    artifacts::load_meeting_artifacts(Path::new(&recording_dir))
+}
+
+/// One of the summaries listed in `MeetingArtifacts::previous_summaries`
+#[tauri::command]
+fn load_summary_version(recording_dir: String, version: u32) -> Result<SummaryResult, String> {
+    artifacts::load_summary_version(Path::new(&recording_dir), version)
+}
+
+/// Replace the action items of a recording's summary with the edited list; returns
+/// the updated summary
+#[tauri::command]
+fn save_action_items(
+    state: State<'_, AppState>,
+    recording_dir: String,
+    action_items: Vec<ActionItem>,
+) -> Result<SummaryResult, String> {
+    let summary = artifacts::save_action_items(Path::new(&recording_dir), action_items)?;
+    update_search_index(&state, Path::new(&recording_dir));
+    Ok(summary)
+}
+
+// === Chat Commands ===
+
+/// Ask a question about a recording; the answer cites the transcript segments it
+/// is based on and is added to the meeting's conversation
+#[tauri::command]
+async fn chat_with_meeting(recording_dir: String, question: String) -> Result<ChatTurn, String> {
+    chat::ask(Path::new(&recording_dir), &question).await
+}
+
+#[tauri::command]
+fn get_meeting_chat(recording_dir: String) -> Result<Vec<ChatTurn>, String> {
+    chat::load_history(Path::new(&recording_dir))
+}
+
+#[tauri::command]
+fn clear_meeting_chat(recording_dir: String) -> Result<(), String> {
+    chat::clear_history(Path::new(&recording_dir))
+}
+
+/// Enable only the tray actions that make sense in the current recorder state
+fn update_tray_menu(app: &AppHandle, is_recording: bool, is_paused: bool) {
+    let Some(items) = app.try_state::<TrayItems>() else {
+        return;
+    };
+    let states = [
+        (&items.start, !is_recording),
+        (&items.stop, is_recording),
+        (&items.pause, is_recording && !is_paused),
+        (&items.resume, is_recording && is_paused),
+    ];
+    for (item, enabled) in states {
+        if let Err(e) = item.set_enabled(enabled) {
+            eprintln!("Failed to update tray menu: {}", e);
+        }
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -247,10 +581,11 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .manage(AppState {
             recorder: Mutex::new(AudioRecorder::new()),
-            recordings_dir: dirs::document_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("MeetingRecordings"),
+            recordings_dir: AppConfig::recordings_dir(),
             config: Mutex::new(AppConfig::load()),
+            library: Mutex::new(Library::new(AppConfig::recordings_dir())),
+            search: Mutex::new(SearchIndex::new(AppConfig::recordings_dir())),
+            summary_cancel: Mutex::new(None),
         })
         .setup(|app| {
             // Hide from dock on macOS
@@ -259,6 +594,43 @@ pub fn run() {
                 app.set_activation_policy(tauri::ActivationPolicy::Accessory);
             }
 
+            // Repair sessions a crash or power loss left without valid WAV headers
+            // or a mixed track. Runs in the background so startup isn't delayed.
+            {
+                let state = app.state::<AppState>();
+                let recordings_dir = state.recordings_dir.clone();
+                let config = state.config.lock().clone();
+                let app_handle = app.handle().clone();
+                std::thread::spawn(move || {
+                    let recovered = recovery::recover_unfinished_sessions(&recordings_dir, &config);
+                    if !recovered.is_empty() {
+                        let _ = app_handle.emit("recordings-recovered", recovered);
+                    }
+                    // index transcripts written while the app wasn't running
+                    let state = app_handle.state::<AppState>();
+                    if let Err(e) = state.search.lock().sync() {
+                        eprintln!("Failed to update search index: {}", e);
+                    }
+                });
+            }
+
+            // Voice-activated sessions are stopped here rather than by the recorder
+            // itself, so they are saved the same way as a stop from the UI
+            {
+                let app_handle = app.handle().clone();
+                let state = app.state::<AppState>();
+                state.recorder.lock().set_auto_record_handler(Arc::new(
+                    move |dir: &Path, event: AutoRecordEvent| {
+                        if let AutoRecordEvent::SilenceTimeout { .. } = event {
+                            // the handler runs on the writer thread, which stopping waits for
+                            let app = app_handle.clone();
+                            let dir = dir.to_path_buf();
+                            std::thread::spawn(move || auto_stop_recording(&app, &dir));
+                        }
+                    },
+                ));
+            }
+
             // Spawn bundled `ollama serve` as a sidecar so summarization works
             // out of the box. If a system Ollama is already serving on 11434,
             // this child will fail to bind and exit — that's fine, the existing
@@ -288,10 +660,16 @@ pub fn run() {
             let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let start = MenuItem::with_id(app, "start", "Start Recording", true, None::<&str>)?;
             let stop = MenuItem::with_id(app, "stop", "Stop Recording", false, None::<&str>)?;
-
-            let menu = Menu::with_items(app, &[&start, &stop, &quit])?;
-
-            let app_handle = app.handle().clone();
+            let pause = MenuItem::with_id(app, "pause", "Pause Recording", false, None::<&str>)?;
+            let resume = MenuItem::with_id(app, "resume", "Resume Recording", false, None::<&str>)?;
+
+            let menu = Menu::with_items(app, &[&start, &pause, &resume, &stop, &quit])?;
+            app.manage(TrayItems {
+                start: start.clone(),
+                stop: stop.clone(),
+                pause: pause.clone(),
+                resume: resume.clone(),
+            });
 
             // Build tray icon
             let _tray = TrayIconBuilder::new()
@@ -305,20 +683,20 @@ pub fn run() {
                             app.exit(0);
                         }
                         "start" => {
-                            let timestamp =
-                                chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-                            let recording_dir = state.recordings_dir.join(&timestamp);
+                            let recording_dir = audio::timestamped_dir(&state.recordings_dir);
 
                             if let Err(e) = std::fs::create_dir_all(&state.recordings_dir) {
                                 eprintln!("Failed to create directory: {}", e);
                                 return;
                             }
 
+                            let config = state.config.lock().clone();
                             let mut recorder = state.recorder.lock();
-                            match recorder.start_recording(&recording_dir) {
+                            recorder.apply_config(&config);
+                            match recorder.start_recording(&recording_dir, Some(app)) {
                                 Ok(_) => {
                                     println!("Recording started");
-                                    update_tray_menu(&app_handle, true);
+                                    update_tray_menu(app, true, false);
                                 }
                                 Err(e) => eprintln!("Failed to start recording: {}", e),
                             }
@@ -328,11 +706,25 @@ pub fn run() {
                             match recorder.stop_recording(Some(app)) {
                                 Ok(output) => {
                                     println!("Recording saved to: {:?}", output.directory);
-                                    update_tray_menu(&app_handle, false);
+                                    update_tray_menu(app, false, false);
                                 }
                                 Err(e) => eprintln!("Failed to stop recording: {}", e),
                             }
                         }
+                        "pause" => match state.recorder.lock().pause_recording() {
+                            Ok(_) => {
+                                println!("Recording paused");
+                                update_tray_menu(app, true, true);
+                            }
+                            Err(e) => eprintln!("Failed to pause recording: {}", e),
+                        },
+                        "resume" => match state.recorder.lock().resume_recording() {
+                            Ok(_) => {
+                                println!("Recording resumed");
+                                update_tray_menu(app, true, false);
+                            }
+                            Err(e) => eprintln!("Failed to resume recording: {}", e),
+                        },
                         _ => {}
                     }
                 })
@@ -373,6 +765,8 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             start_recording,
             stop_recording,
+            pause_recording,
+            resume_recording,
             is_recording,
             get_recording_stats,
             check_setup_needed,
@@ -382,10 +776,31 @@ pub fn run() {
             download_llm_model,
             complete_setup,
             get_config,
+            set_mix_settings,
+            set_level_settings,
+            set_auto_record_settings,
+            set_storage_codec,
+            list_input_devices,
+            list_system_devices,
+            set_input_device,
+            set_system_device,
+            list_meetings,
+            get_meeting,
+            rename_meeting,
+            delete_meeting,
+            search_meetings,
+            semantic_search,
             transcribe_recording,
             summarize_transcript,
+            cancel_summarization,
             open_editor,
             save_edited_transcript,
+            load_meeting_artifacts,
+            load_summary_version,
+            save_action_items,
+            chat_with_meeting,
+            get_meeting_chat,
+            clear_meeting_chat,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
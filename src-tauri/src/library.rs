@@ -0,0 +1,420 @@
+use crate::artifacts::{EDITED_TRANSCRIPT_FILE, SUMMARY_FILE, TRANSCRIPT_FILE};
+use crate::audio::IN_PROGRESS_MARKER;
+use crate::codec;
+use crate::session::{SessionMetadata, SESSION_FILE};
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Index of the recordings directory, kept next to the recordings so listing
+/// doesn't have to open every session
+const CACHE_FILE: &str = ".library.json";
+/// Bump when `Meeting` changes so old caches are rebuilt rather than misread
+const CACHE_VERSION: u32 = 1;
+
+/// One recording directory as shown in the meeting list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Meeting {
+    /// Name of the recording directory
+    pub id: String,
+    pub title: String,
+    pub started_at: DateTime<Local>,
+    pub duration_secs: f64,
+    pub has_transcript: bool,
+    pub has_summary: bool,
+    /// Still being recorded, or interrupted by a crash and not yet recovered
+    pub in_progress: bool,
+}
+
+/// A meeting with everything known about it on disk
+#[derive(Debug, Clone, Serialize)]
+pub struct MeetingDetails {
+    #[serde(flatten)]
+    pub meeting: Meeting,
+    pub directory: PathBuf,
+    pub session: Option<SessionMetadata>,
+    pub system_file: Option<PathBuf>,
+    pub mic_file: Option<PathBuf>,
+    pub mixed_file: Option<PathBuf>,
+}
+
+/// Modification times (ms since the epoch) a cached entry was built from. Files
+/// being added or removed touch the directory; renames rewrite session.json.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Stamp {
+    dir_ms: u64,
+    session_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedMeeting {
+    stamp: Stamp,
+    meeting: Meeting,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LibraryCache {
+    version: u32,
+    meetings: HashMap<String, CachedMeeting>,
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+fn stamp(dir: &Path) -> Option<Stamp> {
+    Some(Stamp {
+        dir_ms: modified_ms(dir)?,
+        session_ms: modified_ms(&dir.join(SESSION_FILE)).unwrap_or(0),
+    })
+}
+
+/// Session metadata for recordings made before session.json existed: the start
+/// time comes from the timestamped directory name, or failing that its mtime
+fn legacy_session(dir: &Path) -> SessionMetadata {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    let started_at = NaiveDateTime::parse_from_str(&name, "%Y-%m-%d_%H-%M-%S")
+        .ok()
+        .and_then(|t| t.and_local_timezone(Local).earliest())
+        .or_else(|| {
+            let modified = fs::metadata(dir).and_then(|m| m.modified()).ok()?;
+            Some(DateTime::<Local>::from(modified))
+        })
+        .unwrap_or_else(Local::now);
+    let mut session = SessionMetadata::new(started_at);
+    session.app_version = String::new();
+    session
+}
+
+fn load_session(dir: &Path) -> SessionMetadata {
+    SessionMetadata::load(dir).unwrap_or_else(|_| legacy_session(dir))
+}
+
+/// Title shown until the meeting is renamed
+fn default_title(started_at: &DateTime<Local>) -> String {
+    format!("Meeting {}", started_at.format("%Y-%m-%d %H:%M"))
+}
+
+fn read_meeting(id: &str, dir: &Path) -> Meeting {
+    let session = load_session(dir);
+    let has_transcript =
+        dir.join(TRANSCRIPT_FILE).exists() || dir.join(EDITED_TRANSCRIPT_FILE).exists();
+    Meeting {
+        id: id.to_string(),
+        title: session
+            .title
+            .clone()
+            .unwrap_or_else(|| default_title(&session.started_at)),
+        started_at: session.started_at,
+        duration_secs: session.recorded_secs().unwrap_or(0.0),
+        has_transcript,
+        has_summary: dir.join(SUMMARY_FILE).exists(),
+        in_progress: dir.join(IN_PROGRESS_MARKER).exists(),
+    }
+}
+
+/// The meetings in the recordings directory. Each one is a timestamped directory
+/// holding its audio, `session.json` and whatever transcripts and summaries were
+/// made from it.
+pub struct Library {
+    root: PathBuf,
+    // loaded from CACHE_FILE on first use
+    cache: Option<LibraryCache>,
+}
+
+impl Library {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, cache: None }
+    }
+
+    fn cache(&mut self) -> &mut LibraryCache {
+        let root = &self.root;
+        self.cache.get_or_insert_with(|| {
+            fs::read_to_string(root.join(CACHE_FILE))
+                .ok()
+                .and_then(|json| serde_json::from_str::<LibraryCache>(&json).ok())
+                .filter(|cache| cache.version == CACHE_VERSION)
+                .unwrap_or_else(|| LibraryCache {
+                    version: CACHE_VERSION,
+                    meetings: HashMap::new(),
+                })
+        })
+    }
+
+    fn save_cache(&self) {
+        let Some(cache) = &self.cache else {
+            return;
+        };
+        // the cache can always be rebuilt, so failing to write it is not an error
+        let result = serde_json::to_string(cache)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                fs::write(self.root.join(CACHE_FILE), json).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            eprintln!("Failed to write library cache: {}", e);
+        }
+    }
+
+    /// Resolve a meeting id to its directory, refusing anything that isn't a plain
+    /// directory name inside the library
+    fn meeting_dir(&self, id: &str) -> Result<PathBuf, String> {
+        let is_plain_name = Path::new(id)
+            .components()
+            .eq([std::path::Component::Normal(std::ffi::OsStr::new(id))]);
+        if !is_plain_name || id.starts_with('.') {
+            return Err(format!("Invalid meeting id: {}", id));
+        }
+        let dir = self.root.join(id);
+        if !dir.is_dir() {
+            return Err(format!("Meeting not found: {}", id));
+        }
+        Ok(dir)
+    }
+
+    /// Every meeting, newest first. Only directories that changed since the last
+    /// call are read again.
+    pub fn list(&mut self) -> Result<Vec<Meeting>, String> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to read recordings directory: {}", e)),
+        };
+
+        let mut ids: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|id| !id.starts_with('.'))
+            .collect();
+        ids.sort();
+
+        let root = self.root.clone();
+        let cache = self.cache();
+        let mut changed = false;
+        let mut meetings = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let dir = root.join(id);
+            let Some(stamp) = stamp(&dir) else {
+                continue;
+            };
+            match cache.meetings.get(id) {
+                Some(cached) if cached.stamp == stamp => meetings.push(cached.meeting.clone()),
+                _ => {
+                    let meeting = read_meeting(id, &dir);
+                    // sessions still being written change constantly; don't cache them
+                    if !meeting.in_progress {
+                        cache.meetings.insert(
+                            id.clone(),
+                            CachedMeeting {
+                                stamp,
+                                meeting: meeting.clone(),
+                            },
+                        );
+                        changed = true;
+                    }
+                    meetings.push(meeting);
+                }
+            }
+        }
+
+        // forget meetings deleted behind our back
+        let before = cache.meetings.len();
+        cache.meetings.retain(|id, _| ids.binary_search(id).is_ok());
+        changed |= cache.meetings.len() != before;
+        if changed {
+            self.save_cache();
+        }
+
+        meetings.sort_by_key(|m| std::cmp::Reverse(m.started_at));
+        Ok(meetings)
+    }
+
+    pub fn get(&self, id: &str) -> Result<MeetingDetails, String> {
+        let dir = self.meeting_dir(id)?;
+        Ok(MeetingDetails {
+            meeting: read_meeting(id, &dir),
+            session: SessionMetadata::load(&dir).ok(),
+            system_file: codec::find_track(&dir, "system"),
+            mic_file: codec::find_track(&dir, "mic"),
+            mixed_file: codec::find_track(&dir, "mixed"),
+            directory: dir,
+        })
+    }
+
+    /// Set the meeting's title. It is kept in session.json, so the directory (and
+    /// with it the id) never changes.
+    pub fn rename(&mut self, id: &str, title: &str) -> Result<Meeting, String> {
+        let dir = self.meeting_dir(id)?;
+        let title = title.trim();
+        if title.is_empty() {
+            return Err("Title can't be empty".to_string());
+        }
+
+        // a recorder or a background stage may be writing session.json right now
+        if dir.join(SESSION_FILE).exists() {
+            SessionMetadata::update(&dir, |session| session.title = Some(title.to_string()))?;
+        } else {
+            let mut session = load_session(&dir);
+            session.title = Some(title.to_string());
+            session.save(&dir)?;
+        }
+
+        let meeting = read_meeting(id, &dir);
+        if let Some(stamp) = stamp(&dir).filter(|_| !meeting.in_progress) {
+            self.cache().meetings.insert(
+                id.to_string(),
+                CachedMeeting {
+                    stamp,
+                    meeting: meeting.clone(),
+                },
+            );
+            self.save_cache();
+        }
+        Ok(meeting)
+    }
+
+    /// Remove the meeting's directory with everything in it
+    pub fn delete(&mut self, id: &str) -> Result<(), String> {
+        let dir = self.meeting_dir(id)?;
+        if dir.join(IN_PROGRESS_MARKER).exists() {
+            return Err("Can't delete a meeting that is still being recorded".to_string());
+        }
+        fs::remove_dir_all(&dir).map_err(|e| format!("Failed to delete meeting: {}", e))?;
+        if self.cache().meetings.remove(id).is_some() {
+            self.save_cache();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn library_dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn add_session(root: &Path, id: &str, started_at: DateTime<Local>, secs: i64) -> PathBuf {
+        let dir = root.join(id);
+        fs::create_dir_all(&dir).unwrap();
+        let mut session = SessionMetadata::new(started_at);
+        session.ended_at = Some(started_at + chrono::Duration::seconds(secs));
+        session.save(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_list_newest_first_with_artifacts() {
+        let root = library_dir("library_test_list");
+        let at = |h| Local.with_ymd_and_hms(2024, 5, 1, h, 0, 0).unwrap();
+        add_session(&root, "2024-05-01_09-00-00", at(9), 1800);
+        let dir = add_session(&root, "2024-05-01_14-00-00", at(14), 600);
+        fs::write(dir.join("transcript.json"), "{}").unwrap();
+        // from before session.json: the date comes from the directory name
+        let legacy = root.join("2024-04-30_16-30-00");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("summary.json"), "{}").unwrap();
+        fs::write(root.join(CACHE_FILE), "not json").unwrap();
+
+        let meetings = Library::new(root.clone()).list().unwrap();
+        let ids: Vec<&str> = meetings.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "2024-05-01_14-00-00",
+                "2024-05-01_09-00-00",
+                "2024-04-30_16-30-00"
+            ]
+        );
+        assert_eq!(meetings[0].title, "Meeting 2024-05-01 14:00");
+        assert_eq!(meetings[0].duration_secs, 600.0);
+        assert!(meetings[0].has_transcript && !meetings[0].has_summary);
+        assert_eq!(meetings[2].title, "Meeting 2024-04-30 16:30");
+        assert!(meetings[2].has_summary);
+    }
+
+    #[test]
+    fn test_unchanged_meetings_come_from_cache() {
+        let root = library_dir("library_test_cache");
+        let started_at = Local.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let dir = add_session(&root, "2024-05-01_09-00-00", started_at, 60);
+        Library::new(root.clone()).list().unwrap();
+
+        // doctor the cache; a fresh library must trust it while nothing changed
+        let json = fs::read_to_string(root.join(CACHE_FILE)).unwrap();
+        fs::write(
+            root.join(CACHE_FILE),
+            json.replace("Meeting 2024", "Cached 2024"),
+        )
+        .unwrap();
+        let mut library = Library::new(root.clone());
+        assert_eq!(library.list().unwrap()[0].title, "Cached 2024-05-01 09:00");
+
+        // a new file in the directory invalidates the entry
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(dir.join("summary.json"), "{}").unwrap();
+        let meeting = &library.list().unwrap()[0];
+        assert_eq!(meeting.title, "Meeting 2024-05-01 09:00");
+        assert!(meeting.has_summary);
+    }
+
+    #[test]
+    fn test_rename_and_delete() {
+        let root = library_dir("library_test_rename");
+        let started_at = Local.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        add_session(&root, "2024-05-01_09-00-00", started_at, 60);
+        let legacy = root.join("2024-04-30_16-30-00");
+        fs::create_dir_all(&legacy).unwrap();
+
+        let mut library = Library::new(root.clone());
+        library.list().unwrap();
+        library
+            .rename("2024-05-01_09-00-00", "  Planning  ")
+            .unwrap();
+        // a legacy recording gets a session.json to keep its title in
+        library.rename("2024-04-30_16-30-00", "Retro").unwrap();
+        assert!(library.rename("2024-04-30_16-30-00", " ").is_err());
+
+        let meetings = Library::new(root.clone()).list().unwrap();
+        assert_eq!(meetings[0].title, "Planning");
+        assert_eq!(meetings[1].title, "Retro");
+        let details = library.get("2024-04-30_16-30-00").unwrap();
+        assert_eq!(
+            details.session.unwrap().started_at,
+            Local.with_ymd_and_hms(2024, 4, 30, 16, 30, 0).unwrap()
+        );
+
+        fs::write(
+            root.join("2024-05-01_09-00-00").join(IN_PROGRESS_MARKER),
+            "",
+        )
+        .unwrap();
+        assert!(library.delete("2024-05-01_09-00-00").is_err());
+        library.delete("2024-04-30_16-30-00").unwrap();
+        assert!(!legacy.exists());
+        assert_eq!(library.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_ids_cannot_escape_the_library() {
+        let root = library_dir("library_test_ids");
+        let mut library = Library::new(root.join("recordings"));
+        fs::create_dir_all(root.join("recordings")).unwrap();
+        for id in ["..", "../recordings", "/tmp", "", ".", "a/b"] {
+            assert!(library.delete(id).is_err(), "{}", id);
+            assert!(library.get(id).is_err(), "{}", id);
+        }
+        assert!(library.get("missing").is_err());
+        assert!(root.exists());
+    }
+}
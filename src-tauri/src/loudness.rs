@@ -0,0 +1,215 @@
+use hound::WavReader;
+use std::path::Path;
+
+/// Blocks quieter than this never count towards integrated loudness (BS.1770)
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks more than this far below the ungated loudness are dropped too
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Second-order IIR section (direct form I)
+#[derive(Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The two K-weighting stages (high shelf + high pass) for an arbitrary sample
+/// rate, derived the same way as libebur128 so 48 kHz matches the BS.1770 tables
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        x: [0.0; 2],
+        y: [0.0; 2],
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        x: [0.0; 2],
+        y: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Streaming EBU R128 integrated loudness meter. Keeps one energy value per
+/// 100 ms, so memory grows by ~300 KB per hour of audio regardless of channels.
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    samples_per_step: usize,
+    // accumulated K-weighted energy of the current 100 ms step
+    step_energy: f64,
+    step_samples: usize,
+    step_energies: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    pub fn new(channels: u16, sample_rate: u32) -> Self {
+        Self {
+            channels: channels.max(1) as usize,
+            filters: vec![k_weighting(sample_rate); channels.max(1) as usize],
+            samples_per_step: (sample_rate / 10).max(1) as usize,
+            step_energy: 0.0,
+            step_samples: 0,
+            step_energies: Vec::new(),
+        }
+    }
+
+    /// Feed interleaved samples
+    pub fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            // channel weights are 1.0 for everything but surround channels
+            for (sample, [shelf, high_pass]) in frame.iter().zip(self.filters.iter_mut()) {
+                let weighted = high_pass.process(shelf.process(*sample as f64));
+                self.step_energy += weighted * weighted;
+            }
+            self.step_samples += 1;
+            if self.step_samples == self.samples_per_step {
+                self.step_energies
+                    .push(self.step_energy / self.samples_per_step as f64);
+                self.step_energy = 0.0;
+                self.step_samples = 0;
+            }
+        }
+    }
+
+    /// Gated integrated loudness in LUFS, or None if the audio is shorter than one
+    /// 400 ms block or entirely below the absolute gate (silence)
+    pub fn integrated(&self) -> Option<f64> {
+        // 400 ms blocks with 75% overlap = every window of four 100 ms steps
+        let blocks: Vec<f64> = self
+            .step_energies
+            .windows(4)
+            .map(|w| w.iter().sum::<f64>() / 4.0)
+            .filter(|&energy| energy_to_lufs(energy) > ABSOLUTE_GATE_LUFS)
+            .collect();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let ungated = energy_to_lufs(blocks.iter().sum::<f64>() / blocks.len() as f64);
+        let relative_gate = ungated + RELATIVE_GATE_LU;
+        let gated: Vec<f64> = blocks
+            .into_iter()
+            .filter(|&energy| energy_to_lufs(energy) > relative_gate)
+            .collect();
+        if gated.is_empty() {
+            return None;
+        }
+        Some(energy_to_lufs(
+            gated.iter().sum::<f64>() / gated.len() as f64,
+        ))
+    }
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.max(1e-20).log10()
+}
+
+/// Measure the integrated loudness of a float WAV file, streaming it in blocks
+pub fn measure_wav(path: &Path) -> Result<Option<f64>, String> {
+    let mut reader =
+        WavReader::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let spec = reader.spec();
+    let mut meter = LoudnessMeter::new(spec.channels, spec.sample_rate);
+
+    let block_len = spec.sample_rate as usize * spec.channels as usize;
+    let mut block = Vec::with_capacity(block_len);
+    for sample in reader.samples::<f32>() {
+        // a truncated sample at the end of a recovered file just ends the measurement
+        let Ok(sample) = sample else { break };
+        block.push(sample);
+        if block.len() == block_len {
+            meter.push(&block);
+            block.clear();
+        }
+    }
+    meter.push(&block);
+    Ok(meter.integrated())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frames: usize, channels: u16, amplitude: f32, freq: f32, rate: u32) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|i| {
+                let s = (i as f32 * freq * std::f32::consts::TAU / rate as f32).sin() * amplitude;
+                std::iter::repeat_n(s, channels as usize)
+            })
+            .collect()
+    }
+
+    fn measure(samples: &[f32], channels: u16, rate: u32) -> Option<f64> {
+        let mut meter = LoudnessMeter::new(channels, rate);
+        for chunk in samples.chunks(4099) {
+            meter.push(chunk);
+        }
+        meter.integrated()
+    }
+
+    #[test]
+    fn test_stereo_1khz_at_minus_23_dbfs_reads_minus_23_lufs() {
+        // EBU Tech 3341 test case 1
+        let amplitude = 10f32.powf(-23.0 / 20.0);
+        let lufs = measure(&sine(48000 * 5, 2, amplitude, 1000.0, 48000), 2, 48000).unwrap();
+        assert!((lufs - -23.0).abs() < 0.1, "got {}", lufs);
+    }
+
+    #[test]
+    fn test_mono_is_3db_below_stereo_and_rate_independent() {
+        let amplitude = 10f32.powf(-20.0 / 20.0);
+        let lufs = measure(&sine(44100 * 5, 1, amplitude, 1000.0, 44100), 1, 44100).unwrap();
+        assert!((lufs - -23.0).abs() < 0.1, "got {}", lufs);
+    }
+
+    #[test]
+    fn test_silence_and_short_audio_have_no_loudness() {
+        assert_eq!(measure(&vec![0.0; 48000 * 2], 1, 48000), None);
+        assert_eq!(measure(&sine(4800, 1, 0.5, 1000.0, 48000), 1, 48000), None);
+    }
+
+    #[test]
+    fn test_relative_gate_ignores_quiet_passages() {
+        // speech-like: loud bursts with long near-silent gaps in between
+        let amplitude = 10f32.powf(-20.0 / 20.0);
+        let mut samples = sine(48000 * 3, 1, amplitude, 1000.0, 48000);
+        samples.extend(sine(48000 * 6, 1, amplitude / 100.0, 1000.0, 48000));
+        let lufs = measure(&samples, 1, 48000).unwrap();
+        // ungated this would read about -28; the blocks straddling the transition still count
+        assert!((lufs - -23.0).abs() < 0.3, "got {}", lufs);
+    }
+}
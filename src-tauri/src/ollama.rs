@@ -0,0 +1,461 @@
+use futures_util::StreamExt;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Where the bundled sidecar (or a system-wide `ollama serve`) listens
+pub const DEFAULT_URL: &str = "http://localhost:11434";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Whole-request limit for a reply that isn't streamed; generating a summary of a
+/// long meeting on a laptop can take minutes
+const CHAT_TIMEOUT: Duration = Duration::from_secs(600);
+/// Longest a streamed reply may go without sending anything. Loading the model and
+/// reading a long prompt both happen before the first token arrives.
+const STREAM_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+const EMBED_TIMEOUT: Duration = Duration::from_secs(120);
+const SHOW_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// "system", "user" or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChatOptions {
+    num_ctx: u32,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<ChatOptions>,
+    /// JSON schema the reply must follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ChatMessage,
+}
+
+/// One line of a streamed `/api/chat` response
+#[derive(Deserialize)]
+struct ChatChunk {
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    done: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Serialize)]
+struct ShowRequest<'a> {
+    model: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ShowResponse {
+    /// Keyed by architecture, e.g. `qwen2.context_length`
+    #[serde(default)]
+    model_info: HashMap<String, serde_json::Value>,
+}
+
+/// Client for the parts of Ollama's HTTP API the app uses
+#[derive(Clone)]
+pub struct Ollama {
+    base_url: String,
+    http: reqwest::Client,
+    idle_timeout: Duration,
+}
+
+impl Ollama {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self {
+            base_url: base_url.into(),
+            http,
+            idle_timeout: STREAM_IDLE_TIMEOUT,
+        }
+    }
+
+    /// Give up on a streamed reply after `timeout` without any data
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// The server at `DEFAULT_URL`
+    pub fn local() -> Self {
+        Self::new(DEFAULT_URL)
+    }
+
+    /// POST `body` as JSON. `timeout` limits the whole request, body included;
+    /// streamed replies pass `None` and time out between chunks instead.
+    async fn send<T: Serialize>(
+        &self,
+        path: &str,
+        body: &T,
+        timeout: Option<Duration>,
+    ) -> Result<reqwest::Response, String> {
+        let mut request = self.http.post(format!("{}{}", self.base_url, path));
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        let resp = request
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| format!("Ollama request failed: {}. Is `ollama serve` running?", e))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(format!("Ollama returned {}: {}", status, body));
+        }
+        Ok(resp)
+    }
+
+    async fn post<T: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &T,
+        timeout: Duration,
+    ) -> Result<R, String> {
+        self.send(path, body, Some(timeout))
+            .await?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Ollama response: {}", e))
+    }
+
+    /// Send a conversation to `/api/chat` and return the reply
+    pub async fn chat(&self, model: &str, messages: &[ChatMessage]) -> Result<String, String> {
+        let req = ChatRequest {
+            model,
+            messages,
+            stream: false,
+            options: None,
+            format: None,
+        };
+        let chat: ChatResponse = self.post("/api/chat", &req, CHAT_TIMEOUT).await?;
+        Ok(chat.message.content)
+    }
+
+    /// Like `chat`, but with the reply streamed: `on_chunk` is called with each
+    /// piece (usually a single token) as it is generated. Returns the whole reply.
+    /// `num_ctx` overrides the context size Ollama loads the model with, which by
+    /// default is much smaller than most models support. With a JSON schema as
+    /// `format`, Ollama constrains the reply to JSON that follows it.
+    pub async fn chat_stream(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+        num_ctx: Option<u32>,
+        format: Option<&serde_json::Value>,
+        mut on_chunk: impl FnMut(&str),
+    ) -> Result<String, String> {
+        let req = ChatRequest {
+            model,
+            messages,
+            stream: true,
+            options: num_ctx.map(|num_ctx| ChatOptions { num_ctx }),
+            format,
+        };
+        // a long reply may take far longer than any total limit, so only silence counts
+        let idle = self.idle_timeout;
+        let stalled = || format!("Ollama stopped responding: nothing received for {:?}", idle);
+        let mut stream = tokio::time::timeout(idle, self.send("/api/chat", &req, None))
+            .await
+            .map_err(|_| stalled())??
+            .bytes_stream();
+
+        // the reply is newline-delimited JSON, but lines can be split across reads
+        let mut pending: Vec<u8> = Vec::new();
+        let mut reply = String::new();
+        while let Some(bytes) = tokio::time::timeout(idle, stream.next())
+            .await
+            .map_err(|_| stalled())?
+        {
+            let bytes = bytes.map_err(|e| format!("Ollama stream failed: {}", e))?;
+            pending.extend_from_slice(&bytes);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                if line.iter().all(u8::is_ascii_whitespace) {
+                    continue;
+                }
+                let chunk: ChatChunk = serde_json::from_slice(&line)
+                    .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+                if let Some(error) = chunk.error {
+                    return Err(format!("Ollama failed: {}", error));
+                }
+                if let Some(message) = chunk.message {
+                    on_chunk(&message.content);
+                    reply.push_str(&message.content);
+                }
+                if chunk.done {
+                    return Ok(reply);
+                }
+            }
+        }
+        Err("Ollama closed the stream before the reply was complete".to_string())
+    }
+
+    /// Context length (in tokens) the model was trained with, from `/api/show`;
+    /// `None` if Ollama doesn't report one
+    pub async fn context_length(&self, model: &str) -> Result<Option<u32>, String> {
+        let resp: ShowResponse = self
+            .post("/api/show", &ShowRequest { model }, SHOW_TIMEOUT)
+            .await?;
+        Ok(resp
+            .model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|tokens| tokens.min(u32::MAX as u64) as u32))
+    }
+
+    /// Embed each of `input` with `/api/embed`, one vector per input in the same order
+    pub async fn embed(&self, model: &str, input: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let req = EmbedRequest { model, input };
+        let resp: EmbedResponse = self.post("/api/embed", &req, EMBED_TIMEOUT).await?;
+        if resp.embeddings.len() != input.len() {
+            return Err(format!(
+                "Ollama returned {} embeddings for {} inputs",
+                resp.embeddings.len(),
+                input.len()
+            ));
+        }
+        Ok(resp.embeddings)
+    }
+}
+
+/// Stand-in for Ollama in tests: a local HTTP server that answers every request
+/// with whatever `respond` returns for its path and JSON body.
+#[cfg(test)]
+pub(crate) mod stub {
+    use serde_json::Value;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    /// Start the server and return its base URL, for `Ollama::new`
+    pub fn serve<F>(respond: F) -> String
+    where
+        F: Fn(&str, &Value) -> String + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or("/")
+                    .to_string();
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let body = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+                let response = respond(&path, &body);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+            }
+        });
+        url
+    }
+
+    /// Run `future` to completion on a fresh single-threaded runtime
+    pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use stub::block_on;
+
+    #[test]
+    fn test_chat_and_embed_against_stub() {
+        let url = stub::serve(|path, body| match path {
+            "/api/chat" => {
+                let question = body["messages"][1]["content"].as_str().unwrap_or_default();
+                json!({ "message": { "role": "assistant", "content": format!("echo: {}", question) } })
+                    .to_string()
+            }
+            "/api/embed" => {
+                let n = body["input"].as_array().map_or(0, |a| a.len());
+                json!({ "embeddings": vec![[1.0, 0.0]; n] }).to_string()
+            }
+            _ => "{}".to_string(),
+        });
+        let ollama = Ollama::new(url);
+
+        let messages = [
+            ChatMessage::new("system", "Be brief."),
+            ChatMessage::new("user", "hi"),
+        ];
+        assert_eq!(block_on(ollama.chat("m", &messages)).unwrap(), "echo: hi");
+
+        let vectors = block_on(ollama.embed("m", &["a".into(), "b".into()])).unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0]; 2]);
+    }
+
+    #[test]
+    fn test_chat_stream_reassembles_chunks() {
+        let url = stub::serve(|_, body| {
+            assert_eq!(body["stream"], true);
+            assert_eq!(body["format"]["type"], "object");
+            ["Hel", "lo", " there", ""]
+                .iter()
+                .enumerate()
+                .map(|(i, piece)| {
+                    let line = json!({
+                        "message": { "role": "assistant", "content": piece },
+                        "done": i == 3,
+                    });
+                    format!("{}\n", line)
+                })
+                .collect()
+        });
+        let mut pieces = Vec::new();
+        let schema = json!({ "type": "object" });
+        let reply = block_on(
+            Ollama::new(url).chat_stream("m", &[], None, Some(&schema), |p| {
+                pieces.push(p.to_string())
+            }),
+        )
+        .unwrap();
+        assert_eq!(reply, "Hello there");
+        assert_eq!(pieces, ["Hel", "lo", " there", ""]);
+    }
+
+    #[test]
+    fn test_chat_stream_reports_errors_and_truncation() {
+        let url = stub::serve(|_, _| "{\"error\":\"model not found\"}\n".to_string());
+        let err = block_on(Ollama::new(url).chat_stream("m", &[], None, None, |_| {})).unwrap_err();
+        assert!(err.contains("model not found"), "{}", err);
+
+        let url = stub::serve(|_, _| {
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":false}\n".to_string()
+        });
+        assert!(block_on(Ollama::new(url).chat_stream("m", &[], None, None, |_| {})).is_err());
+    }
+
+    #[test]
+    fn test_chat_stream_times_out_when_ollama_goes_quiet() {
+        use std::io::{Read, Write};
+
+        // sends the first token, then hangs without closing the connection
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 4096];
+            let _ = stream.read(&mut request);
+            let line = "{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"done\":false}\n";
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{}",
+                line
+            );
+            std::thread::sleep(Duration::from_secs(5));
+        });
+
+        let ollama = Ollama::new(url).with_idle_timeout(Duration::from_millis(200));
+        let mut pieces = Vec::new();
+        let err =
+            block_on(ollama.chat_stream("m", &[], None, None, |p| pieces.push(p.to_string())))
+                .unwrap_err();
+        assert!(err.contains("stopped responding"), "{}", err);
+        assert_eq!(pieces, ["Hi"]);
+    }
+
+    #[test]
+    fn test_context_length_from_model_info() {
+        let url = stub::serve(|path, body| {
+            assert_eq!(path, "/api/show");
+            match body["model"].as_str() {
+                Some("qwen") => json!({
+                    "model_info": {
+                        "general.architecture": "qwen2",
+                        "qwen2.context_length": 32768,
+                    }
+                })
+                .to_string(),
+                _ => json!({ "details": {} }).to_string(),
+            }
+        });
+        let ollama = Ollama::new(url);
+        assert_eq!(
+            block_on(ollama.context_length("qwen")).unwrap(),
+            Some(32768)
+        );
+        assert_eq!(block_on(ollama.context_length("other")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_unreachable_server_mentions_ollama_serve() {
+        // nothing listens on the discard port
+        let ollama = Ollama::new("http://127.0.0.1:9");
+        let err = block_on(ollama.chat("m", &[])).unwrap_err();
+        assert!(err.contains("Is `ollama serve` running"), "{}", err);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::audio::{self, SessionLock, IN_PROGRESS_MARKER};
+use crate::codec::{self, StorageCodec};
+use crate::config::AppConfig;
+use crate::session::SessionMetadata;
+use chrono::{DateTime, Local};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Rewrite the RIFF and data chunk sizes of a WAV file from the bytes actually on
+/// disk. Used on files whose writer never got to `finalize()` (crash, power loss).
+/// A trailing partial frame is truncated. Returns the repaired data length in bytes.
+pub fn repair_wav_header(path: &Path) -> Result<u64, String> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let file_len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff)
+        .map_err(|e| format!("Failed to read WAV header: {}", e))?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(format!("{} is not a WAV file", path.display()));
+    }
+
+    // Walk the chunks until we reach `data`, which is always last in files we write
+    let mut pos: u64 = 12;
+    let mut block_align: u64 = 1;
+    loop {
+        if pos + 8 > file_len {
+            return Err(format!("No data chunk in {}", path.display()));
+        }
+
+        let mut chunk = [0u8; 8];
+        read_at(&mut file, pos, &mut chunk)?;
+        let id = &chunk[0..4];
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+
+        if id == b"fmt " {
+            // block_align lives at offset 12 in the fmt chunk body
+            let mut align = [0u8; 2];
+            read_at(&mut file, pos + 8 + 12, &mut align)?;
+            block_align = (u16::from_le_bytes(align) as u64).max(1);
+        } else if id == b"data" {
+            let data_start = pos + 8;
+            let available = file_len.saturating_sub(data_start);
+            let data_len = (available - available % block_align).min(u32::MAX as u64 - data_start);
+
+            file.set_len(data_start + data_len)
+                .map_err(|e| format!("Failed to truncate {}: {}", path.display(), e))?;
+            write_u32_at(&mut file, pos + 4, data_len as u32)?;
+            write_u32_at(&mut file, 4, (data_start + data_len - 8) as u32)?;
+            file.flush()
+                .map_err(|e| format!("Failed to flush {}: {}", path.display(), e))?;
+            return Ok(data_len);
+        }
+
+        // chunks are padded to an even size
+        pos += 8 + size + (size & 1);
+    }
+}
+
+fn read_at(file: &mut File, pos: u64, buf: &mut [u8]) -> Result<(), String> {
+    file.seek(SeekFrom::Start(pos))
+        .and_then(|_| file.read_exact(buf))
+        .map_err(|e| format!("Failed to read WAV chunk: {}", e))
+}
+
+fn write_u32_at(file: &mut File, pos: u64, value: u32) -> Result<(), String> {
+    file.seek(SeekFrom::Start(pos))
+        .and_then(|_| file.write_all(&value.to_le_bytes()))
+        .map_err(|e| format!("Failed to patch WAV header: {}", e))
+}
+
+/// Repair one interrupted session: fix both track headers, regenerate the mixed
+/// track and store everything as configured, as `stop_recording` would have
+pub fn recover_session(dir: &Path, config: &AppConfig) -> Result<(), String> {
+    let system_file = codec::track_path(dir, "system", StorageCodec::Wav);
+    let mic_file = codec::track_path(dir, "mic", StorageCodec::Wav);
+
+    // the last write before the crash is as close as we get to when it ended;
+    // read it before the repair touches the files
+    let ended_at = [&system_file, &mic_file]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+        .map(DateTime::<Local>::from);
+
+    let system_bytes = repair_wav_header(&system_file)?;
+    let mic_bytes = repair_wav_header(&mic_file)?;
+    println!(
+        "Repaired {:?}: system={} bytes, mic={} bytes",
+        dir, system_bytes, mic_bytes
+    );
+
+    // what stop_recording would have noted; both tracks hold 32-bit float samples
+    let update = SessionMetadata::update(dir, |session| {
+        if session.ended_at.is_none() {
+            session.ended_at = ended_at;
+        }
+        if let Some(tracks) = session.tracks.as_mut() {
+            tracks.system.samples = system_bytes / 4;
+            tracks.mic.samples = mic_bytes / 4;
+        }
+    });
+    if let Err(e) = update {
+        eprintln!(
+            "Failed to record recovered tracks in session metadata: {}",
+            e
+        );
+    }
+
+    audio::finish_session_files(dir, config, None)?;
+    Ok(())
+}
+
+/// Scan the recordings directory for sessions that never reached `stop_recording`
+/// and recover them. Sessions whose marker is still locked by a running recorder
+/// are skipped. Returns the directories that were repaired.
+pub fn recover_unfinished_sessions(recordings_dir: &Path, config: &AppConfig) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(recordings_dir) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut recovered = Vec::new();
+    for dir in entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join(IN_PROGRESS_MARKER).exists())
+    {
+        let _lock = match SessionLock::acquire(&dir) {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                println!("Skipping {:?}: still being recorded", dir);
+                continue;
+            }
+            Err(e) => {
+                eprintln!("Failed to recover {:?}: {}", dir, e);
+                continue;
+            }
+        };
+
+        println!("Recovering unfinished recording: {:?}", dir);
+        match recover_session(&dir, config) {
+            Ok(()) => recovered.push(dir),
+            Err(e) => eprintln!("Failed to recover {:?}: {}", dir, e),
+        }
+    }
+    recovered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::{SessionTracks, TrackInfo};
+
+    /// Write a float WAV, then zero the size fields like a writer that died before finalize()
+    fn write_crashed_wav(path: &Path, channels: u16, frames: usize, value: f32) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: 48000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for _ in 0..frames * channels as usize {
+            writer.write_sample(value).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .unwrap();
+        let len = file.metadata().unwrap().len();
+        write_u32_at(&mut file, 4, 0).unwrap();
+        write_u32_at(
+            &mut file,
+            len - (frames * channels as usize * 4) as u64 - 4,
+            0,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_repair_wav_header_restores_length() {
+        let path = std::env::temp_dir().join("recovery_test_repair.wav");
+        write_crashed_wav(&path, 2, 1000, 0.5);
+        assert_eq!(hound::WavReader::open(&path).unwrap().len(), 0);
+
+        let data_len = repair_wav_header(&path).unwrap();
+        assert_eq!(data_len, 1000 * 2 * 4);
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.len(), 2000);
+        assert!(reader.samples::<f32>().all(|s| s.unwrap() == 0.5));
+    }
+
+    #[test]
+    fn test_repair_wav_header_drops_partial_frame() {
+        let path = std::env::temp_dir().join("recovery_test_partial.wav");
+        write_crashed_wav(&path, 2, 10, 0.25);
+
+        // half a frame (one stereo sample) made it to disk before the crash
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&0.25f32.to_le_bytes()).unwrap();
+        drop(file);
+
+        repair_wav_header(&path).unwrap();
+        assert_eq!(hound::WavReader::open(&path).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn test_recover_unfinished_sessions() {
+        let base = std::env::temp_dir().join("recovery_test_sessions");
+        let _ = std::fs::remove_dir_all(&base);
+        let crashed = base.join("2024-01-01_10-00-00");
+        let finished = base.join("2024-01-01_11-00-00");
+        std::fs::create_dir_all(&crashed).unwrap();
+        std::fs::create_dir_all(&finished).unwrap();
+
+        write_crashed_wav(&crashed.join("system.wav"), 2, 4800, 0.5);
+        write_crashed_wav(&crashed.join("mic.wav"), 1, 4800, 0.25);
+        std::fs::write(crashed.join(IN_PROGRESS_MARKER), "").unwrap();
+        // session.json as start_recording leaves it
+        let mut session = SessionMetadata::new(Local::now() - chrono::Duration::minutes(5));
+        let track = |channels| TrackInfo {
+            sample_rate: 48000,
+            channels,
+            ..Default::default()
+        };
+        session.tracks = Some(SessionTracks {
+            system: track(2),
+            mic: track(1),
+        });
+        session.save(&crashed).unwrap();
+
+        let recovered = recover_unfinished_sessions(&base, &AppConfig::default());
+        assert_eq!(recovered, vec![crashed.clone()]);
+        assert!(!crashed.join(IN_PROGRESS_MARKER).exists());
+
+        let mixed = hound::WavReader::open(crashed.join("mixed.wav")).unwrap();
+        assert_eq!(mixed.spec().channels, 2);
+        assert_eq!(mixed.len(), 4800 * 2);
+
+        // the session knows how long it is and roughly when it ended
+        let session = SessionMetadata::load(&crashed).unwrap();
+        assert_eq!(session.recorded_secs(), Some(0.1));
+        assert_eq!(session.tracks.unwrap().system.samples, 9600);
+        assert!(session.ended_at.unwrap() > session.started_at);
+
+        // nothing left to do on a second scan
+        assert!(recover_unfinished_sessions(&base, &AppConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_recover_skips_locked_session() {
+        let base = std::env::temp_dir().join("recovery_test_locked");
+        let _ = std::fs::remove_dir_all(&base);
+        let live = base.join("2024-01-01_10-00-00");
+        std::fs::create_dir_all(&live).unwrap();
+
+        write_crashed_wav(&live.join("system.wav"), 2, 4800, 0.5);
+        write_crashed_wav(&live.join("mic.wav"), 1, 4800, 0.25);
+        let system_len = std::fs::metadata(live.join("system.wav")).unwrap().len();
+        let lock = SessionLock::create(&live).unwrap();
+
+        // a recorder still holds the marker: nothing may be repaired or mixed
+        assert!(recover_unfinished_sessions(&base, &AppConfig::default()).is_empty());
+        assert!(live.join(IN_PROGRESS_MARKER).exists());
+        assert!(!live.join("mixed.wav").exists());
+        assert_eq!(
+            std::fs::metadata(live.join("system.wav")).unwrap().len(),
+            system_len
+        );
+        assert_eq!(
+            hound::WavReader::open(live.join("system.wav"))
+                .unwrap()
+                .len(),
+            0
+        );
+
+        // once the recorder is gone the session is recovered as usual
+        drop(lock);
+        assert_eq!(
+            recover_unfinished_sessions(&base, &AppConfig::default()),
+            vec![live.clone()]
+        );
+    }
+
+    #[test]
+    fn test_recover_session_with_flac_storage() {
+        let dir = std::env::temp_dir().join("recovery_test_flac");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        write_crashed_wav(&dir.join("system.wav"), 2, 4800, 0.5);
+        write_crashed_wav(&dir.join("mic.wav"), 1, 4800, 0.25);
+        std::fs::write(dir.join(IN_PROGRESS_MARKER), "").unwrap();
+
+        let config = AppConfig {
+            storage_codec: StorageCodec::Flac,
+            ..Default::default()
+        };
+        recover_session(&dir, &config).unwrap();
+        assert!(!dir.join(IN_PROGRESS_MARKER).exists());
+        assert!(!dir.join("system.wav").exists());
+        assert!(!dir.join("mic.wav").exists());
+
+        let mic = codec::decode_file(&dir.join("mic.flac")).unwrap();
+        assert_eq!(mic.samples.len(), 4800);
+        let mixed = codec::decode_file(&dir.join("mixed.flac")).unwrap();
+        assert_eq!(mixed.channels, 2);
+        assert_eq!(mixed.samples.len(), 4800 * 2);
+    }
+}
@@ -0,0 +1,319 @@
+//! Band-limited sample rate conversion.
+//!
+//! A rational-ratio polyphase resampler with a Kaiser-windowed sinc filter. Filter
+//! history is carried between `process` calls, so audio fed in arbitrary chunks
+//! (capture callbacks) comes out identical to converting it in one go.
+
+/// Zero crossings of the sinc on each side of the centre tap, at the lower of the two rates
+const ZERO_CROSSINGS: usize = 16;
+/// Passband edge as a fraction of the lower Nyquist frequency; the rest is transition band
+const ROLLOFF: f64 = 0.9;
+/// Kaiser window shape, good for roughly 80 dB of stopband attenuation
+const KAISER_BETA: f64 = 8.0;
+
+/// Streaming resampler for interleaved audio
+pub struct Resampler {
+    channels: usize,
+    // output rate / input rate, reduced
+    up: u64,
+    down: u64,
+    // filter reaches this many input frames either side of an output sample
+    half_width: usize,
+    // `up` phases of `2 * half_width` taps each
+    taps: Vec<f32>,
+    // interleaved input still needed by upcoming outputs; frame 0 is input frame `buffer_start`
+    buffer: Vec<f32>,
+    buffer_start: i64,
+    input_frames: u64,
+    output_frames: u64,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        let x = std::f64::consts::PI * x;
+        x.sin() / x
+    }
+}
+
+/// Zeroth-order modified Bessel function of the first kind, for the Kaiser window
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-12 {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+fn kaiser(x: f64) -> f64 {
+    if x.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(KAISER_BETA * (1.0 - x * x).sqrt()) / bessel_i0(KAISER_BETA)
+}
+
+impl Resampler {
+    pub fn new(from_rate: u32, to_rate: u32, channels: u16) -> Self {
+        let divisor = gcd(from_rate as u64, to_rate as u64).max(1);
+        let up = (to_rate as u64 / divisor).max(1);
+        let down = (from_rate as u64 / divisor).max(1);
+
+        // cutoff in cycles per input sample (x2); when downsampling it has to sit
+        // below the output Nyquist, which widens the filter in input samples
+        let cutoff = ROLLOFF * (up as f64 / down as f64).min(1.0);
+        let half_width = (ZERO_CROSSINGS as f64 / cutoff).ceil() as usize;
+        let taps_len = 2 * half_width;
+
+        let mut taps = Vec::with_capacity(up as usize * taps_len);
+        for phase in 0..up {
+            let frac = phase as f64 / up as f64;
+            let row: Vec<f64> = (0..taps_len)
+                .map(|j| {
+                    // distance from the output instant to input frame j of the window
+                    let distance = j as f64 - (half_width - 1) as f64 - frac;
+                    cutoff * sinc(cutoff * distance) * kaiser(distance / half_width as f64)
+                })
+                .collect();
+            // unity DC gain in every phase, so no phase-dependent ripple
+            let sum: f64 = row.iter().sum();
+            taps.extend(row.iter().map(|t| (t / sum) as f32));
+        }
+
+        let channels = channels.max(1) as usize;
+        Self {
+            channels,
+            up,
+            down,
+            half_width,
+            taps,
+            // zeros before the first frame, so output starts right at t = 0
+            buffer: vec![0.0; (half_width - 1) * channels],
+            buffer_start: -(half_width as i64 - 1),
+            input_frames: 0,
+            output_frames: 0,
+        }
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.up == self.down
+    }
+
+    /// Convert the next chunk of input. Output lags the input by `half_width`
+    /// frames of lookahead, which `finish` releases at the end.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() {
+            return input.to_vec();
+        }
+        self.buffer.extend_from_slice(input);
+        self.input_frames += (input.len() / self.channels) as u64;
+        let mut output =
+            Vec::with_capacity(input.len() * self.up as usize / self.down as usize + self.channels);
+        self.emit(&mut output, u64::MAX);
+        output
+    }
+
+    /// Flush the lookahead at the end of the stream. Together with everything
+    /// `process` returned this is `ceil(input_frames * to_rate / from_rate)` frames.
+    pub fn finish(&mut self) -> Vec<f32> {
+        if self.is_passthrough() {
+            return Vec::new();
+        }
+        let total = (self.input_frames * self.up).div_ceil(self.down);
+        self.buffer
+            .extend(std::iter::repeat_n(0.0, self.half_width * self.channels));
+        let mut output = Vec::new();
+        self.emit(&mut output, total);
+        output
+    }
+
+    /// Output frames owed for input already passed in but held back as lookahead
+    pub fn buffered_output_frames(&self) -> f64 {
+        if self.is_passthrough() {
+            return 0.0;
+        }
+        self.input_frames as f64 * self.up as f64 / self.down as f64 - self.output_frames as f64
+    }
+
+    fn emit(&mut self, output: &mut Vec<f32>, limit: u64) {
+        let taps_len = 2 * self.half_width;
+        let available = self.buffer.len() / self.channels;
+
+        while self.output_frames < limit {
+            let t = self.output_frames * self.down;
+            let position = (t / self.up) as i64;
+            let phase = (t % self.up) as usize;
+            let first = (position - (self.half_width as i64 - 1) - self.buffer_start) as usize;
+            if first + taps_len > available {
+                break;
+            }
+
+            let taps = &self.taps[phase * taps_len..(phase + 1) * taps_len];
+            let window = &self.buffer[first * self.channels..(first + taps_len) * self.channels];
+            for c in 0..self.channels {
+                let sample: f32 = window
+                    .iter()
+                    .skip(c)
+                    .step_by(self.channels)
+                    .zip(taps)
+                    .map(|(x, h)| x * h)
+                    .sum();
+                output.push(sample);
+            }
+            self.output_frames += 1;
+        }
+
+        // drop input that no upcoming output reaches back to
+        let next_first =
+            (self.output_frames * self.down / self.up) as i64 - (self.half_width as i64 - 1);
+        let consumed = (next_first - self.buffer_start).clamp(0, available as i64) as usize;
+        self.buffer.drain(..consumed * self.channels);
+        self.buffer_start += consumed as i64;
+    }
+}
+
+/// Resample a whole buffer of interleaved audio in one go
+pub fn resample(samples: &[f32], channels: u16, from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate {
+        return samples.to_vec();
+    }
+    let mut resampler = Resampler::new(from_rate, to_rate, channels);
+    let mut output = Vec::with_capacity(
+        (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize + channels as usize,
+    );
+    // in ~1 s chunks so the resampler's own buffer stays small for long recordings
+    for chunk in samples.chunks(from_rate as usize * channels.max(1) as usize) {
+        output.extend(resampler.process(chunk));
+    }
+    output.extend(resampler.finish());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(frames: usize, freq: f64, rate: u32) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (i as f64 * freq * std::f64::consts::TAU / rate as f64).sin() as f32)
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f64 {
+        (samples.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / samples.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_resample_identity() {
+        let input = vec![0.1, 0.2, 0.3, 0.4, 0.5];
+        assert_eq!(resample(&input, 1, 48000, 48000), input);
+    }
+
+    #[test]
+    fn test_resample_empty() {
+        assert!(resample(&[], 1, 48000, 16000).is_empty());
+    }
+
+    #[test]
+    fn test_resample_lengths() {
+        assert_eq!(resample(&vec![0.0; 4800], 1, 48000, 16000).len(), 1600);
+        assert_eq!(resample(&vec![0.0; 1600], 1, 16000, 48000).len(), 4800);
+        assert_eq!(resample(&vec![0.0; 44100], 1, 44100, 48000).len(), 48000);
+        // stereo keeps whole frames
+        assert_eq!(
+            resample(&vec![0.0; 2 * 4410], 2, 44100, 16000).len(),
+            2 * 1600
+        );
+    }
+
+    #[test]
+    fn test_passband_tone_is_preserved() {
+        // a 1 kHz tone at 44.1 kHz should come out as a clean 1 kHz tone at 48 kHz
+        let output = resample(&sine(44100, 1000.0, 44100), 1, 44100, 48000);
+        let expected = sine(48000, 1000.0, 48000);
+        // skip the edges, where the filter sees the zeros before/after the signal
+        let max_error = output[1000..47000]
+            .iter()
+            .zip(&expected[1000..47000])
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0f32, f32::max);
+        assert!(max_error < 1e-3, "max error {}", max_error);
+    }
+
+    #[test]
+    fn test_downsampling_rejects_aliases() {
+        // 12 kHz is above the 8 kHz Nyquist of the output and must not fold back to 4 kHz.
+        // Linear interpolation lets most of it through.
+        let output = resample(&sine(48000, 12000.0, 48000), 1, 48000, 16000);
+        let alias = rms(&output[1000..15000]);
+        assert!(
+            alias < 1e-3,
+            "alias rms {} ({:.1} dB)",
+            alias,
+            20.0 * alias.log10()
+        );
+
+        // while a tone just inside the passband keeps its level
+        let output = resample(&sine(48000, 6000.0, 48000), 1, 48000, 16000);
+        let level = rms(&output[1000..15000]) * 2f64.sqrt();
+        assert!((level - 1.0).abs() < 0.01, "passband level {}", level);
+    }
+
+    #[test]
+    fn test_chunk_boundaries_are_seamless() {
+        // feeding callback-sized chunks must give exactly the one-shot result
+        let input = sine(16000, 440.0, 16000);
+        let whole = resample(&input, 1, 16000, 48000);
+
+        let mut resampler = Resampler::new(16000, 48000, 1);
+        let mut chunked = Vec::new();
+        let mut rest = &input[..];
+        for size in [1, 7, 160, 333, 4000].iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (chunk, tail) = rest.split_at((*size).min(rest.len()));
+            chunked.extend(resampler.process(chunk));
+            rest = tail;
+        }
+        chunked.extend(resampler.finish());
+        assert_eq!(chunked, whole);
+
+        // and no jumps: sample-to-sample steps never exceed a clean 440 Hz sine's
+        let max_step = 440.0 * std::f32::consts::TAU / 48000.0;
+        for pair in chunked[100..47900].windows(2) {
+            assert!((pair[1] - pair[0]).abs() < max_step * 1.01);
+        }
+    }
+
+    #[test]
+    fn test_channels_are_independent() {
+        let left = sine(4800, 500.0, 48000);
+        let input: Vec<f32> = left.iter().flat_map(|&s| [s, 0.0]).collect();
+        let output = resample(&input, 2, 48000, 16000);
+        assert!(output.iter().skip(1).step_by(2).all(|&s| s.abs() < 1e-6));
+        assert!(rms(&output.iter().step_by(2).copied().collect::<Vec<_>>()) > 0.5);
+    }
+
+    #[test]
+    fn test_buffered_frames_account_for_lookahead() {
+        let mut resampler = Resampler::new(16000, 48000, 1);
+        let out = resampler.process(&vec![0.0; 1600]);
+        let owed = resampler.buffered_output_frames();
+        assert!(owed > 0.0);
+        assert_eq!(out.len() as f64 + owed, 4800.0);
+        assert_eq!(resampler.finish().len() as f64, owed);
+    }
+}
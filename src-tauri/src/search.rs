@@ -0,0 +1,512 @@
+use crate::artifacts::{self, EDITED_TRANSCRIPT_FILE, SUMMARY_FILE, TRANSCRIPT_FILE};
+use crate::library::Meeting;
+use crate::summarize::SummaryResult;
+use crate::transcribe::TranscriptionResult;
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// SQLite database holding the index, next to the recordings
+const INDEX_FILE: &str = ".search.db";
+/// Words of context on either side of a match in a snippet
+const SNIPPET_TOKENS: i32 = 16;
+// wrap matched terms in snippets; never found in transcript text
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+const SCHEMA: &str = "
+    CREATE VIRTUAL TABLE IF NOT EXISTS entries USING fts5(
+        text,
+        meeting UNINDEXED,
+        kind UNINDEXED,
+        segment_id UNINDEXED,
+        speaker UNINDEXED,
+        start_time UNINDEXED,
+        tokenize = 'porter unicode61'
+    );
+    CREATE TABLE IF NOT EXISTS indexed (
+        meeting TEXT PRIMARY KEY,
+        stamp INTEGER NOT NULL
+    );
+";
+
+/// Where in a meeting a hit was found
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitKind {
+    Transcript,
+    Summary,
+    KeyPoint,
+    Decision,
+    ActionItem,
+    OpenQuestion,
+}
+
+impl HitKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            HitKind::Transcript => "transcript",
+            HitKind::Summary => "summary",
+            HitKind::KeyPoint => "key_point",
+            HitKind::Decision => "decision",
+            HitKind::ActionItem => "action_item",
+            HitKind::OpenQuestion => "open_question",
+        }
+    }
+
+    fn parse(kind: &str) -> Self {
+        match kind {
+            "summary" => HitKind::Summary,
+            "key_point" => HitKind::KeyPoint,
+            "decision" => HitKind::Decision,
+            "action_item" => HitKind::ActionItem,
+            "open_question" => HitKind::OpenQuestion,
+            _ => HitKind::Transcript,
+        }
+    }
+}
+
+/// Part of a snippet; the parts that matched the query are highlighted
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlight: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SearchHit {
+    pub meeting_id: String,
+    pub kind: HitKind,
+    /// Transcript hits only
+    pub segment_id: Option<String>,
+    pub speaker: Option<String>,
+    pub start_time: Option<f32>,
+    pub snippet: Vec<SnippetPart>,
+}
+
+/// Hits within one meeting, as returned by `search_meetings`
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MeetingHits {
+    pub meeting: Meeting,
+    pub hits: Vec<SearchHit>,
+}
+
+/// Split a snippet marked up by SQLite into plain and highlighted parts
+fn snippet_parts(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut highlight = false;
+    for piece in snippet.split([MATCH_START, MATCH_END]) {
+        if !piece.is_empty() {
+            parts.push(SnippetPart {
+                text: piece.to_string(),
+                highlight,
+            });
+        }
+        highlight = !highlight;
+    }
+    parts
+}
+
+/// Turn what the user typed into an FTS5 query: every word must appear, the last
+/// one may be a prefix (search-as-you-type). Punctuation only separates words and
+/// each word is quoted, so FTS5 operators in the input are searched for as text.
+fn fts_query(input: &str) -> Option<String> {
+    let mut words: Vec<String> = input
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(|w| format!("\"{}\"", w))
+        .collect();
+    words.last_mut()?.push('*');
+    Some(words.join(" "))
+}
+
+fn modified_ms(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Latest change to anything indexed from a meeting; 0 if it has none of it
+fn artifacts_stamp(dir: &Path) -> u64 {
+    [TRANSCRIPT_FILE, EDITED_TRANSCRIPT_FILE, SUMMARY_FILE]
+        .iter()
+        .map(|name| modified_ms(&dir.join(name)))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Full-text index over every meeting's transcript and summary. Kept up to date by
+/// `index_meeting` whenever they are saved; `sync` catches up with changes made
+/// while the app wasn't running (e.g. by the command line tool).
+pub struct SearchIndex {
+    root: PathBuf,
+    // opened on first use
+    conn: Option<Connection>,
+}
+
+impl SearchIndex {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, conn: None }
+    }
+
+    fn conn(&mut self) -> Result<&mut Connection, String> {
+        if self.conn.is_none() {
+            fs::create_dir_all(&self.root)
+                .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+            let conn = Connection::open(self.root.join(INDEX_FILE))
+                .map_err(|e| format!("Failed to open search index: {}", e))?;
+            conn.execute_batch(SCHEMA)
+                .map_err(|e| format!("Failed to create search index: {}", e))?;
+            self.conn = Some(conn);
+        }
+        Ok(self.conn.as_mut().expect("opened above"))
+    }
+
+    /// Re-index the saved transcript (edited if there is one) and summary of a meeting
+    pub fn index_meeting(&mut self, id: &str) -> Result<(), String> {
+        let dir = self.root.join(id);
+        let transcript = artifacts::load_transcript(&dir)?.map(|(t, _)| t);
+        let summary = artifacts::load_summary(&dir)?;
+        let stamp = artifacts_stamp(&dir);
+        self.replace(id, transcript.as_ref(), summary.as_ref(), stamp)
+    }
+
+    fn replace(
+        &mut self,
+        id: &str,
+        transcript: Option<&TranscriptionResult>,
+        summary: Option<&SummaryResult>,
+        stamp: u64,
+    ) -> Result<(), String> {
+        let conn = self.conn()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to update search index: {}", e))?;
+        let result = (|| {
+            tx.execute("DELETE FROM entries WHERE meeting = ?1", [id])?;
+            let mut insert = tx.prepare(
+                "INSERT INTO entries (text, meeting, kind, segment_id, speaker, start_time)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for seg in transcript.iter().flat_map(|t| &t.segments) {
+                insert.execute(params![
+                    seg.text,
+                    id,
+                    HitKind::Transcript.as_str(),
+                    seg.id,
+                    seg.speaker,
+                    seg.start_time
+                ])?;
+            }
+            if let Some(summary) = summary {
+                let entries = std::iter::once((HitKind::Summary, &summary.summary))
+                    .chain(summary.key_points.iter().map(|p| (HitKind::KeyPoint, p)))
+                    .chain(summary.decisions.iter().map(|d| (HitKind::Decision, d)))
+                    .chain(
+                        summary
+                            .action_items
+                            .iter()
+                            .map(|a| (HitKind::ActionItem, &a.text)),
+                    )
+                    .chain(
+                        summary
+                            .open_questions
+                            .iter()
+                            .map(|q| (HitKind::OpenQuestion, q)),
+                    );
+                for (kind, text) in entries {
+                    insert.execute(params![
+                        text,
+                        id,
+                        kind.as_str(),
+                        None::<String>,
+                        None::<String>,
+                        None::<f32>
+                    ])?;
+                }
+            }
+            drop(insert);
+            tx.execute(
+                "INSERT OR REPLACE INTO indexed (meeting, stamp) VALUES (?1, ?2)",
+                params![id, stamp as i64],
+            )?;
+            Ok::<_, rusqlite::Error>(())
+        })();
+        result
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("Failed to update search index: {}", e))
+    }
+
+    /// Drop a deleted meeting from the index
+    pub fn remove_meeting(&mut self, id: &str) -> Result<(), String> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM entries WHERE meeting = ?1", [id])
+            .and_then(|_| conn.execute("DELETE FROM indexed WHERE meeting = ?1", [id]))
+            .map(|_| ())
+            .map_err(|e| format!("Failed to update search index: {}", e))
+    }
+
+    /// Bring the index in line with the recordings directory: index meetings whose
+    /// transcript or summary changed since they were last indexed and forget ones
+    /// that were deleted. Returns how many meetings were (re)indexed.
+    pub fn sync(&mut self) -> Result<usize, String> {
+        let ids: HashSet<String> = match fs::read_dir(&self.root) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|id| !id.starts_with('.'))
+                .collect(),
+            Err(_) => HashSet::new(),
+        };
+
+        let indexed: Vec<(String, i64)> = {
+            let conn = self.conn()?;
+            let mut stmt = conn
+                .prepare("SELECT meeting, stamp FROM indexed")
+                .map_err(|e| format!("Failed to read search index: {}", e))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| format!("Failed to read search index: {}", e))?;
+            rows
+        };
+        for (id, _) in indexed.iter().filter(|(id, _)| !ids.contains(id)) {
+            self.remove_meeting(id)?;
+        }
+
+        let mut updated = 0;
+        for id in &ids {
+            let stamp = artifacts_stamp(&self.root.join(id));
+            let known = indexed
+                .iter()
+                .find(|(indexed_id, _)| indexed_id == id)
+                .map(|(_, stamp)| *stamp as u64);
+            if known == Some(stamp) || (known.is_none() && stamp == 0) {
+                continue;
+            }
+            if let Err(e) = self.index_meeting(id) {
+                eprintln!("Failed to index {}: {}", id, e);
+                continue;
+            }
+            updated += 1;
+        }
+        Ok(updated)
+    }
+
+    /// Best matches for `query` across all meetings, at most `limit` of them
+    pub fn search(&mut self, query: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+        let Some(query) = fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.conn()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT meeting, kind, segment_id, speaker, start_time,
+                        snippet(entries, 0, char(1), char(2), '…', ?3)
+                 FROM entries WHERE entries MATCH ?1
+                 ORDER BY rank LIMIT ?2",
+            )
+            .map_err(|e| format!("Failed to search: {}", e))?;
+        let hits = stmt
+            .query_map(params![query, limit as i64, SNIPPET_TOKENS], |row| {
+                Ok(SearchHit {
+                    meeting_id: row.get(0)?,
+                    kind: HitKind::parse(&row.get::<_, String>(1)?),
+                    segment_id: row.get(2)?,
+                    speaker: row.get(3)?,
+                    start_time: row.get::<_, Option<f64>>(4)?.map(|t| t as f32),
+                    snippet: snippet_parts(&row.get::<_, String>(5)?),
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Failed to search: {}", e))?;
+        Ok(hits)
+    }
+
+    /// When a meeting was last indexed from disk, if it ever was
+    #[cfg(test)]
+    fn stamp(&mut self, id: &str) -> Option<i64> {
+        use rusqlite::OptionalExtension;
+        self.conn()
+            .ok()?
+            .query_row(
+                "SELECT stamp FROM indexed WHERE meeting = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()?
+    }
+}
+
+/// Group hits by meeting, best-matching meeting first. Hits in meetings that are
+/// not in `meetings` (deleted since they were indexed) are dropped.
+pub fn group_by_meeting(hits: Vec<SearchHit>, meetings: &[Meeting]) -> Vec<MeetingHits> {
+    let mut grouped: Vec<MeetingHits> = Vec::new();
+    for hit in hits {
+        if let Some(group) = grouped.iter_mut().find(|g| g.meeting.id == hit.meeting_id) {
+            group.hits.push(hit);
+        } else if let Some(meeting) = meetings.iter().find(|m| m.id == hit.meeting_id) {
+            grouped.push(MeetingHits {
+                meeting: meeting.clone(),
+                hits: vec![hit],
+            });
+        }
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action_items::ActionItem;
+    use crate::transcribe::fixture;
+
+    fn search_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn add_transcript(root: &Path, id: &str, lines: &[(&str, &str)]) -> PathBuf {
+        let dir = root.join(id);
+        fs::create_dir_all(&dir).unwrap();
+        artifacts::save_transcript(&dir, &fixture::transcript(lines)).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_search_finds_segments_with_highlighted_snippets() {
+        let root = search_root("search_test_segments");
+        add_transcript(
+            &root,
+            "2024-05-01_09-00-00",
+            &[
+                ("Me", "Morning everyone"),
+                (
+                    "Meeting",
+                    "We should start migrating the database next week",
+                ),
+            ],
+        );
+        add_transcript(&root, "2024-05-02_09-00-00", &[("Me", "Lunch plans?")]);
+
+        let mut index = SearchIndex::new(root.clone());
+        assert_eq!(index.sync().unwrap(), 2);
+        // stemmed, case-insensitive, last word as a prefix
+        let hits = index.search("Migration datab", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.meeting_id, "2024-05-01_09-00-00");
+        assert_eq!(hit.kind, HitKind::Transcript);
+        assert_eq!(hit.segment_id.as_deref(), Some("seg_1"));
+        assert_eq!(hit.speaker.as_deref(), Some("Meeting"));
+        assert_eq!(hit.start_time, Some(10.0));
+        let highlighted: Vec<&str> = hit
+            .snippet
+            .iter()
+            .filter(|p| p.highlight)
+            .map(|p| p.text.as_str())
+            .collect();
+        assert_eq!(highlighted, ["migrating", "database"]);
+
+        // FTS5 syntax in the input is searched for, not interpreted
+        assert!(index.search("\"NEAR( AND *", 10).unwrap().is_empty());
+        assert!(index.search("  ", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_summaries_are_searchable() {
+        let root = search_root("search_test_summary");
+        let dir = add_transcript(&root, "2024-05-01_09-00-00", &[("Me", "Hello")]);
+        artifacts::save_summary(
+            &dir,
+            &SummaryResult {
+                summary: "Budget review".into(),
+                key_points: vec!["Costs are up".into()],
+                decisions: vec!["Switch to annual billing".into()],
+                action_items: vec![ActionItem::new("Email the vendor about invoices")],
+                open_questions: vec![],
+            },
+        )
+        .unwrap();
+
+        let mut index = SearchIndex::new(root.clone());
+        index.index_meeting("2024-05-01_09-00-00").unwrap();
+        let hits = index.search("vendor", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, HitKind::ActionItem);
+        assert_eq!(hits[0].segment_id, None);
+        assert_eq!(
+            index.search("annual", 10).unwrap()[0].kind,
+            HitKind::Decision
+        );
+    }
+
+    #[test]
+    fn test_sync_picks_up_edits_and_deletions() {
+        let root = search_root("search_test_sync");
+        let dir = add_transcript(
+            &root,
+            "2024-05-01_09-00-00",
+            &[("Me", "kubernettes rollout")],
+        );
+        add_transcript(&root, "2024-05-02_09-00-00", &[("Me", "kubernetes again")]);
+        // no transcript yet: nothing to index
+        fs::create_dir_all(root.join("2024-05-03_09-00-00")).unwrap();
+
+        let mut index = SearchIndex::new(root.clone());
+        index.sync().unwrap();
+        assert_eq!(index.stamp("2024-05-03_09-00-00"), None);
+        assert_eq!(index.sync().unwrap(), 0);
+
+        // fixed in the editor (by another process, as far as the index knows)
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let mut edited = artifacts::load_transcript(&dir).unwrap().unwrap().0;
+        edited.segments[0].text = "kubernetes rollout".into();
+        artifacts::save_edited_transcript(&dir, &edited).unwrap();
+        fs::remove_dir_all(root.join("2024-05-02_09-00-00")).unwrap();
+
+        assert_eq!(index.sync().unwrap(), 1);
+        let hits = index.search("kubernetes", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].meeting_id, "2024-05-01_09-00-00");
+        assert!(index.search("kubernettes", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_group_by_meeting_keeps_rank_order() {
+        let hit = |id: &str| SearchHit {
+            meeting_id: id.into(),
+            kind: HitKind::Transcript,
+            segment_id: None,
+            speaker: None,
+            start_time: None,
+            snippet: vec![],
+        };
+        let meeting = |id: &str| Meeting {
+            id: id.into(),
+            title: id.into(),
+            started_at: chrono::Local::now(),
+            duration_secs: 0.0,
+            has_transcript: true,
+            has_summary: false,
+            in_progress: false,
+        };
+        let grouped = group_by_meeting(
+            vec![hit("b"), hit("a"), hit("gone"), hit("b")],
+            &[meeting("a"), meeting("b")],
+        );
+        let shape: Vec<(&str, usize)> = grouped
+            .iter()
+            .map(|g| (g.meeting.id.as_str(), g.hits.len()))
+            .collect();
+        assert_eq!(shape, [("b", 2), ("a", 1)]);
+    }
+}
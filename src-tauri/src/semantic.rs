@@ -0,0 +1,413 @@
+use crate::artifacts::{self, EDITED_TRANSCRIPT_FILE, TRANSCRIPT_FILE};
+use crate::config::AppConfig;
+use crate::ollama::Ollama;
+use crate::transcribe::TranscriptionResult;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Vectors for one meeting's transcript, stored next to it
+pub const EMBEDDINGS_FILE: &str = "embeddings.json";
+
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+/// Consecutive segments are joined into chunks of about this size before embedding;
+/// single Whisper segments are often too short to carry their meaning
+const CHUNK_CHARS: usize = 600;
+/// Chunks sent to Ollama per request
+const EMBED_BATCH: usize = 32;
+
+/// Pick the embedding model from config, falling back to the default.
+pub fn embedding_model_name(config: &AppConfig) -> String {
+    config
+        .embedding_model
+        .clone()
+        .unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string())
+}
+
+/// Consecutive transcript segments embedded together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Chunk {
+    segment_ids: Vec<String>,
+    start_time: f32,
+    end_time: f32,
+    /// "[Speaker] text" lines, as embedded
+    text: String,
+    /// Unit length, so a dot product is the cosine similarity
+    vector: Vec<f32>,
+}
+
+/// Contents of `embeddings.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MeetingIndex {
+    model: String,
+    chunks: Vec<Chunk>,
+}
+
+/// A chunk of a meeting that is close in meaning to the query
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticHit {
+    pub meeting_id: String,
+    pub segment_ids: Vec<String>,
+    pub start_time: f32,
+    pub end_time: f32,
+    pub text: String,
+    /// Cosine similarity to the query, higher is closer
+    pub score: f32,
+}
+
+/// Split a transcript into chunks of whole segments, without vectors yet
+fn chunk_transcript(transcript: &TranscriptionResult) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    for seg in &transcript.segments {
+        let line = format!("[{}] {}", seg.speaker, seg.text.trim());
+        match chunks.last_mut() {
+            Some(chunk) if chunk.text.len() + line.len() < CHUNK_CHARS => {
+                chunk.segment_ids.push(seg.id.clone());
+                chunk.end_time = seg.end_time;
+                chunk.text.push('\n');
+                chunk.text.push_str(&line);
+            }
+            _ => chunks.push(Chunk {
+                segment_ids: vec![seg.id.clone()],
+                start_time: seg.start_time,
+                end_time: seg.end_time,
+                text: line,
+                vector: Vec::new(),
+            }),
+        }
+    }
+    chunks
+}
+
+fn normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn load_index(dir: &Path) -> Option<MeetingIndex> {
+    let json = fs::read(dir.join(EMBEDDINGS_FILE)).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// Whether `embeddings.json` was written after the transcript was last saved
+fn index_is_current(dir: &Path) -> bool {
+    let Some(indexed) = modified(&dir.join(EMBEDDINGS_FILE)) else {
+        return false;
+    };
+    [TRANSCRIPT_FILE, EDITED_TRANSCRIPT_FILE]
+        .iter()
+        .filter_map(|name| modified(&dir.join(name)))
+        .all(|saved| saved <= indexed)
+}
+
+/// Embed the transcript of the recording in `dir` (the edited one if there is one)
+/// and save the vectors as `embeddings.json`. Chunks whose text hasn't changed since
+/// the last run keep their vectors, so re-indexing after an edit only embeds what
+/// was edited. Returns false if the recording has no transcript yet.
+pub async fn index_meeting(ollama: &Ollama, model: &str, dir: &Path) -> Result<bool, String> {
+    let Some((transcript, _)) = artifacts::load_transcript(dir)? else {
+        return Ok(false);
+    };
+
+    let mut known: HashMap<String, Vec<f32>> = load_index(dir)
+        .filter(|index| index.model == model)
+        .map(|index| {
+            index
+                .chunks
+                .into_iter()
+                .map(|chunk| (chunk.text, chunk.vector))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut chunks = chunk_transcript(&transcript);
+    for chunk in &mut chunks {
+        if let Some(vector) = known.remove(&chunk.text) {
+            chunk.vector = vector;
+        }
+    }
+    let missing: Vec<usize> = (0..chunks.len())
+        .filter(|&i| chunks[i].vector.is_empty())
+        .collect();
+    for batch in missing.chunks(EMBED_BATCH) {
+        let texts: Vec<String> = batch.iter().map(|&i| chunks[i].text.clone()).collect();
+        let vectors = ollama.embed(model, &texts).await?;
+        for (&i, vector) in batch.iter().zip(vectors) {
+            chunks[i].vector = normalize(vector);
+        }
+    }
+    println!(
+        "Embedded {} of {} chunks in {}",
+        missing.len(),
+        chunks.len(),
+        dir.display()
+    );
+
+    let index = MeetingIndex {
+        model: model.to_string(),
+        chunks,
+    };
+    let json =
+        serde_json::to_vec(&index).map_err(|e| format!("Failed to serialize embeddings: {}", e))?;
+    fs::write(dir.join(EMBEDDINGS_FILE), json)
+        .map_err(|e| format!("Failed to write embeddings: {}", e))?;
+    Ok(true)
+}
+
+async fn embed_query(
+    ollama: &Ollama,
+    model: &str,
+    query: &str,
+) -> Result<Option<Vec<f32>>, String> {
+    if query.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(ollama
+        .embed(model, &[query.to_string()])
+        .await?
+        .pop()
+        .map(normalize))
+}
+
+/// Every chunk of the meeting in `dir` scored against `query`, (re)indexing the
+/// meeting first if its embeddings are missing or older than its transcript
+async fn score_meeting(
+    ollama: &Ollama,
+    model: &str,
+    dir: &Path,
+    query: &[f32],
+) -> Result<Vec<SemanticHit>, String> {
+    let id = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let current = load_index(dir).filter(|i| i.model == model && index_is_current(dir));
+    let index = match current {
+        Some(index) => index,
+        None if index_meeting(ollama, model, dir).await? => match load_index(dir) {
+            Some(index) => index,
+            None => return Ok(Vec::new()),
+        },
+        None => return Ok(Vec::new()),
+    };
+    Ok(index
+        .chunks
+        .into_iter()
+        .map(|chunk| SemanticHit {
+            meeting_id: id.clone(),
+            score: dot(query, &chunk.vector),
+            segment_ids: chunk.segment_ids,
+            start_time: chunk.start_time,
+            end_time: chunk.end_time,
+            text: chunk.text,
+        })
+        .collect())
+}
+
+fn best(mut hits: Vec<SemanticHit>, limit: usize) -> Vec<SemanticHit> {
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    hits
+}
+
+/// The `limit` chunks of the meeting in `dir` closest in meaning to `query`, best first
+pub async fn search_meeting(
+    ollama: &Ollama,
+    model: &str,
+    dir: &Path,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SemanticHit>, String> {
+    let Some(query) = embed_query(ollama, model, query).await? else {
+        return Ok(Vec::new());
+    };
+    Ok(best(
+        score_meeting(ollama, model, dir, &query).await?,
+        limit,
+    ))
+}
+
+/// The `limit` chunks closest in meaning to `query` across every meeting under
+/// `root`, best first. Meetings whose embeddings are missing or older than their
+/// transcript are (re)indexed first.
+pub async fn semantic_search(
+    ollama: &Ollama,
+    model: &str,
+    root: &Path,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SemanticHit>, String> {
+    let Some(query) = embed_query(ollama, model, query).await? else {
+        return Ok(Vec::new());
+    };
+
+    let mut dirs: Vec<_> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .map(|e| e.path())
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    dirs.sort();
+
+    let mut hits = Vec::new();
+    for dir in dirs {
+        hits.extend(score_meeting(ollama, model, &dir, &query).await?);
+    }
+    Ok(best(hits, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ollama::stub::{self, block_on};
+    use crate::transcribe::fixture;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    /// Fake embedding: one dimension per topic the text mentions
+    fn topic_vector(text: &str) -> Vec<f32> {
+        let text = text.to_lowercase();
+        ["budget", "hiring", "launch"]
+            .iter()
+            .map(|topic| if text.contains(topic) { 1.0 } else { 0.0 })
+            .collect()
+    }
+
+    /// Stub Ollama returning topic vectors; records every text it was asked to embed
+    fn stub_embeddings() -> (Ollama, Arc<Mutex<Vec<String>>>) {
+        let embedded = Arc::new(Mutex::new(Vec::new()));
+        let log = embedded.clone();
+        let url = stub::serve(move |path, body| {
+            assert_eq!(path, "/api/embed");
+            let inputs: Vec<String> = serde_json::from_value(body["input"].clone()).unwrap();
+            let vectors: Vec<Vec<f32>> = inputs.iter().map(|t| topic_vector(t)).collect();
+            log.lock().unwrap().extend(inputs);
+            serde_json::json!({ "embeddings": vectors }).to_string()
+        });
+        (Ollama::new(url), embedded)
+    }
+
+    fn transcript(texts: &[&str]) -> TranscriptionResult {
+        let lines: Vec<(&str, &str)> = texts.iter().map(|text| ("Meeting", *text)).collect();
+        fixture::transcript(&lines)
+    }
+
+    fn semantic_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_chunks_keep_whole_segments() {
+        let long = "word ".repeat(100);
+        let chunks = chunk_transcript(&transcript(&["Hi", "there", &long, &long, "bye"]));
+        let ids: Vec<Vec<&str>> = chunks
+            .iter()
+            .map(|c| c.segment_ids.iter().map(String::as_str).collect())
+            .collect();
+        assert_eq!(
+            ids,
+            [vec!["seg_0", "seg_1", "seg_2"], vec!["seg_3", "seg_4"]]
+        );
+        assert_eq!(chunks[1].start_time, 30.0);
+        assert_eq!(chunks[1].end_time, 45.0);
+        assert!(chunks.iter().all(|c| c.text.len() < CHUNK_CHARS));
+    }
+
+    #[test]
+    fn test_search_ranks_chunks_across_meetings() {
+        let root = semantic_root("semantic_test_search");
+        for (id, text) in [
+            ("2024-05-01_09-00-00", "The budget for Q3 is tight"),
+            ("2024-05-02_09-00-00", "We are hiring two engineers"),
+            (
+                "2024-05-03_09-00-00",
+                "Launch slips, and the launch budget too",
+            ),
+        ] {
+            let dir = root.join(id);
+            fs::create_dir_all(&dir).unwrap();
+            artifacts::save_transcript(&dir, &transcript(&[text])).unwrap();
+        }
+        // no transcript yet
+        fs::create_dir_all(root.join("2024-05-04_09-00-00")).unwrap();
+
+        let (ollama, embedded) = stub_embeddings();
+        let hits = block_on(semantic_search(
+            &ollama,
+            "stub",
+            &root,
+            "how much money is in the budget?",
+            2,
+        ))
+        .unwrap();
+        let found: Vec<(&str, &str)> = hits
+            .iter()
+            .map(|h| (h.meeting_id.as_str(), h.segment_ids[0].as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("2024-05-01_09-00-00", "seg_0"),
+                ("2024-05-03_09-00-00", "seg_0")
+            ]
+        );
+        assert!((hits[0].score - 1.0).abs() < 1e-6);
+        assert!(root
+            .join("2024-05-02_09-00-00")
+            .join(EMBEDDINGS_FILE)
+            .exists());
+
+        // indexes are reused: only the query is embedded the second time
+        embedded.lock().unwrap().clear();
+        block_on(semantic_search(&ollama, "stub", &root, "hiring", 1)).unwrap();
+        assert_eq!(*embedded.lock().unwrap(), ["hiring"]);
+    }
+
+    #[test]
+    fn test_reindex_embeds_only_edited_chunks() {
+        let root = semantic_root("semantic_test_reindex");
+        let dir = root.join("2024-05-01_09-00-00");
+        fs::create_dir_all(&dir).unwrap();
+        let long = "budget ".repeat(100);
+        let mut edited = transcript(&[&long, "hiring plan"]);
+        artifacts::save_transcript(&dir, &edited).unwrap();
+
+        let (ollama, embedded) = stub_embeddings();
+        assert!(block_on(index_meeting(&ollama, "stub", &dir)).unwrap());
+        assert_eq!(embedded.lock().unwrap().len(), 2);
+        assert!(index_is_current(&dir));
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        edited.segments[1].text = "launch plan".into();
+        artifacts::save_edited_transcript(&dir, &edited).unwrap();
+        assert!(!index_is_current(&dir));
+
+        embedded.lock().unwrap().clear();
+        block_on(index_meeting(&ollama, "stub", &dir)).unwrap();
+        assert_eq!(*embedded.lock().unwrap(), ["[Meeting] launch plan"]);
+
+        // switching models embeds everything again
+        embedded.lock().unwrap().clear();
+        block_on(index_meeting(&ollama, "other", &dir)).unwrap();
+        assert_eq!(embedded.lock().unwrap().len(), 2);
+    }
+}
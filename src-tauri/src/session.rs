@@ -0,0 +1,372 @@
+use crate::codec::StorageCodec;
+use crate::config::MixMode;
+use chrono::{DateTime, Duration, Local};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Metadata file written into every recording directory
+pub const SESSION_FILE: &str = "session.json";
+
+/// Serialises every write of `session.json`, so the stages updating it from
+/// different threads (mixdown, transcription, summary, rename) can't lose each
+/// other's changes
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A stretch of the meeting that was deliberately not recorded
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PauseInterval {
+    /// Position in the recorded audio where the pause starts (seconds)
+    pub offset_secs: f64,
+    pub paused_at: DateTime<Local>,
+    /// None if the session was stopped while paused
+    pub resumed_at: Option<DateTime<Local>>,
+}
+
+/// How one track was kept on the session clock
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TrackTiming {
+    /// When the first sample was captured, in seconds of recorded audio. The track
+    /// starts with this much silence. None if the source never delivered timestamps.
+    pub start_secs: Option<f64>,
+    /// Silence inserted where the source fell behind (late start, dropouts, device gaps)
+    pub padded_secs: f64,
+    /// Audio dropped where the source ran ahead (device clock faster than ours)
+    pub dropped_secs: f64,
+}
+
+/// Alignment of the system and mic tracks, measured while recording
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TrackAlignment {
+    pub system: TrackTiming,
+    pub mic: TrackTiming,
+    /// How much later the mic's first sample was captured than system audio's
+    pub mic_offset_secs: Option<f64>,
+}
+
+/// The capture source behind a track and what was written from it
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TrackInfo {
+    /// Device name as reported by the capture backend when the session started
+    pub device: String,
+    pub source_sample_rate: u32,
+    pub source_channels: u16,
+    /// Rate and channel count of the stored file
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved samples written, filled in when the session is stopped
+    pub samples: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SessionTracks {
+    pub system: TrackInfo,
+    pub mic: TrackInfo,
+}
+
+/// How the mixed track was produced
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MixInfo {
+    pub mode: MixMode,
+    /// Linear gains actually applied; measured per session in normalize mode
+    pub system_gain: f32,
+    pub mic_gain: f32,
+    pub codec: StorageCodec,
+    pub mixed_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TranscriptionInfo {
+    /// Whisper model file used
+    pub model: String,
+    pub segments: usize,
+    pub duration_secs: f32,
+    pub transcribed_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SummaryInfo {
+    /// Ollama model tag used
+    pub model: String,
+    pub summarized_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExportInfo {
+    pub path: PathBuf,
+    pub exported_at: DateTime<Local>,
+}
+
+/// Contents of `session.json`. Written when recording starts and stops; every later
+/// stage (mixdown, transcription, summary, export) adds its part via `update`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    /// Version of the app that recorded the session
+    #[serde(default)]
+    pub app_version: String,
+    /// Set by renaming the meeting in the library
+    #[serde(default)]
+    pub title: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub ended_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub tracks: Option<SessionTracks>,
+    #[serde(default)]
+    pub pauses: Vec<PauseInterval>,
+    #[serde(default)]
+    pub alignment: Option<TrackAlignment>,
+    #[serde(default)]
+    pub mix: Option<MixInfo>,
+    #[serde(default)]
+    pub transcription: Option<TranscriptionInfo>,
+    #[serde(default)]
+    pub summary: Option<SummaryInfo>,
+    #[serde(default)]
+    pub exports: Vec<ExportInfo>,
+}
+
+impl SessionMetadata {
+    pub fn new(started_at: DateTime<Local>) -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            title: None,
+            started_at,
+            ended_at: None,
+            tracks: None,
+            pauses: Vec::new(),
+            alignment: None,
+            mix: None,
+            transcription: None,
+            summary: None,
+            exports: Vec::new(),
+        }
+    }
+
+    /// Load `session.json` from a recording directory
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(dir.join(SESSION_FILE))
+            .map_err(|e| format!("Failed to read session metadata: {}", e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse session metadata: {}", e))
+    }
+
+    /// Write `session.json` into a recording directory, replacing it as a whole
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let _guard = WRITE_LOCK.lock();
+        self.write(dir)
+    }
+
+    /// Load `session.json`, apply `f` and write it back. Recordings made before
+    /// the app wrote session metadata have nothing to add to and are left alone.
+    pub fn update(dir: &Path, f: impl FnOnce(&mut Self)) -> Result<(), String> {
+        let _guard = WRITE_LOCK.lock();
+        if !dir.join(SESSION_FILE).exists() {
+            return Ok(());
+        }
+        let mut session = Self::load(dir)?;
+        f(&mut session);
+        session.write(dir)
+    }
+
+    /// Write to a temporary file and rename it over `session.json`, so readers
+    /// never see a half-written file
+    fn write(&self, dir: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize session metadata: {}", e))?;
+        let temp = dir.join(format!(".{}.{}.tmp", SESSION_FILE, std::process::id()));
+        fs::write(&temp, json).map_err(|e| format!("Failed to write session metadata: {}", e))?;
+        fs::rename(&temp, dir.join(SESSION_FILE)).map_err(|e| {
+            let _ = fs::remove_file(&temp);
+            format!("Failed to write session metadata: {}", e)
+        })
+    }
+
+    /// Seconds of audio in the session: from the sample counts if it was stopped
+    /// normally, otherwise wall-clock time minus pauses
+    pub fn recorded_secs(&self) -> Option<f64> {
+        let from_samples = |track: &TrackInfo| {
+            track.samples as f64 / (track.sample_rate.max(1) as f64 * track.channels.max(1) as f64)
+        };
+        if let Some(tracks) = &self.tracks {
+            let secs = from_samples(&tracks.system).max(from_samples(&tracks.mic));
+            if secs > 0.0 {
+                return Some(secs);
+            }
+        }
+        let ended_at = self.ended_at?;
+        let paused: Duration = self
+            .pauses
+            .iter()
+            .map(|p| p.resumed_at.unwrap_or(ended_at) - p.paused_at)
+            .sum();
+        Some(((ended_at - self.started_at - paused).num_milliseconds() as f64 / 1000.0).max(0.0))
+    }
+
+    /// Map a position in the recorded audio (e.g. a transcript segment's
+    /// `start_time`) to the wall-clock time it was spoken, skipping over pauses
+    pub fn wall_clock_at(&self, offset_secs: f64) -> DateTime<Local> {
+        let paused: Duration = self
+            .pauses
+            .iter()
+            .filter(|p| p.offset_secs <= offset_secs)
+            .filter_map(|p| p.resumed_at.map(|r| r - p.paused_at))
+            .sum();
+        self.started_at + Duration::milliseconds((offset_secs * 1000.0) as i64) + paused
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 1, 1, h, m, s).unwrap()
+    }
+
+    #[test]
+    fn test_wall_clock_without_pauses() {
+        let session = SessionMetadata::new(at(10, 0, 0));
+        assert_eq!(session.wall_clock_at(90.0), at(10, 1, 30));
+    }
+
+    #[test]
+    fn test_wall_clock_skips_pauses() {
+        let mut session = SessionMetadata::new(at(10, 0, 0));
+        // recorded 60s, paused for 5 minutes, then recorded again
+        session.pauses.push(PauseInterval {
+            offset_secs: 60.0,
+            paused_at: at(10, 1, 0),
+            resumed_at: Some(at(10, 6, 0)),
+        });
+
+        assert_eq!(session.wall_clock_at(30.0), at(10, 0, 30));
+        assert_eq!(session.wall_clock_at(60.0), at(10, 6, 0));
+        assert_eq!(session.wall_clock_at(90.0), at(10, 6, 30));
+    }
+
+    #[test]
+    fn test_recorded_secs() {
+        let mut session = SessionMetadata::new(at(10, 0, 0));
+        assert_eq!(session.recorded_secs(), None);
+
+        // crashed before the sample counts were written: the wall clock minus the pause
+        session.ended_at = Some(at(10, 10, 0));
+        session.pauses.push(PauseInterval {
+            offset_secs: 60.0,
+            paused_at: at(10, 1, 0),
+            resumed_at: Some(at(10, 3, 0)),
+        });
+        assert_eq!(session.recorded_secs(), Some(480.0));
+
+        session.tracks = Some(SessionTracks {
+            system: TrackInfo {
+                sample_rate: 48000,
+                channels: 2,
+                samples: 48000 * 2 * 479,
+                ..Default::default()
+            },
+            mic: TrackInfo {
+                sample_rate: 48000,
+                channels: 1,
+                samples: 48000 * 478,
+                ..Default::default()
+            },
+        });
+        assert_eq!(session.recorded_secs(), Some(479.0));
+    }
+
+    #[test]
+    fn test_concurrent_updates_keep_every_field() {
+        let dir = std::env::temp_dir().join("session_test_concurrent");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        SessionMetadata::new(at(10, 0, 0)).save(&dir).unwrap();
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = dir.clone();
+                std::thread::spawn(move || {
+                    for j in 0..10 {
+                        SessionMetadata::update(&dir, |session| {
+                            session.exports.push(ExportInfo {
+                                path: PathBuf::from(format!("{}-{}.md", i, j)),
+                                exported_at: at(11, 0, 0),
+                            })
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(SessionMetadata::load(&dir).unwrap().exports.len(), 80);
+        // only session.json is left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = std::env::temp_dir().join("session_test_roundtrip");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut session = SessionMetadata::new(at(9, 30, 0));
+        session.pauses.push(PauseInterval {
+            offset_secs: 12.5,
+            paused_at: at(9, 30, 12),
+            resumed_at: None,
+        });
+        session.ended_at = Some(at(9, 45, 0));
+        session.save(&dir).unwrap();
+
+        let loaded = SessionMetadata::load(&dir).unwrap();
+        assert_eq!(loaded.started_at, session.started_at);
+        assert_eq!(loaded.ended_at, session.ended_at);
+        assert_eq!(loaded.pauses, session.pauses);
+    }
+
+    #[test]
+    fn test_update_appends_to_existing_session() {
+        let dir = std::env::temp_dir().join("session_test_update");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // nothing to update in a recording from before session.json
+        SessionMetadata::update(&dir, |s| s.summary = None).unwrap();
+        assert!(!dir.join(SESSION_FILE).exists());
+
+        SessionMetadata::new(at(9, 30, 0)).save(&dir).unwrap();
+        SessionMetadata::update(&dir, |s| {
+            s.summary = Some(SummaryInfo {
+                model: "qwen3:8b".into(),
+                summarized_at: at(10, 0, 0),
+            })
+        })
+        .unwrap();
+
+        let loaded = SessionMetadata::load(&dir).unwrap();
+        assert_eq!(loaded.app_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(loaded.summary.unwrap().model, "qwen3:8b");
+    }
+
+    #[test]
+    fn test_loads_session_written_by_older_versions() {
+        let dir = std::env::temp_dir().join("session_test_legacy");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(SESSION_FILE),
+            r#"{"started_at": "2024-01-01T09:30:00+00:00", "ended_at": null}"#,
+        )
+        .unwrap();
+
+        let loaded = SessionMetadata::load(&dir).unwrap();
+        assert_eq!(loaded.app_version, "");
+        assert!(loaded.tracks.is_none());
+        assert!(loaded.exports.is_empty());
+    }
+}
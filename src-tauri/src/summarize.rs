@@ -1,84 +1,292 @@
+use crate::action_items::{self, ActionItem, ActionStatus};
+use crate::artifacts;
 use crate::config::AppConfig;
+use crate::ollama::{ChatMessage, Ollama};
+use crate::session::{SessionMetadata, SummaryInfo};
 use crate::transcribe::TranscriptionResult;
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use futures_util::future::{select, Either};
+use std::path::Path;
+use std::pin::pin;
+use std::sync::mpsc::Sender;
+use tokio::sync::watch;
 
 /// Summary output from the LLM
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 pub struct SummaryResult {
     pub summary: String,
     pub key_points: Vec<String>,
-    pub action_items: Vec<String>,
+    /// Missing from summaries written before they were asked for
+    #[serde(default)]
+    pub decisions: Vec<String>,
+    pub action_items: Vec<ActionItem>,
+    #[serde(default)]
+    pub open_questions: Vec<String>,
 }
 
+/// Summary generated so far, sent as each token arrives
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SummaryProgress {
+    /// Output of the current request so far, without any thinking block
+    pub text: String,
+    /// Tokens generated so far, over all requests
+    pub tokens: u32,
+    /// Part of the transcript being summarized (1-based). Transcripts too long for
+    /// the model are summarized in several parts, which are then combined.
+    pub part: u32,
+    pub parts: u32,
+    /// Combining the summaries of the parts into the final one
+    pub combining: bool,
+}
+
+/// Error returned when a summarization is cancelled
+pub const CANCELLED: &str = "Summarization cancelled";
+
 const SYSTEM_PROMPT: &str = "You are a helpful assistant that summarizes meeting transcripts. Provide a concise summary, key points, and action items. Do not include any thinking or reasoning - just provide the formatted output directly.";
 
-const OLLAMA_BASE_URL: &str = "http://localhost:11434";
 const DEFAULT_MODEL: &str = "qwen3.5:latest";
 
+/// Context assumed when Ollama doesn't report the model's
+const DEFAULT_CONTEXT_TOKENS: u32 = 4096;
+/// Models may support far more, but the memory for a larger context may not be there
+const MAX_CONTEXT_TOKENS: u32 = 32_768;
+/// Room left in the context for the instructions and the model's answer
+const RESERVED_TOKENS: u32 = 1536;
+/// Lower bound on the transcript text per request, however small the context
+const MIN_CHUNK_TOKENS: u32 = 512;
+/// Rough size of a token in transcript text, on the low side so chunks fit
+const CHARS_PER_TOKEN: usize = 3;
+/// Each chunk starts with the last 1/OVERLAP_DIVISOR of the previous one, so what is
+/// said across a chunk boundary is seen in context
+const OVERLAP_DIVISOR: usize = 10;
+
+/// Times an invalid JSON reply is sent back to the model to fix before falling
+/// back to asking for markdown
+const REPAIR_ATTEMPTS: usize = 1;
+
+const JSON_INSTRUCTIONS: &str = r#"Reply with a JSON object with these fields:
+- "summary": a 2-3 sentence overview of the meeting
+- "key_points": the main points discussed
+- "decisions": decisions that were made
+- "action_items": tasks someone agreed to do, each an object with
+  - "text": the task
+  - "assignee": who will do it, as one of the speakers listed below, or null
+  - "due": the deadline as it was said (e.g. "Friday", "end of next week", "March 3"), or null
+  - "segment_ids": ids of the transcript lines the task comes from, e.g. ["seg_4"]
+- "open_questions": questions left unresolved
+The other lists hold short plain sentences. Any list may be empty."#;
+
+const MARKDOWN_INSTRUCTIONS: &str = r#"Provide your response in this exact format:
+## Summary
+[2-3 sentence overview of the meeting]
+
+## Key Points
+- [point 1]
+- [point 2]
+- [point 3]
+
+## Decisions
+- [decision 1]
+
+## Action Items
+- [ ] [action 1]
+- [ ] [action 2]
+
+## Open Questions
+- [question 1]"#;
+
+/// JSON schema of the reply, passed to Ollama as `format` so the model can only
+/// produce a `SummaryReply`
+fn summary_schema() -> serde_json::Value {
+    let list = serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let optional = serde_json::json!({ "type": ["string", "null"] });
+    let action_item = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "text": { "type": "string" },
+            "assignee": optional,
+            "due": optional,
+            "segment_ids": list,
+        },
+        "required": ["text", "assignee", "due", "segment_ids"],
+    });
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "summary": { "type": "string" },
+            "key_points": list,
+            "decisions": list,
+            "action_items": { "type": "array", "items": action_item },
+            "open_questions": list,
+        },
+        "required": ["summary", "key_points", "decisions", "action_items", "open_questions"],
+    })
+}
+
+/// A summary as the model writes it, before `validate_summary`
+#[derive(serde::Deserialize)]
+struct SummaryReply {
+    summary: String,
+    key_points: Vec<String>,
+    decisions: Vec<String>,
+    action_items: Vec<ActionItemReply>,
+    open_questions: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ActionItemReply {
+    text: String,
+    assignee: Option<String>,
+    /// As said in the meeting, e.g. "Friday"
+    due: Option<String>,
+    #[serde(default)]
+    segment_ids: Vec<String>,
+}
+
+/// What the model's replies are checked against
+struct MeetingContext<'a> {
+    transcript: &'a TranscriptionResult,
+    /// Distinct speakers, in order of first appearance
+    speakers: Vec<String>,
+    /// Due dates are relative to this
+    date: NaiveDate,
+}
+
+impl<'a> MeetingContext<'a> {
+    fn new(transcript: &'a TranscriptionResult, date: NaiveDate) -> Self {
+        let mut speakers: Vec<String> = Vec::new();
+        for seg in &transcript.segments {
+            if !speakers.contains(&seg.speaker) {
+                speakers.push(seg.speaker.clone());
+            }
+        }
+        Self {
+            transcript,
+            speakers,
+            date,
+        }
+    }
+}
+
 /// Pick the Ollama model tag from config, falling back to the default.
-fn ollama_model_name(config: &AppConfig) -> String {
+pub(crate) fn ollama_model_name(config: &AppConfig) -> String {
     config
         .llm_model
         .clone()
         .unwrap_or_else(|| DEFAULT_MODEL.to_string())
 }
 
-#[derive(Serialize)]
-struct ChatMessage<'a> {
-    role: &'a str,
-    content: &'a str,
+/// One "[seg_4 Speaker] text" line per segment
+fn transcript_lines(transcript: &TranscriptionResult) -> Vec<String> {
+    transcript
+        .segments
+        .iter()
+        .map(|seg| format!("[{} {}] {}\n", seg.id, seg.speaker, seg.text))
+        .collect()
 }
 
-#[derive(Serialize)]
-struct ChatRequest<'a> {
-    model: &'a str,
-    messages: Vec<ChatMessage<'a>>,
-    stream: bool,
-}
-
-#[derive(Deserialize)]
-struct ChatResponseMessage {
-    content: String,
+/// Build the user prompt with transcript. The reply format is added by `Generator`.
+fn build_user_prompt(transcript: &TranscriptionResult) -> String {
+    format!(
+        "Please summarize the following meeting transcript. Each line starts with its segment id and the speaker.\n\n{}",
+        transcript_lines(transcript).concat()
+    )
 }
 
-#[derive(Deserialize)]
-struct ChatResponse {
-    message: ChatResponseMessage,
+/// Prompt for one part of a transcript too long to summarize at once
+fn build_part_prompt(part: usize, parts: usize, text: &str) -> String {
+    format!(
+        "This is part {} of {} of a long meeting transcript. Each line starts with its segment id and the speaker. Please summarize this part:\n\n{}",
+        part, parts, text
+    )
 }
 
-/// Build the user prompt with transcript
-fn build_user_prompt(transcript: &TranscriptionResult) -> String {
-    let mut formatted_transcript = String::new();
-    for seg in &transcript.segments {
-        formatted_transcript.push_str(&format!("[{}] {}\n", seg.speaker, seg.text));
+/// A summary as text, for combining with others
+fn render_partial(summary: &SummaryResult) -> String {
+    let mut text = format!("Summary: {}\n", summary.summary);
+    for point in &summary.key_points {
+        text.push_str(&format!("Key point: {}\n", point));
+    }
+    for decision in &summary.decisions {
+        text.push_str(&format!("Decision: {}\n", decision));
     }
+    for item in &summary.action_items {
+        let mut details = Vec::new();
+        if let Some(assignee) = &item.assignee {
+            details.push(format!("assignee: {}", assignee));
+        }
+        if let Some(due) = item.due {
+            details.push(format!("due: {}", due));
+        }
+        if !item.segment_ids.is_empty() {
+            details.push(format!("segments: {}", item.segment_ids.join(", ")));
+        }
+        text.push_str(&format!("Action item: {}", item.text));
+        if !details.is_empty() {
+            text.push_str(&format!(" ({})", details.join("; ")));
+        }
+        text.push('\n');
+    }
+    for question in &summary.open_questions {
+        text.push_str(&format!("Open question: {}\n", question));
+    }
+    text
+}
 
+/// Prompt combining summaries of consecutive parts of a meeting (as rendered by
+/// `render_partial`) into one
+fn build_combine_prompt(partials: &str) -> String {
     format!(
-        r#"Please summarize the following meeting transcript:
-
-{}
-
-Provide your response in this exact format:
-## Summary
-[2-3 sentence overview of the meeting]
-
-## Key Points
-- [point 1]
-- [point 2]
-- [point 3]
-
-## Action Items
-- [ ] [action 1]
-- [ ] [action 2]"#,
-        formatted_transcript
+        "The following are summaries of consecutive parts of one meeting. Combine them into a single summary of the whole meeting, merging points, decisions, action items and questions that repeat, and dropping questions a later part answers:\n\n{}",
+        partials
     )
 }
 
-/// Parse the LLM output into structured summary
+/// Join `pieces` into chunks of at most `max_chars` (a piece longer than that gets a
+/// chunk of its own). With `overlap`, each chunk begins with the pieces that ended
+/// the previous one, up to a tenth of `max_chars`.
+fn pack(pieces: &[String], max_chars: usize, overlap: bool) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current: Vec<&String> = Vec::new();
+    let mut len = 0;
+    let mut fresh = 0; // pieces in `current` not carried over from the previous chunk
+    for piece in pieces {
+        if fresh > 0 && len + piece.len() > max_chars {
+            chunks.push(current.iter().map(|p| p.as_str()).collect::<String>());
+            let mut carried = Vec::new();
+            if overlap {
+                let mut carried_len = 0;
+                for p in current.iter().rev() {
+                    carried_len += p.len();
+                    if carried_len > max_chars / OVERLAP_DIVISOR
+                        || carried_len + piece.len() > max_chars
+                    {
+                        break;
+                    }
+                    carried.insert(0, *p);
+                }
+            }
+            len = carried.iter().map(|p| p.len()).sum();
+            current = carried;
+            fresh = 0;
+        }
+        current.push(piece);
+        len += piece.len();
+        fresh += 1;
+    }
+    if fresh > 0 {
+        chunks.push(current.iter().map(|p| p.as_str()).collect());
+    }
+    chunks
+}
+
+/// Parse markdown LLM output (`MARKDOWN_INSTRUCTIONS`) into structured summary
 fn parse_summary(output: &str) -> SummaryResult {
     let mut summary = String::new();
     let mut key_points = Vec::new();
+    let mut decisions = Vec::new();
     let mut action_items = Vec::new();
+    let mut open_questions = Vec::new();
 
     let mut current_section = "";
 
@@ -91,8 +299,12 @@ fn parse_summary(output: &str) -> SummaryResult {
             current_section = "summary";
         } else if trimmed.starts_with("## Key Points") {
             current_section = "key_points";
+        } else if trimmed.starts_with("## Decisions") {
+            current_section = "decisions";
         } else if trimmed.starts_with("## Action Items") {
             current_section = "action_items";
+        } else if trimmed.starts_with("## Open Questions") {
+            current_section = "open_questions";
         } else if !trimmed.is_empty() {
             match current_section {
                 "summary" => {
@@ -101,21 +313,31 @@ fn parse_summary(output: &str) -> SummaryResult {
                     }
                     summary.push_str(trimmed);
                 }
-                "key_points" => {
+                "key_points" | "decisions" | "open_questions" => {
+                    let list = match current_section {
+                        "key_points" => &mut key_points,
+                        "decisions" => &mut decisions,
+                        _ => &mut open_questions,
+                    };
                     if trimmed.starts_with("- ") || trimmed.starts_with("* ") {
-                        key_points.push(trimmed[2..].to_string());
+                        list.push(trimmed[2..].to_string());
                     } else if !trimmed.starts_with('#') {
-                        key_points.push(trimmed.to_string());
+                        list.push(trimmed.to_string());
                     }
                 }
                 "action_items" => {
+                    let done = trimmed.starts_with("- [x] ");
                     let item = trimmed
                         .trim_start_matches("- [ ] ")
                         .trim_start_matches("- [x] ")
                         .trim_start_matches("- ")
                         .trim_start_matches("* ");
                     if !item.is_empty() && !item.starts_with('#') {
-                        action_items.push(item.to_string());
+                        let mut item = ActionItem::new(item);
+                        if done {
+                            item.status = ActionStatus::Done;
+                        }
+                        action_items.push(item);
                     }
                 }
                 _ => {}
@@ -126,40 +348,146 @@ fn parse_summary(output: &str) -> SummaryResult {
     SummaryResult {
         summary,
         key_points,
+        decisions,
         action_items,
+        open_questions,
     }
 }
 
-/// Remove <think>...</think> blocks that Qwen3 may emit
-fn strip_thinking_blocks(text: &str) -> String {
-    let mut result = String::new();
-    let mut in_think = false;
-    let mut chars = text.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c == '<' {
-            let mut tag = String::from("<");
-            while let Some(&next) = chars.peek() {
-                if next == '>' {
-                    tag.push(chars.next().unwrap());
-                    break;
-                }
-                tag.push(chars.next().unwrap());
+/// Parse a JSON reply (as constrained by `summary_schema`) and check it is usable.
+/// The error says what is wrong, for the model to fix.
+fn parse_json_summary(output: &str, meeting: &MeetingContext) -> Result<SummaryResult, String> {
+    let cleaned = strip_thinking_blocks(output);
+    // some models fence the JSON even when asked for nothing but
+    let json = cleaned
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    let reply: SummaryReply =
+        serde_json::from_str(json).map_err(|e| format!("the reply is not valid JSON: {}", e))?;
+    validate_summary(reply, meeting)
+}
+
+/// A list entry without surrounding space or a leading bullet
+fn tidy_entry(entry: &str) -> &str {
+    entry
+        .trim()
+        .trim_start_matches("- [ ] ")
+        .trim_start_matches("- [x] ")
+        .trim_start_matches("- ")
+        .trim_start_matches("* ")
+        .trim()
+}
+
+/// Tidy a summary the model returned: trim everything, drop list bullets and
+/// empty or repeated entries. Action item assignees are matched to the meeting's
+/// speakers, due dates resolved against its date and segment ids the transcript
+/// doesn't have dropped. Fails if there is no overview.
+fn validate_summary(
+    reply: SummaryReply,
+    meeting: &MeetingContext,
+) -> Result<SummaryResult, String> {
+    let tidy = |items: Vec<String>| {
+        let mut tidied: Vec<String> = Vec::new();
+        for item in items {
+            let item = tidy_entry(&item);
+            if !item.is_empty() && !tidied.iter().any(|t| t == item) {
+                tidied.push(item.to_string());
             }
+        }
+        tidied
+    };
+    let overview = reply.summary.trim();
+    if overview.is_empty() {
+        return Err("\"summary\" is empty".to_string());
+    }
+
+    let mut action_items: Vec<ActionItem> = Vec::new();
+    for item in reply.action_items {
+        let text = tidy_entry(&item.text);
+        if text.is_empty() || action_items.iter().any(|a| a.text == text) {
+            continue;
+        }
+        let mut segment_ids: Vec<String> = Vec::new();
+        for id in &item.segment_ids {
+            let id = id.trim().trim_matches(|c| c == '[' || c == ']');
+            let known = meeting.transcript.segments.iter().any(|seg| seg.id == id);
+            if known && !segment_ids.iter().any(|s| s == id) {
+                segment_ids.push(id.to_string());
+            }
+        }
+        action_items.push(ActionItem {
+            text: text.to_string(),
+            assignee: item
+                .assignee
+                .as_deref()
+                .and_then(|name| action_items::match_speaker(name, &meeting.speakers)),
+            due: item
+                .due
+                .as_deref()
+                .and_then(|due| action_items::parse_due(due, meeting.date)),
+            status: ActionStatus::Open,
+            segment_ids,
+        });
+    }
+
+    Ok(SummaryResult {
+        summary: overview.to_string(),
+        key_points: tidy(reply.key_points),
+        decisions: tidy(reply.decisions),
+        action_items,
+        open_questions: tidy(reply.open_questions),
+    })
+}
+
+/// Remove <think>...</think> blocks that Qwen3 may emit
+pub(crate) fn strip_thinking_blocks(text: &str) -> String {
+    let mut filter = ThinkingFilter::default();
+    filter.push(text);
+    filter.finish()
+}
+
+/// Strips <think> blocks from a reply as it streams in, one chunk at a time,
+/// so the cleaned text so far is always at hand without rescanning the reply
+#[derive(Default)]
+struct ThinkingFilter {
+    text: String,
+    /// A tag that has been opened with `<` but not yet closed
+    tag: String,
+    in_think: bool,
+}
 
-            if tag == "<think>" {
-                in_think = true;
-            } else if tag == "</think>" {
-                in_think = false;
-            } else if !in_think {
-                result.push_str(&tag);
+impl ThinkingFilter {
+    fn push(&mut self, chunk: &str) {
+        for c in chunk.chars() {
+            if !self.tag.is_empty() {
+                self.tag.push(c);
+                if c == '>' {
+                    match self.tag.as_str() {
+                        "<think>" => self.in_think = true,
+                        "</think>" => self.in_think = false,
+                        _ if !self.in_think => self.text.push_str(&self.tag),
+                        _ => {}
+                    }
+                    self.tag.clear();
+                }
+            } else if c == '<' {
+                self.tag.push(c);
+            } else if !self.in_think {
+                self.text.push(c);
             }
-        } else if !in_think {
-            result.push(c);
         }
     }
 
-    result
+    /// The cleaned text, including a trailing tag that was never closed
+    fn finish(mut self) -> String {
+        if !self.in_think {
+            self.text.push_str(&self.tag);
+        }
+        self.text
+    }
 }
 
 /// Summarize a transcript by calling Ollama's HTTP API at localhost:11434.
@@ -167,55 +495,280 @@ fn strip_thinking_blocks(text: &str) -> String {
 /// in production; in dev, run `ollama serve` separately).
 pub async fn summarize_transcript(
     transcript: &TranscriptionResult,
+) -> Result<SummaryResult, String> {
+    summarize_transcript_with_progress(transcript, None, None).await
+}
+
+/// Like `summarize_transcript`, reporting the output as it is generated. Setting
+/// `cancel` to true stops generation and returns `CANCELLED`. Action item due
+/// dates are taken to be relative to today.
+pub async fn summarize_transcript_with_progress(
+    transcript: &TranscriptionResult,
+    progress_tx: Option<Sender<SummaryProgress>>,
+    cancel: Option<watch::Receiver<bool>>,
+) -> Result<SummaryResult, String> {
+    let today = chrono::Local::now().date_naive();
+    summarize_meeting(transcript, today, progress_tx, cancel).await
+}
+
+async fn summarize_meeting(
+    transcript: &TranscriptionResult,
+    meeting_date: NaiveDate,
+    progress_tx: Option<Sender<SummaryProgress>>,
+    cancel: Option<watch::Receiver<bool>>,
 ) -> Result<SummaryResult, String> {
     let config = AppConfig::load();
+    let ollama = Ollama::local();
     let model = ollama_model_name(&config);
-    let user_prompt = build_user_prompt(transcript);
-
-    println!("Summarizing with Ollama model: {}", model);
-    println!("Prompt length: {} chars", user_prompt.len());
-
-    let req = ChatRequest {
-        model: &model,
-        messages: vec![
-            ChatMessage { role: "system", content: SYSTEM_PROMPT },
-            ChatMessage { role: "user", content: &user_prompt },
-        ],
-        stream: false,
+    let work = summarize(&ollama, &model, transcript, meeting_date, progress_tx);
+    until_cancelled(work, cancel).await
+}
+
+/// Run `work` until it finishes or `cancel` becomes true, whichever comes first.
+/// Dropping the request future closes the connection, which makes Ollama stop generating.
+async fn until_cancelled<T>(
+    work: impl std::future::Future<Output = Result<T, String>>,
+    cancel: Option<watch::Receiver<bool>>,
+) -> Result<T, String> {
+    let Some(mut cancel) = cancel else {
+        return work.await;
     };
+    let cancelled = async move {
+        // nobody can cancel any more once the sender is gone
+        if cancel.wait_for(|&cancelled| cancelled).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    match select(pin!(work), pin!(cancelled)).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => {
+            println!("Summarization cancelled");
+            Err(CANCELLED.to_string())
+        }
+    }
+}
+
+/// Context to load the model with, from what Ollama reports for it
+async fn context_tokens(ollama: &Ollama, model: &str) -> u32 {
+    match ollama.context_length(model).await {
+        Ok(Some(tokens)) => tokens.min(MAX_CONTEXT_TOKENS),
+        Ok(None) => DEFAULT_CONTEXT_TOKENS,
+        Err(e) => {
+            eprintln!("Failed to look up context length of {}: {}", model, e);
+            DEFAULT_CONTEXT_TOKENS
+        }
+    }
+}
+
+/// Which request of a summary is running, for progress reports
+#[derive(Clone, Copy)]
+struct Stage {
+    part: u32,
+    parts: u32,
+    combining: bool,
+}
+
+/// Streams prompts through the model, reporting progress as it goes
+struct Generator<'a> {
+    ollama: &'a Ollama,
+    model: &'a str,
+    num_ctx: u32,
+    meeting: MeetingContext<'a>,
+    progress_tx: Option<Sender<SummaryProgress>>,
+    tokens: u32,
+}
+
+impl Generator<'_> {
+    async fn complete(
+        &mut self,
+        messages: &[ChatMessage],
+        format: Option<&serde_json::Value>,
+        stage: Stage,
+    ) -> Result<String, String> {
+        let mut partial = ThinkingFilter::default();
+        let tokens = &mut self.tokens;
+        let progress_tx = &self.progress_tx;
+        let output = self
+            .ollama
+            .chat_stream(self.model, messages, Some(self.num_ctx), format, |chunk| {
+                partial.push(chunk);
+                *tokens += 1;
+                if let Some(tx) = progress_tx {
+                    let _ = tx.send(SummaryProgress {
+                        text: partial.text.clone(),
+                        tokens: *tokens,
+                        part: stage.part,
+                        parts: stage.parts,
+                        combining: stage.combining,
+                    });
+                }
+            })
+            .await?;
+        println!("Generated {} chars of output", output.len());
+        Ok(output)
+    }
+
+    /// Have the model carry out `task` (a prompt without the reply format) and
+    /// return its summary. The reply is JSON following `summary_schema`; an invalid
+    /// one is sent back to be fixed, and if that fails too the model is asked for
+    /// the markdown format instead.
+    async fn generate(&mut self, task: String, stage: Stage) -> Result<SummaryResult, String> {
+        let schema = summary_schema();
+        let instructions = format!(
+            "{}\nThe speakers are: {}.",
+            JSON_INSTRUCTIONS,
+            self.meeting.speakers.join(", ")
+        );
+        let mut messages = vec![
+            ChatMessage::new("system", SYSTEM_PROMPT),
+            ChatMessage::new("user", format!("{}\n\n{}", task, instructions)),
+        ];
+        let mut repairs = 0;
+        loop {
+            let output = self.complete(&messages, Some(&schema), stage).await?;
+            let error = match parse_json_summary(&output, &self.meeting) {
+                Ok(summary) => return Ok(summary),
+                Err(e) => e,
+            };
+            eprintln!("Unusable summary from {}: {}", self.model, error);
+            if repairs == REPAIR_ATTEMPTS {
+                break;
+            }
+            repairs += 1;
+            messages.push(ChatMessage::new("assistant", output));
+            messages.push(ChatMessage::new(
+                "user",
+                format!(
+                    "That reply can't be used: {}. Reply again with only the JSON object described above, with every field present.",
+                    error
+                ),
+            ));
+        }
+
+        println!("Asking {} for a markdown summary instead", self.model);
+        let messages = [
+            ChatMessage::new("system", SYSTEM_PROMPT),
+            ChatMessage::new("user", format!("{}\n\n{}", task, MARKDOWN_INSTRUCTIONS)),
+        ];
+        let output = self.complete(&messages, None, stage).await?;
+        Ok(parse_summary(&output))
+    }
+}
 
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(600))
-        .build()
-        .map_err(|e| format!("http client: {}", e))?;
+/// Summarize in one request if the transcript fits the model's context. Otherwise
+/// map-reduce: summarize overlapping chunks that do fit, then combine their summaries
+/// (in rounds, if even those don't fit together).
+async fn summarize(
+    ollama: &Ollama,
+    model: &str,
+    transcript: &TranscriptionResult,
+    meeting_date: NaiveDate,
+    progress_tx: Option<Sender<SummaryProgress>>,
+) -> Result<SummaryResult, String> {
+    let num_ctx = context_tokens(ollama, model).await;
+    let chunk_chars =
+        num_ctx.saturating_sub(RESERVED_TOKENS).max(MIN_CHUNK_TOKENS) as usize * CHARS_PER_TOKEN;
+    let mut generator = Generator {
+        ollama,
+        model,
+        num_ctx,
+        meeting: MeetingContext::new(transcript, meeting_date),
+        progress_tx,
+        tokens: 0,
+    };
 
-    let resp = client
-        .post(format!("{}/api/chat", OLLAMA_BASE_URL))
-        .json(&req)
-        .send()
-        .await
-        .map_err(|e| format!("Ollama request failed: {}. Is `ollama serve` running?", e))?;
+    println!("Summarizing with Ollama model: {} ({} token context)", model, num_ctx);
+    let lines = transcript_lines(transcript);
+    let chunks = pack(&lines, chunk_chars, true);
+    if chunks.len() <= 1 {
+        let user_prompt = build_user_prompt(transcript);
+        println!("Prompt length: {} chars", user_prompt.len());
+        let stage = Stage {
+            part: 1,
+            parts: 1,
+            combining: false,
+        };
+        return generator.generate(user_prompt, stage).await;
+    }
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
-        return Err(format!("Ollama returned {}: {}", status, body));
+    let parts = chunks.len();
+    println!(
+        "Transcript too long for one request, summarizing in {} parts of up to {} chars",
+        parts, chunk_chars
+    );
+    let mut partials = Vec::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let prompt = build_part_prompt(i + 1, parts, chunk);
+        let stage = Stage {
+            part: i as u32 + 1,
+            parts: parts as u32,
+            combining: false,
+        };
+        partials.push(generator.generate(prompt, stage).await?);
     }
 
-    let chat: ChatResponse = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse Ollama response: {}", e))?;
+    loop {
+        let rendered: Vec<String> = partials.iter().map(render_partial).collect();
+        let mut groups = pack(&rendered, chunk_chars, false);
+        if groups.len() == rendered.len() {
+            // no two summaries fit together; combine them all and let the model cope
+            groups = vec![rendered.concat()];
+        }
+        let mut combined = Vec::new();
+        for group in groups {
+            let prompt = build_combine_prompt(&group);
+            let stage = Stage {
+                part: parts as u32,
+                parts: parts as u32,
+                combining: true,
+            };
+            combined.push(generator.generate(prompt, stage).await?);
+        }
+        if combined.len() == 1 {
+            return Ok(combined.remove(0));
+        }
+        partials = combined;
+    }
+}
 
-    let output = chat.message.content;
-    println!("Generated {} chars of output", output.len());
+/// Summarize the transcript of the recording in `dir`, save it as the current
+/// summary (keeping the previous one) and note the model in its `session.json`
+pub async fn summarize_recording(
+    dir: &Path,
+    transcript: &TranscriptionResult,
+) -> Result<SummaryResult, String> {
+    summarize_recording_with_progress(dir, transcript, None, None).await
+}
 
-    Ok(parse_summary(&output))
+/// `summarize_recording` with progress and cancellation as in
+/// `summarize_transcript_with_progress`. Nothing is saved if it is cancelled.
+/// Action item due dates are relative to the day of the recording.
+pub async fn summarize_recording_with_progress(
+    dir: &Path,
+    transcript: &TranscriptionResult,
+    progress_tx: Option<Sender<SummaryProgress>>,
+    cancel: Option<watch::Receiver<bool>>,
+) -> Result<SummaryResult, String> {
+    let meeting_date = SessionMetadata::load(dir)
+        .map(|session| session.started_at.date_naive())
+        .unwrap_or_else(|_| chrono::Local::now().date_naive());
+    let summary = summarize_meeting(transcript, meeting_date, progress_tx, cancel).await?;
+    artifacts::save_summary(dir, &summary)?;
+    let info = SummaryInfo {
+        model: ollama_model_name(&AppConfig::load()),
+        summarized_at: chrono::Local::now(),
+    };
+    if let Err(e) = SessionMetadata::update(dir, |session| session.summary = Some(info)) {
+        eprintln!("Failed to record summary in session metadata: {}", e);
+    }
+    Ok(summary)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ollama::stub::block_on;
+    use crate::transcribe::fixture;
 
     #[test]
     fn test_parse_summary_basic() {
@@ -238,7 +791,7 @@ This was a productive meeting about the project.
         assert_eq!(result.key_points.len(), 3);
         assert_eq!(result.key_points[0], "Discussed timeline");
         assert_eq!(result.action_items.len(), 2);
-        assert_eq!(result.action_items[0], "Send follow-up email");
+        assert_eq!(result.action_items[0], ActionItem::new("Send follow-up email"));
     }
 
     #[test]
@@ -258,6 +811,22 @@ This was a productive meeting about the project.
         assert_eq!(result, "## Summary\nHere is the summary.");
     }
 
+    #[test]
+    fn test_thinking_filter_handles_tags_split_across_chunks() {
+        let mut filter = ThinkingFilter::default();
+        for chunk in [
+            "<thi",
+            "nk>hm</th",
+            "ink>a <b",
+            "> c <",
+            "think>x</think>d <e",
+        ] {
+            filter.push(chunk);
+        }
+        assert_eq!(filter.text, "a <b> c d ");
+        assert_eq!(filter.finish(), "a <b> c d <e");
+    }
+
     #[test]
     fn test_build_user_prompt_formats_speakers() {
         let transcript = crate::transcribe::TranscriptionResult {
@@ -282,9 +851,8 @@ This was a productive meeting about the project.
         };
 
         let prompt = build_user_prompt(&transcript);
-        assert!(prompt.contains("[Me] Hello team"));
-        assert!(prompt.contains("[Meeting] Hi there"));
-        assert!(prompt.contains("## Summary"));
+        assert!(prompt.contains("[seg_0 Me] Hello team"));
+        assert!(prompt.contains("[seg_1 Meeting] Hi there"));
     }
 
     #[test]
@@ -293,6 +861,7 @@ This was a productive meeting about the project.
             setup_complete: false,
             whisper_model: None,
             llm_model: None,
+            ..Default::default()
         };
         assert_eq!(ollama_model_name(&config), DEFAULT_MODEL);
     }
@@ -303,6 +872,7 @@ This was a productive meeting about the project.
             setup_complete: true,
             whisper_model: None,
             llm_model: Some("qwen3:8b".into()),
+            ..Default::default()
         };
         assert_eq!(ollama_model_name(&config), "qwen3:8b");
     }
@@ -328,6 +898,324 @@ This was a productive meeting.
         assert_eq!(result.action_items.len(), 1);
     }
 
+    /// `reply` as Ollama streams it: one NDJSON line per word, then a final done line
+    fn streamed(reply: &str) -> String {
+        let mut lines: Vec<String> = reply
+            .split_inclusive(' ')
+            .map(|word| {
+                let message = serde_json::json!({ "role": "assistant", "content": word });
+                serde_json::json!({ "message": message, "done": false }).to_string()
+            })
+            .collect();
+        lines.push(r#"{"message":{"role":"assistant","content":""},"done":true}"#.to_string());
+        lines.join("\n") + "\n"
+    }
+
+    fn ship_it_transcript() -> TranscriptionResult {
+        fixture::transcript(&[("Me", "Ship it")])
+    }
+
+    #[test]
+    fn test_summarize_streams_progress_and_parses_reply() {
+        let url = crate::ollama::stub::serve(|path, body| {
+            if path == "/api/show" {
+                return "{}".to_string();
+            }
+            assert_eq!(path, "/api/chat");
+            assert_eq!(body["model"], "test-model");
+            assert_eq!(body["stream"], true);
+            // unknown context length
+            assert_eq!(body["options"]["num_ctx"], DEFAULT_CONTEXT_TOKENS);
+            assert_eq!(body["format"], summary_schema());
+            assert!(body["messages"][1]["content"]
+                .as_str()
+                .unwrap()
+                .contains("[seg_0 Me] Ship it"));
+            streamed(SHIP_IT_REPLY)
+        });
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let result = block_on(summarize(
+            &Ollama::new(url),
+            "test-model",
+            &ship_it_transcript(),
+            meeting_date(),
+            Some(tx),
+        ))
+        .unwrap();
+        assert_eq!(result.summary, "Short.");
+        assert_eq!(result.key_points, ["Shipping"]);
+        assert_eq!(result.decisions, ["Ship on Friday"]);
+        assert_eq!(
+            result.action_items,
+            [ActionItem {
+                text: "Ship it".into(),
+                assignee: Some("Me".into()),
+                due: NaiveDate::from_ymd_opt(2024, 3, 8),
+                status: ActionStatus::Open,
+                segment_ids: vec!["seg_0".into()],
+            }]
+        );
+        assert!(result.open_questions.is_empty());
+
+        let progress: Vec<SummaryProgress> = rx.try_iter().collect();
+        // one per word plus the empty final chunk; the thinking block is never shown
+        assert_eq!(progress.len(), 24);
+        assert_eq!(progress[0].tokens, 1);
+        assert_eq!(progress[0].text, "");
+        assert!(progress[2].text.starts_with("{\"summary\""));
+        let last = progress.last().unwrap();
+        assert_eq!(last.tokens, 24);
+        assert_eq!((last.part, last.parts, last.combining), (1, 1, false));
+        assert!(last.text.ends_with("\"open_questions\": []}"));
+    }
+
+    /// The unknown segment id is dropped and "me" and "Friday" are resolved
+    const SHIP_IT_REPLY: &str = r#"<think>hmm </think>{"summary": "Short.", "key_points": ["Shipping"], "decisions": ["Ship on Friday"], "action_items": [{"text": "Ship it", "assignee": "me", "due": "by Friday", "segment_ids": ["seg_0", "seg_9"]}], "open_questions": []}"#;
+
+    /// A Wednesday
+    fn meeting_date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 6).unwrap()
+    }
+
+    #[test]
+    fn test_parse_json_summary_tidies_and_validates() {
+        let transcript = ship_it_transcript();
+        let meeting = MeetingContext::new(&transcript, meeting_date());
+        let reply = r#"```json
+{"summary": " Short. ", "key_points": ["- Shipping", "", "Shipping"], "decisions": [],
+ "action_items": [{"text": "- [ ] Ship it", "assignee": "Dana", "due": "someday", "segment_ids": ["[seg_0]"]},
+                  {"text": "Ship it", "assignee": null, "due": null, "segment_ids": []}],
+ "open_questions": ["When?"]}
+```"#;
+        let result = parse_json_summary(reply, &meeting).unwrap();
+        assert_eq!(result.summary, "Short.");
+        assert_eq!(result.key_points, ["Shipping"]);
+        // an unknown assignee and an unclear deadline are left out
+        let mut ship_it = ActionItem::new("Ship it");
+        ship_it.segment_ids = vec!["seg_0".into()];
+        assert_eq!(result.action_items, [ship_it]);
+        assert_eq!(result.open_questions, ["When?"]);
+
+        let err = parse_json_summary(r#"{"summary": "Short."}"#, &meeting).unwrap_err();
+        assert!(err.contains("missing field `key_points`"), "{}", err);
+        let empty = r#"{"summary": " ", "key_points": [], "decisions": [], "action_items": [], "open_questions": []}"#;
+        let err = parse_json_summary(empty, &meeting).unwrap_err();
+        assert!(err.contains("\"summary\" is empty"));
+        assert!(parse_json_summary("## Summary\nShort.", &meeting).is_err());
+        let plain_items = r#"{"summary": "Short.", "key_points": [], "decisions": [], "action_items": ["Ship it"], "open_questions": []}"#;
+        assert!(parse_json_summary(plain_items, &meeting).is_err());
+    }
+
+    #[test]
+    fn test_summaries_saved_before_decisions_still_load() {
+        let old = r#"{"summary": "Short.", "key_points": [], "action_items": ["Ship it"]}"#;
+        let summary: SummaryResult = serde_json::from_str(old).unwrap();
+        assert!(summary.decisions.is_empty() && summary.open_questions.is_empty());
+        assert_eq!(summary.action_items, [ActionItem::new("Ship it")]);
+    }
+
+    /// Stub Ollama giving `replies` to successive chat requests; records their bodies
+    fn scripted_ollama(
+        replies: &'static [&'static str],
+    ) -> (Ollama, std::sync::Arc<std::sync::Mutex<Vec<serde_json::Value>>>) {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = requests.clone();
+        let url = crate::ollama::stub::serve(move |path, body| {
+            if path == "/api/show" {
+                return "{}".to_string();
+            }
+            let mut log = log.lock().unwrap();
+            log.push(body.clone());
+            streamed(replies[(log.len() - 1).min(replies.len() - 1)])
+        });
+        (Ollama::new(url), requests)
+    }
+
+    #[test]
+    fn test_invalid_reply_is_sent_back_for_repair() {
+        let (ollama, requests) = scripted_ollama(&[r#"{"summary": "Short."}"#, SHIP_IT_REPLY]);
+        let transcript = ship_it_transcript();
+        let result = block_on(summarize(&ollama, "m", &transcript, meeting_date(), None)).unwrap();
+        assert_eq!(result.decisions, ["Ship on Friday"]);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let repair = requests[1]["messages"].as_array().unwrap();
+        assert_eq!(repair.len(), 4);
+        assert_eq!(repair[2]["role"], "assistant");
+        assert_eq!(repair[2]["content"], r#"{"summary": "Short."}"#);
+        assert!(repair[3]["content"]
+            .as_str()
+            .unwrap()
+            .contains("missing field `key_points`"));
+        assert_eq!(requests[1]["format"], summary_schema());
+    }
+
+    #[test]
+    fn test_falls_back_to_markdown_when_json_keeps_failing() {
+        let (ollama, requests) = scripted_ollama(&[
+            "not json",
+            "still not json",
+            "## Summary\nShort.\n\n## Decisions\n- Ship on Friday\n\n## Action Items\n- [ ] Ship it\n",
+        ]);
+        let transcript = ship_it_transcript();
+        let result = block_on(summarize(&ollama, "m", &transcript, meeting_date(), None)).unwrap();
+        assert_eq!(result.summary, "Short.");
+        assert_eq!(result.decisions, ["Ship on Friday"]);
+        assert_eq!(result.action_items, [ActionItem::new("Ship it")]);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2 + REPAIR_ATTEMPTS);
+        let fallback = requests.last().unwrap();
+        assert!(fallback.get("format").is_none());
+        assert!(fallback["messages"][1]["content"]
+            .as_str()
+            .unwrap()
+            .contains("## Action Items"));
+    }
+
+    #[test]
+    fn test_pack_overlaps_chunks() {
+        let lines: Vec<String> = (0..30).map(|i| format!("line {:03}\n", i)).collect();
+        let text = |range: std::ops::Range<usize>| lines[range].concat();
+        // 9 chars per line: 10 fit in 90, and the last line (within 90/10) is carried over
+        let chunks = pack(&lines, 90, true);
+        assert_eq!(chunks, [text(0..10), text(9..19), text(18..28), text(27..30)]);
+
+        let chunks = pack(&lines, 90, false);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), lines.concat());
+
+        // an oversized piece still goes through, on its own
+        let long = vec!["x".repeat(40), "y\n".into()];
+        assert_eq!(pack(&long, 25, true), [long[0].clone(), "y\n".to_string()]);
+        assert!(pack(&[], 25, true).is_empty());
+    }
+
+    #[test]
+    fn test_long_transcript_is_summarized_in_parts_and_combined() {
+        let prompts = std::sync::Arc::new(std::sync::Mutex::new(Vec::<String>::new()));
+        let log = prompts.clone();
+        let url = crate::ollama::stub::serve(move |path, body| {
+            if path == "/api/show" {
+                return serde_json::json!({ "model_info": { "llama.context_length": 2048 } })
+                    .to_string();
+            }
+            assert_eq!(body["options"]["num_ctx"], 2048);
+            let prompt = body["messages"][1]["content"].as_str().unwrap().to_string();
+            let reply = if let Some(rest) = prompt.strip_prefix("This is part ") {
+                let part = rest.split_whitespace().next().unwrap().to_string();
+                serde_json::json!({
+                    "summary": format!("Part {}.", part),
+                    "key_points": [format!("Point {}", part)],
+                    "decisions": [],
+                    "action_items": [{
+                        "text": format!("Task {}", part),
+                        "assignee": null,
+                        "due": null,
+                        "segment_ids": ["seg_5"],
+                    }],
+                    "open_questions": [],
+                })
+                .to_string()
+            } else {
+                serde_json::json!({
+                    "summary": "Whole meeting.",
+                    "key_points": ["All points"],
+                    "decisions": [],
+                    "action_items": [],
+                    "open_questions": [],
+                })
+                .to_string()
+            };
+            log.lock().unwrap().push(prompt);
+            streamed(&reply)
+        });
+
+        // (2048 - 1536) tokens * 3 = 1536 chars per request; this is about 6000
+        let sentences: Vec<String> = (0..60)
+            .map(|i| {
+                format!(
+                    "Sentence number {} of a very long meeting about many things.",
+                    i
+                )
+            })
+            .collect();
+        let lines: Vec<(&str, &str)> = sentences.iter().map(|s| ("Meeting", s.as_str())).collect();
+        let transcript = fixture::transcript(&lines);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let result = block_on(summarize(
+            &Ollama::new(url),
+            "test-model",
+            &transcript,
+            meeting_date(),
+            Some(tx),
+        ))
+        .unwrap();
+        assert_eq!(result.summary, "Whole meeting.");
+        assert!(result.action_items.is_empty());
+
+        let prompts = prompts.lock().unwrap();
+        let parts = prompts.len() - 1;
+        assert!(parts >= 4, "{} parts", parts);
+        for prompt in &prompts[..parts] {
+            let text_len: usize = prompt
+                .lines()
+                .filter(|l| l.starts_with("[seg_"))
+                .map(|l| l.len() + 1)
+                .sum();
+            assert!(text_len <= 1536);
+        }
+        // every segment is in some part, and consecutive parts overlap
+        for i in 0..60 {
+            let line = format!("Sentence number {} of", i);
+            assert!(prompts[..parts].iter().any(|p| p.contains(&line)));
+        }
+        let last_line_of_first = prompts[0]
+            .lines()
+            .rfind(|l| l.starts_with("[seg_"))
+            .unwrap();
+        assert!(prompts[1].contains(last_line_of_first));
+        let combine = &prompts[parts];
+        assert!(combine.contains("Key point: Point 1"));
+        assert!(combine.contains(&format!("Action item: Task {} (segments: seg_5)", parts)));
+
+        let progress: Vec<SummaryProgress> = rx.try_iter().collect();
+        assert_eq!(progress[0].part, 1);
+        assert_eq!(progress[0].parts, parts as u32);
+        assert!(progress.last().unwrap().combining);
+        assert!(progress.windows(2).all(|w| w[0].tokens < w[1].tokens));
+    }
+
+    #[test]
+    fn test_cancel_stops_waiting_for_the_model() {
+        let url = crate::ollama::stub::serve(|_, _| {
+            // a model still working through the prompt
+            std::thread::sleep(std::time::Duration::from_secs(5));
+            streamed("too late")
+        });
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            let _ = cancel_tx.send(true);
+        });
+
+        let started = std::time::Instant::now();
+        let ollama = Ollama::new(url);
+        let transcript = ship_it_transcript();
+        let work = summarize(&ollama, "test-model", &transcript, meeting_date(), None);
+        let err = block_on(until_cancelled(work, Some(cancel_rx))).unwrap_err();
+        assert_eq!(err, CANCELLED);
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+
+        // a dropped sender doesn't count as a cancel
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        drop(cancel_tx);
+        let done = block_on(until_cancelled(async { Ok(1) }, Some(cancel_rx)));
+        assert_eq!(done, Ok(1));
+    }
+
     /// E2E: run the summarizer against a real running Ollama instance.
     /// Skips gracefully if Ollama isn't reachable at localhost:11434.
     ///
@@ -384,7 +1272,7 @@ This was a productive meeting.
         }
         println!("--- Action Items ({}) ---", result.action_items.len());
         for a in &result.action_items {
-            println!("  - {}", a);
+            println!("  - {} ({:?}, due {:?})", a.text, a.assignee, a.due);
         }
 
         assert!(
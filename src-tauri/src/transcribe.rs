@@ -1,5 +1,8 @@
+use crate::artifacts;
+use crate::codec::{self, DecodedAudio};
 use crate::config::AppConfig;
-use hound::WavReader;
+use crate::resample;
+use crate::session::{SessionMetadata, TranscriptionInfo};
 use std::path::Path;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
@@ -29,31 +32,14 @@ pub struct TranscriptionProgress {
     pub overall_percent: f32,  // 0-100 total
 }
 
-/// Load audio from WAV file and convert to f32 mono at 16kHz (whisper's expected format)
+/// Load audio from a WAV, FLAC or Opus file and convert to f32 mono at 16kHz (whisper's expected format)
 fn load_audio_for_whisper(audio_path: &Path) -> Result<Vec<f32>, String> {
-    let reader = WavReader::open(audio_path)
-        .map_err(|e| format!("Failed to open audio file: {}", e))?;
-
-    let spec = reader.spec();
-    let sample_rate = spec.sample_rate;
-    let channels = spec.channels as usize;
-
-    // Read all samples
-    let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Float => reader
-            .into_samples::<f32>()
-            .filter_map(|s| s.ok())
-            .collect(),
-        hound::SampleFormat::Int => {
-            let bits = spec.bits_per_sample;
-            let max_val = (1 << (bits - 1)) as f32;
-            reader
-                .into_samples::<i32>()
-                .filter_map(|s| s.ok())
-                .map(|s| s as f32 / max_val)
-                .collect()
-        }
-    };
+    let DecodedAudio {
+        samples,
+        sample_rate,
+        channels,
+    } = codec::decode_file(audio_path)?;
+    let channels = channels as usize;
 
     // Convert stereo to mono if needed
     let mono_samples: Vec<f32> = if channels > 1 {
@@ -68,7 +54,7 @@ fn load_audio_for_whisper(audio_path: &Path) -> Result<Vec<f32>, String> {
     // Resample to 16kHz if needed (whisper expects 16kHz)
     let target_rate = 16000;
     let resampled = if sample_rate != target_rate {
-        resample(&mono_samples, sample_rate, target_rate)
+        resample::resample(&mono_samples, 1, sample_rate, target_rate)
     } else {
         mono_samples
     };
@@ -76,41 +62,19 @@ fn load_audio_for_whisper(audio_path: &Path) -> Result<Vec<f32>, String> {
     Ok(resampled)
 }
 
-/// Simple linear interpolation resampling
-fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate || samples.is_empty() {
-        return samples.to_vec();
-    }
-
-    let ratio = from_rate as f64 / to_rate as f64;
-    let new_len = ((samples.len() as f64) / ratio).ceil() as usize;
-    let mut result = Vec::with_capacity(new_len);
-
-    for i in 0..new_len {
-        let src_idx = i as f64 * ratio;
-        let idx_floor = src_idx.floor() as usize;
-        let idx_ceil = (idx_floor + 1).min(samples.len() - 1);
-        let frac = (src_idx - idx_floor as f64) as f32;
-
-        let sample = samples[idx_floor] * (1.0 - frac) + samples[idx_ceil] * frac;
-        result.push(sample);
-    }
-
-    result
-}
-
-/// Transcribe a recording directory (system.wav + mic.wav) with speaker labels
+/// Transcribe a recording directory (system + mic tracks) with speaker labels
 pub fn transcribe_recording_dir(dir: &Path) -> Result<TranscriptionResult, String> {
     transcribe_recording_dir_with_progress(dir, None)
 }
 
-/// Transcribe a recording directory with progress reporting
+/// Transcribe a recording directory with progress reporting. The result is saved
+/// as the directory's `transcript.json`.
 pub fn transcribe_recording_dir_with_progress(
     dir: &Path,
     progress_tx: Option<std::sync::mpsc::Sender<TranscriptionProgress>>,
 ) -> Result<TranscriptionResult, String> {
-    let system_file = dir.join("system.wav");
-    let mic_file = dir.join("mic.wav");
+    let system_file = codec::find_track(dir, "system");
+    let mic_file = codec::find_track(dir, "mic");
 
     let config = AppConfig::load();
     let model_path = config
@@ -127,13 +91,13 @@ pub fn transcribe_recording_dir_with_progress(
     .map_err(|e| format!("Failed to load whisper model: {}", e))?;
 
     // Transcribe both sources
-    let mut meeting_segments = if system_file.exists() {
+    let mut meeting_segments = if let Some(system_file) = system_file {
         transcribe_file_with_context_and_progress(&ctx, &system_file, "Meeting", "system", &progress_tx)?
     } else {
         vec![]
     };
 
-    let mut me_segments = if mic_file.exists() {
+    let mut me_segments = if let Some(mic_file) = mic_file {
         transcribe_file_with_context_and_progress(&ctx, &mic_file, "Me", "mic", &progress_tx)?
     } else {
         vec![]
@@ -144,11 +108,26 @@ pub fn transcribe_recording_dir_with_progress(
 
     println!("Transcription complete: {} segments total", segments.len());
 
-    Ok(TranscriptionResult {
+    let info = TranscriptionInfo {
+        model: model_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        segments: segments.len(),
+        duration_secs: duration,
+        transcribed_at: chrono::Local::now(),
+    };
+    if let Err(e) = SessionMetadata::update(dir, |session| session.transcription = Some(info)) {
+        eprintln!("Failed to record transcription in session metadata: {}", e);
+    }
+
+    let result = TranscriptionResult {
         segments,
         full_text,
         duration,
-    })
+    };
+    artifacts::save_transcript(dir, &result)?;
+    Ok(result)
 }
 
 /// Transcribe a single audio file with a shared whisper context
@@ -385,11 +364,18 @@ pub fn transcribe_audio(audio_path: &Path) -> Result<TranscriptionResult, String
     })
 }
 
+/// Transcript builders for tests across the crate
 #[cfg(test)]
-mod tests {
-    use super::*;
-
-    fn make_segment(id: &str, text: &str, start: f32, end: f32, speaker: &str) -> TranscriptSegment {
+pub(crate) mod fixture {
+    use super::{TranscriptSegment, TranscriptionResult};
+
+    pub fn make_segment(
+        id: &str,
+        text: &str,
+        start: f32,
+        end: f32,
+        speaker: &str,
+    ) -> TranscriptSegment {
         TranscriptSegment {
             id: id.to_string(),
             text: text.to_string(),
@@ -399,6 +385,31 @@ mod tests {
         }
     }
 
+    /// Transcript of `(speaker, text)` lines: `seg_0`, `seg_1`, ... each
+    /// 5 seconds long and starting 10 seconds after the previous one
+    pub fn transcript(lines: &[(&str, &str)]) -> TranscriptionResult {
+        let segments = lines
+            .iter()
+            .enumerate()
+            .map(|(i, (speaker, text))| {
+                let start = i as f32 * 10.0;
+                make_segment(&format!("seg_{}", i), text, start, start + 5.0, speaker)
+            })
+            .collect();
+        let texts: Vec<&str> = lines.iter().map(|(_, text)| *text).collect();
+        TranscriptionResult {
+            segments,
+            full_text: texts.join(" "),
+            duration: lines.len() as f32 * 10.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::make_segment;
+    use super::*;
+
     #[test]
     fn test_merge_segments_empty() {
         let mut meeting: Vec<TranscriptSegment> = vec![];
@@ -469,29 +480,6 @@ mod tests {
         assert_eq!(segments[1].speaker, "Me");
     }
 
-    #[test]
-    fn test_resample_identity() {
-        let input = vec![0.1, 0.2, 0.3, 0.4, 0.5];
-        let output = resample(&input, 48000, 48000);
-        assert_eq!(input, output);
-    }
-
-    #[test]
-    fn test_resample_empty() {
-        let input: Vec<f32> = vec![];
-        let output = resample(&input, 48000, 16000);
-        assert!(output.is_empty());
-    }
-
-    #[test]
-    fn test_resample_downsample_length() {
-        // 48000 → 16000 should produce ~1/3 the samples
-        let input: Vec<f32> = (0..48000).map(|i| (i as f32 / 100.0).sin()).collect();
-        let output = resample(&input, 48000, 16000);
-        // Allow ±1 sample for ceiling math
-        assert!(output.len() >= 15999 && output.len() <= 16001, "got {}", output.len());
-    }
-
     /// Helper: write a sine-wave WAV file to /tmp and return the path
     fn write_test_wav(
         path: &Path,
@@ -555,6 +543,20 @@ mod tests {
         assert_eq!(samples.len(), 4000); // exactly 0.25s * 16000
     }
 
+    #[test]
+    fn test_load_audio_decodes_flac() {
+        let wav = std::env::temp_dir().join("transcribe_test_flac.wav");
+        write_test_wav(&wav, 48000, 2, 0.5, 440.0).unwrap();
+        let flac = codec::transcode_wav(&wav, codec::StorageCodec::Flac).unwrap();
+
+        let samples = load_audio_for_whisper(&flac).unwrap();
+
+        // 0.5s stereo at 48kHz -> 8000 mono samples at 16kHz
+        assert!(samples.len() >= 7990 && samples.len() <= 8010, "got {}", samples.len());
+        let max_abs = samples.iter().map(|s| s.abs()).fold(0.0_f32, f32::max);
+        assert!(max_abs > 0.1, "audio too quiet: max_abs={}", max_abs);
+    }
+
     /// E2E: run the full transcription pipeline against a real recording.
     ///
     /// Selects the recording dir via `TEST_RECORDING_DIR` env var, or falls back to the
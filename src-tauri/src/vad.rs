@@ -0,0 +1,131 @@
+/// Length of one analysis frame
+const FRAME_SECS: f64 = 0.03;
+/// Frames looked at together when deciding whether someone is speaking (300 ms)
+const WINDOW_FRAMES: u32 = 10;
+/// Frames in the window that must be above the threshold, so a click or a door
+/// slam doesn't count as speech
+const MIN_VOICED_FRAMES: u32 = 5;
+/// Rumble and mains hum below this don't count towards the level
+const HIGH_PASS_HZ: f64 = 100.0;
+
+/// Energy-based voice activity detector for one source. Cheap enough to run on
+/// every block the writer thread drains.
+pub struct VoiceDetector {
+    threshold: f32,
+    sample_rate: u32,
+    frame_len: usize,
+    // one-pole high-pass over the downmixed signal
+    alpha: f32,
+    last_input: f32,
+    last_output: f32,
+    // current frame
+    energy: f64,
+    frame_samples: usize,
+    // one bit per frame, most recent in bit 0
+    history: u32,
+}
+
+impl VoiceDetector {
+    /// `threshold_db` is the RMS level in dBFS a frame needs to count as voiced
+    pub fn new(threshold_db: f32) -> Self {
+        let mut detector = Self {
+            threshold: 10f32.powf(threshold_db / 20.0),
+            sample_rate: 0,
+            frame_len: 0,
+            alpha: 0.0,
+            last_input: 0.0,
+            last_output: 0.0,
+            energy: 0.0,
+            frame_samples: 0,
+            history: 0,
+        };
+        detector.set_rate(48000);
+        detector
+    }
+
+    fn set_rate(&mut self, sample_rate: u32) {
+        let rc = 1.0 / (std::f64::consts::TAU * HIGH_PASS_HZ);
+        let dt = 1.0 / sample_rate.max(1) as f64;
+        self.sample_rate = sample_rate;
+        self.frame_len = ((sample_rate as f64 * FRAME_SECS) as usize).max(1);
+        self.alpha = (rc / (rc + dt)) as f32;
+        self.energy = 0.0;
+        self.frame_samples = 0;
+    }
+
+    /// Feed interleaved samples; true if speech was detected anywhere in them
+    pub fn push(&mut self, samples: &[f32], channels: u16, sample_rate: u32) -> bool {
+        // a reopened mic may come back at another rate
+        if sample_rate != self.sample_rate {
+            self.set_rate(sample_rate);
+        }
+        let channels = channels.max(1) as usize;
+        let mut detected = false;
+        for frame in samples.chunks_exact(channels) {
+            let input = frame.iter().sum::<f32>() / channels as f32;
+            let output = self.alpha * (self.last_output + input - self.last_input);
+            self.last_input = input;
+            self.last_output = output;
+
+            self.energy += (output as f64).powi(2);
+            self.frame_samples += 1;
+            if self.frame_samples == self.frame_len {
+                let rms = (self.energy / self.frame_len as f64).sqrt() as f32;
+                let voiced = rms > self.threshold;
+                self.history = ((self.history << 1) | voiced as u32) & ((1 << WINDOW_FRAMES) - 1);
+                detected |= self.history.count_ones() >= MIN_VOICED_FRAMES;
+                self.energy = 0.0;
+                self.frame_samples = 0;
+            }
+        }
+        detected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(secs: f64, freq: f32, db: f32) -> Vec<f32> {
+        let amplitude = 10f32.powf(db / 20.0) * std::f32::consts::SQRT_2;
+        (0..(secs * 48000.0) as usize)
+            .map(|i| (i as f32 * freq * std::f32::consts::TAU / 48000.0).sin() * amplitude)
+            .collect()
+    }
+
+    #[test]
+    fn test_sustained_speech_level_is_voice() {
+        let mut vad = VoiceDetector::new(-45.0);
+        assert!(!vad.push(&vec![0.0; 48000], 1, 48000));
+        assert!(vad.push(&tone(0.5, 300.0, -30.0), 1, 48000));
+    }
+
+    #[test]
+    fn test_clicks_and_quiet_rooms_are_not_voice() {
+        let mut vad = VoiceDetector::new(-45.0);
+        // a 30 ms knock every half second
+        for _ in 0..6 {
+            assert!(!vad.push(&tone(0.03, 1000.0, -10.0), 1, 48000));
+            assert!(!vad.push(&vec![0.0; 24000 - 1440], 1, 48000));
+        }
+        // steady background below the threshold
+        assert!(!vad.push(&tone(2.0, 500.0, -55.0), 1, 48000));
+        // DC offset from a cheap interface
+        assert!(!vad.push(&vec![0.1; 48000], 1, 48000));
+    }
+
+    #[test]
+    fn test_stereo_and_other_rates() {
+        let mut vad = VoiceDetector::new(-45.0);
+        let stereo: Vec<f32> = tone(0.5, 300.0, -30.0)
+            .into_iter()
+            .flat_map(|s| [s, s])
+            .collect();
+        assert!(vad.push(&stereo, 2, 48000));
+
+        let mut vad = VoiceDetector::new(-45.0);
+        // every third sample of 900 Hz at 48 kHz is 300 Hz at 16 kHz
+        let speech_16k: Vec<f32> = tone(1.5, 900.0, -30.0).into_iter().step_by(3).collect();
+        assert!(vad.push(&speech_16k, 1, 16000));
+    }
+}
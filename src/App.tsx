@@ -26,6 +26,19 @@ interface MixingProgress {
   percent: number;
 }
 
+interface MixingComplete {
+  output: RecordingOutput | null;
+  error: string | null;
+}
+
+interface DeviceInfo {
+  id: string;
+  name: string;
+  channels: number;
+  sample_rate: number;
+  is_default: boolean;
+}
+
 interface TranscriptionProgress {
   phase: string;
   file_percent: number;
@@ -53,10 +66,35 @@ interface TranscriptionResult {
   duration: number;
 }
 
+interface ActionItem {
+  text: string;
+  assignee: string | null;
+  /** YYYY-MM-DD */
+  due: string | null;
+  status: "open" | "done";
+  segment_ids: string[];
+}
+
 interface SummaryResult {
   summary: string;
   key_points: string[];
-  action_items: string[];
+  decisions: string[];
+  action_items: ActionItem[];
+  open_questions: string[];
+}
+
+interface SummaryProgress {
+  text: string;
+  tokens: number;
+  part: number;
+  parts: number;
+  combining: boolean;
+}
+
+/** Which request of a long transcript's summary is running, if there are several */
+function summaryStage(progress: SummaryProgress): string {
+  if (progress.combining) return " combining parts";
+  return progress.parts > 1 ? ` part ${progress.part}/${progress.parts}` : "";
 }
 
 type SetupStep = "welcome" | "whisper" | "llm" | "complete";
@@ -279,8 +317,60 @@ function formatDuration(seconds: number): string {
 
 interface RecordingStats {
   duration_secs: number;
+  is_paused: boolean;
   system_samples_written: number;
   mic_samples_written: number;
+  overruns: number;
+  is_armed: boolean;
+}
+
+// voice-activated recording, see AutoRecordSettings
+type AutoRecordEvent = { kind: "started" } | { kind: "silence_timeout"; silent_secs: number };
+
+async function notify(body: string) {
+  try {
+    const { sendNotification, isPermissionGranted, requestPermission } = await import(
+      "@tauri-apps/plugin-notification"
+    );
+    let permitted = await isPermissionGranted();
+    if (!permitted) {
+      const permission = await requestPermission();
+      permitted = permission === "granted";
+    }
+    if (permitted) {
+      sendNotification({ title: "Meeting Recorder", body });
+    }
+  } catch (e) {
+    console.error("Failed to send notification:", e);
+  }
+}
+
+interface SourceLevel {
+  rms_db: number;
+  peak_db: number;
+  silent_secs: number;
+}
+
+interface AudioLevels {
+  system: SourceLevel;
+  mic: SourceLevel;
+}
+
+// meters span -60 dBFS (empty) to 0 dBFS (full)
+function meterPercent(db: number): number {
+  return Math.min(100, Math.max(0, ((db + 60) / 60) * 100));
+}
+
+function LevelMeter({ label, level }: { label: string; level: SourceLevel }) {
+  return (
+    <div className="level-meter">
+      <span className="level-label">{label}</span>
+      <div className="level-bar">
+        <div className="level-fill" style={{ width: `${meterPercent(level.rms_db)}%` }} />
+        <div className="level-peak" style={{ left: `${meterPercent(level.peak_db)}%` }} />
+      </div>
+    </div>
+  );
 }
 
 // milestone thresholds in seconds
@@ -292,14 +382,18 @@ const MILESTONES = [
 
 function RecorderUI() {
   const [isRecording, setIsRecording] = useState(false);
+  const [isPaused, setIsPaused] = useState(false);
   const [status, setStatus] = useState("Ready");
   const [lastRecording, setLastRecording] = useState<RecordingOutput | null>(null);
+  const [mixing, setMixing] = useState(false);
   const [transcribing, setTranscribing] = useState(false);
   const [transcription, setTranscription] = useState<TranscriptionResult | null>(null);
   const [summarizing, setSummarizing] = useState(false);
   const [summary, setSummary] = useState<SummaryResult | null>(null);
+  const [summaryProgress, setSummaryProgress] = useState<SummaryProgress | null>(null);
   const [elapsedTime, setElapsedTime] = useState(0);
   const [warning, setWarning] = useState<string | null>(null);
+  const [levels, setLevels] = useState<AudioLevels | null>(null);
   const [milestonesReached, setMilestonesReached] = useState<Set<number>>(new Set());
   const [processingProgress, setProcessingProgress] = useState<{
     phase: "idle" | "mixing" | "transcribing";
@@ -307,10 +401,27 @@ function RecorderUI() {
     label: string;
   }>({ phase: "idle", percent: 0, label: "" });
 
+  const [inputDevices, setInputDevices] = useState<DeviceInfo[]>([]);
+  const [inputDevice, setInputDevice] = useState<string>("");
+
   useEffect(() => {
     invoke<boolean>("is_recording").then(setIsRecording);
+    invoke<DeviceInfo[]>("list_input_devices").then(setInputDevices).catch(console.error);
+    invoke<{ input_device: string | null }>("get_config").then((config) =>
+      setInputDevice(config.input_device ?? "")
+    );
   }, []);
 
+  async function selectInputDevice(id: string) {
+    setInputDevice(id);
+    try {
+      // empty selection = follow the OS default
+      await invoke("set_input_device", { device: id || null });
+    } catch (e) {
+      setStatus(`Error: ${e}`);
+    }
+  }
+
   // listen for mixing and transcription progress events
   useEffect(() => {
     const unlistenMixing = listen<MixingProgress>("mixing-progress", (event) => {
@@ -333,8 +444,59 @@ function RecorderUI() {
       });
     });
 
+    // mixdown runs in the background after stop_recording returns
+    const unlistenMixingComplete = listen<MixingComplete>("mixing-complete", (event) => {
+      setMixing(false);
+      setProcessingProgress({ phase: "idle", percent: 0, label: "" });
+      if (event.payload.output) {
+        setLastRecording(event.payload.output);
+        setStatus("Saved");
+      } else {
+        setStatus(`Mixing error: ${event.payload.error}`);
+      }
+    });
+
+    // output of the summary being generated, one event per token
+    const unlistenSummary = listen<SummaryProgress>("summary-progress", (event) => {
+      setSummaryProgress(event.payload);
+    });
+
+    // capture problems mid-session (e.g. mic unplugged and reconnected)
+    const unlistenWarning = listen<{ message: string }>("recording-warning", (event) => {
+      setWarning(event.payload.message);
+      setTimeout(() => setWarning(null), 10000);
+    });
+
+    // throttled to ~10/s by the backend
+    const unlistenLevels = listen<AudioLevels>("audio-levels", (event) => {
+      setLevels(event.payload);
+    });
+
+    const unlistenAutoRecord = listen<AutoRecordEvent>("auto-record", (event) => {
+      if (event.payload.kind === "started") {
+        setStatus("Recording");
+        notify("Voice detected, recording started");
+      }
+    });
+
+    // the backend stopped a voice-activated session after a stretch of silence
+    const unlistenAutoStopped = listen<RecordingOutput>("recording-auto-stopped", (event) => {
+      setIsRecording(false);
+      setIsPaused(false);
+      setMixing(true);
+      setStatus("Mixing...");
+      setLastRecording(event.payload);
+      notify("Nobody has spoken for a while, recording stopped and saved");
+    });
+
     return () => {
+      unlistenAutoRecord.then((fn) => fn());
+      unlistenAutoStopped.then((fn) => fn());
+      unlistenLevels.then((fn) => fn());
       unlistenMixing.then((fn) => fn());
+      unlistenMixingComplete.then((fn) => fn());
+      unlistenSummary.then((fn) => fn());
+      unlistenWarning.then((fn) => fn());
       unlistenTranscription.then((fn) => fn());
     };
   }, []);
@@ -344,6 +506,7 @@ function RecorderUI() {
     if (!isRecording) {
       setElapsedTime(0);
       setMilestonesReached(new Set());
+      setLevels(null);
       return;
     }
 
@@ -352,6 +515,9 @@ function RecorderUI() {
         const stats = await invoke<RecordingStats | null>("get_recording_stats");
         if (stats) {
           setElapsedTime(stats.duration_secs);
+          // pause may also be toggled from the tray menu
+          setIsPaused(stats.is_paused);
+          setStatus(stats.is_armed ? "Waiting for voice..." : stats.is_paused ? "Paused" : "Recording");
 
           // check milestones
           for (const milestone of MILESTONES) {
@@ -359,25 +525,7 @@ function RecorderUI() {
               setMilestonesReached((prev) => new Set([...prev, milestone.secs]));
               setWarning(`Recording has been running for ${milestone.label}`);
 
-              // send system notification
-              try {
-                const { sendNotification, isPermissionGranted, requestPermission } = await import(
-                  "@tauri-apps/plugin-notification"
-                );
-                let permitted = await isPermissionGranted();
-                if (!permitted) {
-                  const permission = await requestPermission();
-                  permitted = permission === "granted";
-                }
-                if (permitted) {
-                  sendNotification({
-                    title: "Meeting Recorder",
-                    body: `Recording has been running for ${milestone.label}`,
-                  });
-                }
-              } catch (e) {
-                console.error("Failed to send notification:", e);
-              }
+              await notify(`Recording has been running for ${milestone.label}`);
 
               // auto-dismiss warning after 10 seconds
               setTimeout(() => setWarning(null), 10000);
@@ -397,7 +545,8 @@ function RecorderUI() {
       setStatus("Starting...");
       await invoke("start_recording");
       setIsRecording(true);
-      setStatus("Recording");
+      const stats = await invoke<RecordingStats | null>("get_recording_stats");
+      setStatus(stats?.is_armed ? "Waiting for voice..." : "Recording");
       setLastRecording(null);
       setTranscription(null);
       setSummary(null);
@@ -406,14 +555,31 @@ function RecorderUI() {
     }
   }
 
+  async function togglePause() {
+    try {
+      if (isPaused) {
+        await invoke("resume_recording");
+        setIsPaused(false);
+        setStatus("Recording");
+      } else {
+        await invoke("pause_recording");
+        setIsPaused(true);
+        setStatus("Paused");
+      }
+    } catch (e) {
+      setStatus(`Error: ${e}`);
+    }
+  }
+
   async function stopRecording() {
     try {
       setStatus("Stopping...");
       const output = await invoke<RecordingOutput>("stop_recording");
       setIsRecording(false);
-      setStatus("Saved");
+      setIsPaused(false);
+      setMixing(true);
+      setStatus("Mixing...");
       setLastRecording(output);
-      setProcessingProgress({ phase: "idle", percent: 0, label: "" });
     } catch (e) {
       setStatus(`Error: ${e}`);
       setProcessingProgress({ phase: "idle", percent: 0, label: "" });
@@ -443,16 +609,30 @@ function RecorderUI() {
     if (!transcription) return;
     try {
       setSummarizing(true);
+      // progress of a summary another window ran is not ours to show
+      setSummaryProgress(null);
       setStatus("Summarizing...");
       const result = await invoke<SummaryResult>("summarize_transcript", {
         transcript: transcription,
+        recordingDir: lastRecording?.directory ?? null,
       });
       setSummary(result);
       setStatus("Summarized");
     } catch (e) {
-      setStatus(`Summarization error: ${e}`);
+      setStatus(
+        e === "Summarization cancelled" ? "Summarization cancelled" : `Summarization error: ${e}`,
+      );
     } finally {
       setSummarizing(false);
+      setSummaryProgress(null);
+    }
+  }
+
+  async function cancelSummarization() {
+    try {
+      await invoke("cancel_summarization");
+    } catch (e) {
+      setStatus(`Error: ${e}`);
     }
   }
 
@@ -487,6 +667,13 @@ function RecorderUI() {
         )}
       </div>
 
+      {isRecording && levels && (
+        <div className="level-meters">
+          <LevelMeter label="System" level={levels.system} />
+          <LevelMeter label="Mic" level={levels.mic} />
+        </div>
+      )}
+
       {processingProgress.phase !== "idle" && (
         <div className="progress-container">
           <div className="progress-bar">
@@ -498,15 +685,41 @@ function RecorderUI() {
         </div>
       )}
 
+      {!isRecording && inputDevices.length > 0 && (
+        <div className="device-select">
+          <label htmlFor="input-device">Microphone</label>
+          <select
+            id="input-device"
+            value={inputDevice}
+            onChange={(e) => selectInputDevice(e.target.value)}
+          >
+            <option value="">System default</option>
+            {inputDevices.map((device) => (
+              <option key={device.id} value={device.id}>
+                {device.name} ({device.channels} ch, {device.sample_rate / 1000} kHz)
+              </option>
+            ))}
+            {inputDevice && !inputDevices.some((d) => d.id === inputDevice) && (
+              <option value={inputDevice}>{inputDevice} (disconnected)</option>
+            )}
+          </select>
+        </div>
+      )}
+
       <div className="controls">
         {!isRecording ? (
           <button onClick={startRecording} className="start-btn">
             Start Recording
           </button>
         ) : (
-          <button onClick={stopRecording} className="stop-btn">
-            Stop Recording
-          </button>
+          <>
+            <button onClick={togglePause} className="secondary-btn">
+              {isPaused ? "Resume" : "Pause"}
+            </button>
+            <button onClick={stopRecording} className="stop-btn">
+              Stop Recording
+            </button>
+          </>
         )}
       </div>
 
@@ -516,7 +729,7 @@ function RecorderUI() {
           <code>{lastRecording.directory}</code>
           <button
             onClick={transcribeRecording}
-            disabled={transcribing}
+            disabled={transcribing || mixing}
             className="primary-btn"
             style={{ marginTop: "12px" }}
           >
@@ -547,13 +760,27 @@ function RecorderUI() {
                 disabled={summarizing}
                 className="primary-btn"
               >
-                {summarizing ? "Summarizing..." : "Generate Summary"}
-              </button>
-              <button onClick={openEditor} className="secondary-btn">
-                Open Editor
+                {!summarizing
+                  ? "Generate Summary"
+                  : summaryProgress
+                    ? `Summarizing${summaryStage(summaryProgress)}... (${summaryProgress.tokens} tokens)`
+                    : "Summarizing..."}
               </button>
+              {summarizing ? (
+                <button onClick={cancelSummarization} className="secondary-btn">
+                  Cancel
+                </button>
+              ) : (
+                <button onClick={openEditor} className="secondary-btn">
+                  Open Editor
+                </button>
+              )}
             </div>
           )}
+
+          {summarizing && summaryProgress && (
+            <pre className="summary-preview">{summaryProgress.text}</pre>
+          )}
         </div>
       )}
 
@@ -573,12 +800,41 @@ function RecorderUI() {
             </>
           )}
 
+          {summary.decisions.length > 0 && (
+            <>
+              <h4>Decisions</h4>
+              <ul className="key-points">
+                {summary.decisions.map((decision, i) => (
+                  <li key={i}>{decision}</li>
+                ))}
+              </ul>
+            </>
+          )}
+
           {summary.action_items.length > 0 && (
             <>
               <h4>Action Items</h4>
               <ul className="action-items">
                 {summary.action_items.map((item, i) => (
-                  <li key={i}>{item}</li>
+                  <li key={i} className={item.status}>
+                    {item.text}
+                    {(item.assignee || item.due) && (
+                      <span className="action-item-meta">
+                        {[item.assignee, item.due && `due ${item.due}`].filter(Boolean).join(", ")}
+                      </span>
+                    )}
+                  </li>
+                ))}
+              </ul>
+            </>
+          )}
+
+          {summary.open_questions.length > 0 && (
+            <>
+              <h4>Open Questions</h4>
+              <ul className="key-points">
+                {summary.open_questions.map((question, i) => (
+                  <li key={i}>{question}</li>
                 ))}
               </ul>
             </>
@@ -17,10 +17,35 @@ interface TranscriptionResult {
   duration: number;
 }
 
+interface ActionItem {
+  text: string;
+  assignee: string | null;
+  /** YYYY-MM-DD */
+  due: string | null;
+  status: "open" | "done";
+  segment_ids: string[];
+}
+
 interface SummaryResult {
   summary: string;
   key_points: string[];
-  action_items: string[];
+  decisions: string[];
+  action_items: ActionItem[];
+  open_questions: string[];
+}
+
+interface SummaryProgress {
+  text: string;
+  tokens: number;
+  part: number;
+  parts: number;
+  combining: boolean;
+}
+
+/** Which request of a long transcript's summary is running, if there are several */
+function summaryStage(progress: SummaryProgress): string {
+  if (progress.combining) return " combining parts";
+  return progress.parts > 1 ? ` part ${progress.part}/${progress.parts}` : "";
 }
 
 interface EditorPayload {
@@ -45,7 +70,7 @@ interface SegmentRowProps {
 
 function SegmentRow({ segment, speakers, onTextChange, onSpeakerChange, onDelete }: SegmentRowProps) {
   return (
-    <div className="segment-row">
+    <div className="segment-row" id={`segment-${segment.id}`}>
       <div className="segment-meta">
         <select
           className="speaker-select"
@@ -73,14 +98,87 @@ function SegmentRow({ segment, speakers, onTextChange, onSpeakerChange, onDelete
   );
 }
 
+interface ActionItemRowProps {
+  item: ActionItem;
+  speakers: string[];
+  segments: TranscriptSegment[];
+  onChange: (item: ActionItem) => void;
+  onDelete: () => void;
+}
+
+function ActionItemRow({ item, speakers, segments, onChange, onDelete }: ActionItemRowProps) {
+  // keep an assignee who is no longer among the speakers selectable
+  const assignees = item.assignee && !speakers.includes(item.assignee)
+    ? [...speakers, item.assignee]
+    : speakers;
+  const sources = segments.filter((seg) => item.segment_ids.includes(seg.id));
+
+  const jumpTo = (id: string) => {
+    document.getElementById(`segment-${id}`)?.scrollIntoView({ behavior: "smooth", block: "center" });
+  };
+
+  return (
+    <li className={`action-item ${item.status}`}>
+      <div className="action-item-main">
+        <input
+          type="checkbox"
+          checked={item.status === "done"}
+          onChange={(e) => onChange({ ...item, status: e.target.checked ? "done" : "open" })}
+          title="Done"
+        />
+        <input
+          className="action-item-text"
+          value={item.text}
+          onChange={(e) => onChange({ ...item, text: e.target.value })}
+        />
+        <button className="delete-btn" onClick={onDelete} title="Delete action item">
+          ×
+        </button>
+      </div>
+      <div className="action-item-meta">
+        <select
+          className="speaker-select"
+          value={item.assignee ?? ""}
+          onChange={(e) => onChange({ ...item, assignee: e.target.value || null })}
+        >
+          <option value="">Unassigned</option>
+          {assignees.map((s) => (
+            <option key={s} value={s}>
+              {s}
+            </option>
+          ))}
+        </select>
+        <input
+          type="date"
+          className="action-item-due"
+          value={item.due ?? ""}
+          onChange={(e) => onChange({ ...item, due: e.target.value || null })}
+        />
+        {sources.map((seg) => (
+          <button
+            key={seg.id}
+            className="segment-link"
+            onClick={() => jumpTo(seg.id)}
+            title="Show in transcript"
+          >
+            {formatTime(seg.start_time)}
+          </button>
+        ))}
+      </div>
+    </li>
+  );
+}
+
 export default function EditorWindow() {
   const [recordingDir, setRecordingDir] = useState<string>("");
   const [segments, setSegments] = useState<TranscriptSegment[]>([]);
   const [duration, setDuration] = useState<number>(0);
   const [summary, setSummary] = useState<SummaryResult | null>(null);
   const [summarizing, setSummarizing] = useState(false);
+  const [summaryProgress, setSummaryProgress] = useState<SummaryProgress | null>(null);
   const [saving, setSaving] = useState(false);
   const [hasChanges, setHasChanges] = useState(false);
+  const [actionItemsChanged, setActionItemsChanged] = useState(false);
   const [status, setStatus] = useState("Ready");
 
   // unique speakers from segments
@@ -94,11 +192,17 @@ export default function EditorWindow() {
       setDuration(transcript.duration);
       setSummary(summary);
       setHasChanges(false);
+      setActionItemsChanged(false);
       setStatus("Loaded");
     });
 
+    const unlistenSummary = listen<SummaryProgress>("summary-progress", (event) => {
+      setSummaryProgress(event.payload);
+    });
+
     return () => {
       unlisten.then((fn) => fn());
+      unlistenSummary.then((fn) => fn());
     };
   }, []);
 
@@ -121,6 +225,26 @@ export default function EditorWindow() {
     setHasChanges(true);
   }, []);
 
+  const setActionItems = (update: (items: ActionItem[]) => ActionItem[]) => {
+    setSummary((prev) => (prev ? { ...prev, action_items: update(prev.action_items) } : prev));
+    setActionItemsChanged(true);
+  };
+
+  const handleActionItemChange = (index: number, item: ActionItem) => {
+    setActionItems((items) => items.map((existing, i) => (i === index ? item : existing)));
+  };
+
+  const handleActionItemDelete = (index: number) => {
+    setActionItems((items) => items.filter((_, i) => i !== index));
+  };
+
+  const handleActionItemAdd = () => {
+    setActionItems((items) => [
+      ...items,
+      { text: "", assignee: null, due: null, status: "open", segment_ids: [] },
+    ]);
+  };
+
   const buildTranscript = useCallback((): TranscriptionResult => {
     const fullText = segments.map((s) => `[${s.speaker}] ${s.text}`).join("\n");
     return {
@@ -135,11 +259,21 @@ export default function EditorWindow() {
     try {
       setSaving(true);
       setStatus("Saving...");
-      await invoke("save_edited_transcript", {
-        recordingDir,
-        transcript: buildTranscript(),
-      });
-      setHasChanges(false);
+      if (hasChanges) {
+        await invoke("save_edited_transcript", {
+          recordingDir,
+          transcript: buildTranscript(),
+        });
+        setHasChanges(false);
+      }
+      if (actionItemsChanged && summary) {
+        const saved = await invoke<SummaryResult>("save_action_items", {
+          recordingDir,
+          actionItems: summary.action_items,
+        });
+        setSummary(saved);
+        setActionItemsChanged(false);
+      }
       setStatus("Saved");
     } catch (e) {
       setStatus(`Save error: ${e}`);
@@ -151,16 +285,31 @@ export default function EditorWindow() {
   const handleRegenerate = async () => {
     try {
       setSummarizing(true);
+      // progress of a summary another window ran is not ours to show
+      setSummaryProgress(null);
       setStatus("Regenerating summary...");
       const result = await invoke<SummaryResult>("summarize_transcript", {
         transcript: buildTranscript(),
+        recordingDir: recordingDir || null,
       });
       setSummary(result);
+      setActionItemsChanged(false);
       setStatus("Summary updated");
     } catch (e) {
-      setStatus(`Summarization error: ${e}`);
+      setStatus(
+        e === "Summarization cancelled" ? "Summarization cancelled" : `Summarization error: ${e}`,
+      );
     } finally {
       setSummarizing(false);
+      setSummaryProgress(null);
+    }
+  };
+
+  const handleCancelSummary = async () => {
+    try {
+      await invoke("cancel_summarization");
+    } catch (e) {
+      setStatus(`Error: ${e}`);
     }
   };
 
@@ -187,9 +336,9 @@ export default function EditorWindow() {
           <button
             className="save-btn"
             onClick={handleSave}
-            disabled={saving || !hasChanges}
+            disabled={saving || !(hasChanges || actionItemsChanged)}
           >
-            {saving ? "Saving..." : hasChanges ? "Save" : "Saved"}
+            {saving ? "Saving..." : hasChanges || actionItemsChanged ? "Save" : "Saved"}
           </button>
         </div>
       </header>
@@ -214,16 +363,26 @@ export default function EditorWindow() {
         <div className="summary-panel">
           <div className="summary-header">
             <h2>Summary</h2>
-            <button
-              className="regenerate-btn"
-              onClick={handleRegenerate}
-              disabled={summarizing}
-            >
-              {summarizing ? "Generating..." : "Regenerate"}
-            </button>
+            {summarizing ? (
+              <button className="regenerate-btn" onClick={handleCancelSummary}>
+                Cancel
+              </button>
+            ) : (
+              <button className="regenerate-btn" onClick={handleRegenerate}>
+                Regenerate
+              </button>
+            )}
           </div>
 
-          {summary ? (
+          {summarizing ? (
+            <div className="summary-content">
+              <p className="summary-progress">
+                Generating{summaryProgress && summaryStage(summaryProgress)}...
+                {summaryProgress && ` ${summaryProgress.tokens} tokens`}
+              </p>
+              {summaryProgress && <pre className="summary-preview">{summaryProgress.text}</pre>}
+            </div>
+          ) : summary ? (
             <div className="summary-content">
               <section>
                 <h3>Overview</h3>
@@ -241,12 +400,42 @@ export default function EditorWindow() {
                 </section>
               )}
 
-              {summary.action_items.length > 0 && (
+              {summary.decisions.length > 0 && (
+                <section>
+                  <h3>Decisions</h3>
+                  <ul>
+                    {summary.decisions.map((decision, i) => (
+                      <li key={i}>{decision}</li>
+                    ))}
+                  </ul>
+                </section>
+              )}
+
+              <section>
+                <h3>Action Items</h3>
+                <ul className="action-items">
+                  {summary.action_items.map((item, i) => (
+                    <ActionItemRow
+                      key={i}
+                      item={item}
+                      speakers={speakers}
+                      segments={segments}
+                      onChange={(updated) => handleActionItemChange(i, updated)}
+                      onDelete={() => handleActionItemDelete(i)}
+                    />
+                  ))}
+                </ul>
+                <button className="add-action-item" onClick={handleActionItemAdd}>
+                  + Add action item
+                </button>
+              </section>
+
+              {summary.open_questions.length > 0 && (
                 <section>
-                  <h3>Action Items</h3>
-                  <ul className="action-items">
-                    {summary.action_items.map((item, i) => (
-                      <li key={i}>{item}</li>
+                  <h3>Open Questions</h3>
+                  <ul>
+                    {summary.open_questions.map((question, i) => (
+                      <li key={i}>{question}</li>
                     ))}
                   </ul>
                 </section>